@@ -0,0 +1,8 @@
+ALTER TABLE tasks
+DROP COLUMN task_type,
+DROP COLUMN category,
+DROP COLUMN icon_url,
+DROP COLUMN display_order,
+DROP COLUMN metadata;
+
+DROP TYPE IF EXISTS task_type;
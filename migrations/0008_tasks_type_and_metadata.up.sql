@@ -0,0 +1,18 @@
+CREATE TYPE task_type AS ENUM (
+    'follow',
+    'retweet',
+    'like',
+    'reply',
+    'quote_tweet',
+    'join_discord',
+    'visit_link',
+    'quiz',
+    'custom'
+);
+
+ALTER TABLE tasks
+ADD COLUMN task_type task_type NOT NULL DEFAULT 'custom',
+ADD COLUMN category VARCHAR(64),
+ADD COLUMN icon_url VARCHAR(255),
+ADD COLUMN display_order INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN metadata JSONB NOT NULL DEFAULT '{}';
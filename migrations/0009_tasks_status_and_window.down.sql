@@ -0,0 +1,7 @@
+ALTER TABLE tasks
+DROP CONSTRAINT IF EXISTS tasks_window_check,
+DROP COLUMN status,
+DROP COLUMN starts_at,
+DROP COLUMN ends_at;
+
+DROP TYPE IF EXISTS task_status;
@@ -0,0 +1,7 @@
+CREATE TYPE task_status AS ENUM ('draft', 'scheduled', 'active', 'paused', 'archived');
+
+ALTER TABLE tasks
+ADD COLUMN status task_status NOT NULL DEFAULT 'active',
+ADD COLUMN starts_at TIMESTAMP WITH TIME ZONE,
+ADD COLUMN ends_at TIMESTAMP WITH TIME ZONE,
+ADD CONSTRAINT tasks_window_check CHECK (starts_at IS NULL OR ends_at IS NULL OR ends_at > starts_at);
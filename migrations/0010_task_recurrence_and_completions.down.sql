@@ -0,0 +1,7 @@
+DROP TABLE IF EXISTS task_completions;
+
+ALTER TABLE tasks
+DROP COLUMN recurrence,
+DROP COLUMN recurrence_schedule;
+
+DROP TYPE IF EXISTS task_recurrence;
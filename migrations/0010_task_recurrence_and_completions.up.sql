@@ -0,0 +1,24 @@
+CREATE TYPE task_recurrence AS ENUM ('none', 'daily', 'weekly', 'cron');
+
+ALTER TABLE tasks
+ADD COLUMN recurrence task_recurrence NOT NULL DEFAULT 'none',
+ADD COLUMN recurrence_schedule VARCHAR(120);
+
+CREATE TABLE IF NOT EXISTS task_completions (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    period_key VARCHAR(32) NOT NULL,
+    points_awarded INTEGER NOT NULL,
+    completed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, task_id, period_key)
+);
+
+-- Everything finished so far was a one-off completion. The points in effect back then
+-- are unknown, so the current task points are the best we have.
+INSERT INTO task_completions (user_id, task_id, period_key, points_awarded)
+SELECT users.id, tasks.id, 'once', tasks.points
+FROM users
+CROSS JOIN LATERAL unnest(users.finished_tasks) AS finished(task_id)
+JOIN tasks ON tasks.id = finished.task_id
+ON CONFLICT DO NOTHING;
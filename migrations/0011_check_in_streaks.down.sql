@@ -0,0 +1,7 @@
+ALTER TABLE users
+DROP COLUMN current_streak,
+DROP COLUMN longest_streak,
+DROP COLUMN last_check_in,
+DROP COLUMN streak_freezes;
+
+-- Postgres can't drop a single enum value, 'check_in' stays on task_type.
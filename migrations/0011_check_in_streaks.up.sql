@@ -0,0 +1,7 @@
+ALTER TYPE task_type ADD VALUE IF NOT EXISTS 'check_in';
+
+ALTER TABLE users
+ADD COLUMN current_streak INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN longest_streak INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN last_check_in DATE,
+ADD COLUMN streak_freezes INTEGER NOT NULL DEFAULT 0;
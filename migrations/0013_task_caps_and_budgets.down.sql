@@ -0,0 +1,16 @@
+ALTER TABLE tasks
+DROP COLUMN max_completions,
+DROP COLUMN points_budget,
+DROP COLUMN completions_count,
+DROP COLUMN points_spent;
+
+-- Enum values can't be dropped, so the type is rebuilt without 'exhausted'.
+ALTER TYPE task_status RENAME TO task_status_old;
+CREATE TYPE task_status AS ENUM ('draft', 'scheduled', 'active', 'paused', 'archived');
+
+ALTER TABLE tasks ALTER COLUMN status DROP DEFAULT;
+ALTER TABLE tasks ALTER COLUMN status TYPE task_status
+USING (CASE WHEN status::text = 'exhausted' THEN 'archived' ELSE status::text END)::task_status;
+ALTER TABLE tasks ALTER COLUMN status SET DEFAULT 'active';
+
+DROP TYPE task_status_old;
@@ -0,0 +1,16 @@
+ALTER TYPE task_status ADD VALUE IF NOT EXISTS 'exhausted';
+
+ALTER TABLE tasks
+ADD COLUMN max_completions INTEGER CHECK (max_completions IS NULL OR max_completions >= 0),
+ADD COLUMN points_budget INTEGER CHECK (points_budget IS NULL OR points_budget >= 0),
+ADD COLUMN completions_count INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN points_spent INTEGER NOT NULL DEFAULT 0;
+
+UPDATE tasks
+SET completions_count = totals.completions_count, points_spent = totals.points_spent
+FROM (
+    SELECT task_id, COUNT(*) AS completions_count, SUM(points_awarded) AS points_spent
+    FROM task_completions
+    GROUP BY task_id
+) AS totals
+WHERE totals.task_id = tasks.id;
@@ -0,0 +1,3 @@
+ALTER TABLE task_completions DROP COLUMN IF EXISTS verification_id;
+
+DROP TABLE IF EXISTS task_verifications;
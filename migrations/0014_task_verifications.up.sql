@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS task_verifications (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    verifier VARCHAR(32) NOT NULL,
+    verified BOOLEAN NOT NULL,
+    reason VARCHAR(255),
+    proof JSONB NOT NULL DEFAULT '{}',
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS task_verifications_user_task_idx ON task_verifications (user_id, task_id);
+
+ALTER TABLE task_completions
+ADD COLUMN verification_id INTEGER REFERENCES task_verifications(id) ON DELETE SET NULL;
@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS twitter_oauth_states;
+
+ALTER TABLE users
+DROP COLUMN twitter_user_id,
+DROP COLUMN twitter_handle,
+DROP COLUMN twitter_linked_at;
@@ -0,0 +1,11 @@
+ALTER TABLE users
+ADD COLUMN twitter_user_id VARCHAR(32) UNIQUE,
+ADD COLUMN twitter_handle VARCHAR(255),
+ADD COLUMN twitter_linked_at TIMESTAMP WITH TIME ZONE;
+
+CREATE TABLE IF NOT EXISTS twitter_oauth_states (
+    state VARCHAR(64) PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_verifier VARCHAR(128) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS task_submissions;
+
+DROP TYPE IF EXISTS submission_status;
@@ -0,0 +1,23 @@
+CREATE TYPE submission_status AS ENUM ('pending', 'approved', 'rejected');
+
+CREATE TABLE IF NOT EXISTS task_submissions (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    period_key VARCHAR(32) NOT NULL,
+    status submission_status NOT NULL DEFAULT 'pending',
+    url VARCHAR(2048),
+    text TEXT,
+    screenshot_url VARCHAR(2048),
+    reason VARCHAR(255),
+    reviewed_by INTEGER REFERENCES api_keys(id) ON DELETE SET NULL,
+    reviewed_at TIMESTAMP WITH TIME ZONE,
+    completion_id INTEGER REFERENCES task_completions(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- One open submission per user, task and period.
+CREATE UNIQUE INDEX IF NOT EXISTS task_submissions_pending_idx
+ON task_submissions (user_id, task_id, period_key) WHERE status = 'pending';
+
+CREATE INDEX IF NOT EXISTS task_submissions_status_idx ON task_submissions (status, created_at);
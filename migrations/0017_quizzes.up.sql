@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS quiz_questions (
+    id SERIAL PRIMARY KEY,
+    task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    question TEXT NOT NULL,
+    options TEXT[] NOT NULL,
+    correct_option INTEGER NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0,
+    CHECK (correct_option >= 0 AND correct_option < cardinality(options))
+);
+
+CREATE INDEX IF NOT EXISTS quiz_questions_task_idx ON quiz_questions (task_id, position);
+
+CREATE TABLE IF NOT EXISTS quiz_attempts (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    period_key VARCHAR(32) NOT NULL,
+    correct INTEGER NOT NULL,
+    total INTEGER NOT NULL,
+    answers JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS quiz_attempts_user_task_idx ON quiz_attempts (user_id, task_id, period_key);
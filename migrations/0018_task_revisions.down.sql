@@ -0,0 +1,9 @@
+ALTER TABLE task_completions
+DROP COLUMN IF EXISTS task_revision_id;
+
+DROP TABLE IF EXISTS task_revisions;
+
+DROP TYPE IF EXISTS task_revision_action;
+
+ALTER TABLE tasks
+DROP COLUMN IF EXISTS archived_at;
@@ -0,0 +1,50 @@
+-- Deleted tasks keep their row so completions and awarded points stay explainable.
+ALTER TABLE tasks
+ADD COLUMN archived_at TIMESTAMP WITH TIME ZONE;
+
+CREATE TYPE task_revision_action AS ENUM ('created', 'updated', 'deleted', 'restored');
+
+CREATE TABLE IF NOT EXISTS task_revisions (
+    id SERIAL PRIMARY KEY,
+    task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL,
+    action task_revision_action NOT NULL,
+    -- Editable fields of the task after the change, in the shape `POST /tasks` takes.
+    snapshot JSONB NOT NULL,
+    -- Changed fields as {"field": {"from": ..., "to": ...}}.
+    diff JSONB NOT NULL DEFAULT '{}',
+    changed_by INTEGER REFERENCES api_keys(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (task_id, revision)
+);
+
+-- Existing tasks start their history as they are now.
+INSERT INTO task_revisions (task_id, revision, action, snapshot)
+SELECT id, 1, 'created', jsonb_build_object(
+    'description', description,
+    'points', points,
+    'task_button_text', COALESCE(task_button_text, ''),
+    'link', link,
+    'task_type', task_type,
+    'category', category,
+    'icon_url', icon_url,
+    'display_order', display_order,
+    'metadata', NULLIF(metadata, '{}'::jsonb),
+    'status', status,
+    'starts_at', starts_at,
+    'ends_at', ends_at,
+    'recurrence', recurrence,
+    'recurrence_schedule', recurrence_schedule,
+    'max_completions', max_completions,
+    'points_budget', points_budget
+)
+FROM tasks;
+
+-- The task as it was when the points were awarded.
+ALTER TABLE task_completions
+ADD COLUMN task_revision_id INTEGER REFERENCES task_revisions(id) ON DELETE SET NULL;
+
+UPDATE task_completions
+SET task_revision_id = task_revisions.id
+FROM task_revisions
+WHERE task_revisions.task_id = task_completions.task_id;
@@ -0,0 +1,3 @@
+-- Bumped with every revision, sent as the ETag of the task.
+ALTER TABLE tasks
+ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
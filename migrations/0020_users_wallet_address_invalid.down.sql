@@ -0,0 +1,4 @@
+ALTER TABLE users
+DROP COLUMN IF EXISTS wallet_address_invalid;
+
+DROP FUNCTION IF EXISTS is_solana_address(TEXT);
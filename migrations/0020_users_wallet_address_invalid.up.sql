@@ -0,0 +1,40 @@
+-- Wallets used to be stored as whatever string was sent. Flag the ones that don't
+-- decode to a 32 byte key so they can be cleaned up, binding a new wallet clears it.
+CREATE OR REPLACE FUNCTION is_solana_address(address TEXT) RETURNS BOOLEAN AS $$
+DECLARE
+    alphabet CONSTANT TEXT := '123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz';
+    value NUMERIC := 0;
+    digit INTEGER;
+    leading_zeros INTEGER := 0;
+    seen_non_zero BOOLEAN := FALSE;
+    bytes INTEGER := 0;
+BEGIN
+    IF address IS NULL OR length(address) NOT BETWEEN 32 AND 44 THEN
+        RETURN FALSE;
+    END IF;
+    FOR i IN 1..length(address) LOOP
+        digit := strpos(alphabet, substr(address, i, 1)) - 1;
+        IF digit < 0 THEN
+            RETURN FALSE;
+        END IF;
+        IF digit = 0 AND NOT seen_non_zero THEN
+            leading_zeros := leading_zeros + 1;
+        ELSE
+            seen_non_zero := TRUE;
+        END IF;
+        value := value * 58 + digit;
+    END LOOP;
+    WHILE value > 0 LOOP
+        value := trunc(value / 256);
+        bytes := bytes + 1;
+    END LOOP;
+    RETURN leading_zeros + bytes = 32;
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+ALTER TABLE users
+ADD COLUMN wallet_address_invalid BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE users
+SET wallet_address_invalid = TRUE
+WHERE wallet_address IS NOT NULL AND NOT is_solana_address(wallet_address);
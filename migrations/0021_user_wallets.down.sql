@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS wallet_link_challenges;
+DROP TABLE IF EXISTS user_wallets;
+DROP TYPE IF EXISTS wallet_chain;
@@ -0,0 +1,29 @@
+CREATE TYPE wallet_chain AS ENUM ('solana', 'ethereum', 'base', 'arbitrum', 'polygon');
+
+-- One airdrop wallet per chain and user. Addresses are stored in their canonical
+-- form, base58 for Solana and EIP-55 checksummed for EVM chains.
+CREATE TABLE IF NOT EXISTS user_wallets (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    chain wallet_chain NOT NULL,
+    address VARCHAR(64) NOT NULL,
+    -- When ownership was proven with a signature, sign up wallets start unverified.
+    verified_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, chain),
+    UNIQUE (chain, address)
+);
+
+INSERT INTO user_wallets (user_id, chain, address)
+SELECT id, 'solana', wallet_address
+FROM users
+WHERE wallet_address IS NOT NULL AND NOT wallet_address_invalid
+ON CONFLICT DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS wallet_link_challenges (
+    nonce VARCHAR(64) PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    chain wallet_chain NOT NULL,
+    address VARCHAR(64) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,24 @@
+-- A merkle-distributor airdrop, the root is what gets set on chain.
+CREATE TABLE IF NOT EXISTS airdrops (
+    id SERIAL PRIMARY KEY,
+    merkle_root VARCHAR(64) NOT NULL,
+    total_amount BIGINT NOT NULL,
+    -- Sum of the allocations, rounding leaves the rest of the pool undistributed.
+    allocated_amount BIGINT NOT NULL,
+    num_nodes INTEGER NOT NULL,
+    created_by INTEGER REFERENCES api_keys(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS airdrop_allocations (
+    airdrop_id INTEGER NOT NULL REFERENCES airdrops(id) ON DELETE CASCADE,
+    leaf_index BIGINT NOT NULL,
+    user_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    wallet VARCHAR(64) NOT NULL,
+    points BIGINT NOT NULL,
+    amount BIGINT NOT NULL,
+    -- Hex encoded sibling hashes from the leaf up.
+    proof JSONB NOT NULL,
+    PRIMARY KEY (airdrop_id, leaf_index),
+    UNIQUE (airdrop_id, user_id)
+);
@@ -0,0 +1,5 @@
+ALTER TABLE airdrops
+DROP COLUMN IF EXISTS snapshot_id;
+
+DROP TABLE IF EXISTS snapshot_entries;
+DROP TABLE IF EXISTS snapshots;
@@ -0,0 +1,27 @@
+-- Frozen snapshots keep exactly what a distribution was computed from.
+CREATE TABLE IF NOT EXISTS snapshots (
+    id SERIAL PRIMARY KEY,
+    -- Chain whose linked wallets were used, NULL for account wallets.
+    chain wallet_chain,
+    parameters JSONB NOT NULL,
+    -- sha256 over the entries, see `snapshots::content_hash`.
+    content_hash VARCHAR(64) NOT NULL,
+    num_entries INTEGER NOT NULL,
+    total_points BIGINT NOT NULL,
+    created_by INTEGER REFERENCES api_keys(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- No foreign key on users, the record outlives deleted accounts.
+CREATE TABLE IF NOT EXISTS snapshot_entries (
+    snapshot_id INTEGER NOT NULL REFERENCES snapshots(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL,
+    twitter_id VARCHAR(255) NOT NULL,
+    wallet_address VARCHAR(255) NOT NULL,
+    points BIGINT NOT NULL,
+    PRIMARY KEY (snapshot_id, user_id)
+);
+
+-- The frozen snapshot an airdrop was allocated from.
+ALTER TABLE airdrops
+ADD COLUMN snapshot_id INTEGER REFERENCES snapshots(id) ON DELETE SET NULL;
@@ -0,0 +1 @@
+ALTER TABLE airdrops DROP COLUMN IF EXISTS formula;
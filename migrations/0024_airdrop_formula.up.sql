@@ -0,0 +1,2 @@
+-- Allocation formula the amounts were computed with, `{}` is the plain pro rata default.
+ALTER TABLE airdrops ADD COLUMN IF NOT EXISTS formula JSONB NOT NULL DEFAULT '{}';
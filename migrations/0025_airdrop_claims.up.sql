@@ -0,0 +1,12 @@
+-- Allocations claimed on chain, a row means the claim happened.
+CREATE TABLE IF NOT EXISTS airdrop_claims (
+    airdrop_id INTEGER NOT NULL,
+    leaf_index BIGINT NOT NULL,
+    -- Unknown when the RPC node no longer had the transaction history.
+    signature VARCHAR(128),
+    -- Block time of the claim transaction.
+    claimed_at TIMESTAMP WITH TIME ZONE,
+    recorded_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (airdrop_id, leaf_index),
+    FOREIGN KEY (airdrop_id, leaf_index) REFERENCES airdrop_allocations(airdrop_id, leaf_index) ON DELETE CASCADE
+);
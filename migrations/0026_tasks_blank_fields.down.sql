@@ -0,0 +1 @@
+-- Empty strings and NULL mean the same, there is nothing to restore.
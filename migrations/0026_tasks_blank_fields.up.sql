@@ -0,0 +1,4 @@
+-- Links and button texts used to be saved as empty strings. Clear them so stored
+-- tasks pass the checks updates are held to.
+UPDATE tasks
+SET link = NULLIF(link, ''), task_button_text = NULLIF(task_button_text, '');
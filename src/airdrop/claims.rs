@@ -0,0 +1,275 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::{RpcError, SolanaRpc};
+use crate::{
+    models::SolanaAddress,
+    task_io::{ImportError, RowError, TaskFormat},
+};
+
+/// Accounts asked about per `getMultipleAccounts` call, the most nodes accept.
+pub const ACCOUNTS_PER_REQUEST: usize = 100;
+
+/// A claimed allocation, from a claims file or found on chain.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct ClaimRecord {
+    /// Leaf index of the allocation.
+    pub index: u64,
+    /// Base58 transaction signature, unknown when the node no longer has the history.
+    pub signature: Option<String>,
+    pub claimed_at: Option<DateTime<Utc>>,
+}
+
+fn is_transaction_signature(value: &str) -> bool {
+    bs58::decode(value)
+        .into_vec()
+        .is_ok_and(|bytes| bytes.len() == 64)
+}
+
+/// Reads a claims file, `index,signature,claimed_at` for CSV or an array of objects with
+/// those fields for JSON. Every row needs a signature, `claimed_at` is optional.
+pub fn parse_claims(format: TaskFormat, input: &str) -> Result<Vec<ClaimRecord>, ImportError> {
+    let rows: Vec<(usize, Result<ClaimRecord, String>)> = match format {
+        TaskFormat::Json => {
+            let values: Vec<Value> = serde_json::from_str(input).map_err(|err| {
+                ImportError::Invalid(vec![RowError {
+                    row: 0,
+                    error: format!("Expected a JSON array of claims: {err}"),
+                }])
+            })?;
+            values
+                .into_iter()
+                .enumerate()
+                .map(|(index, value)| {
+                    (
+                        index + 1,
+                        serde_json::from_value(value).map_err(|err| err.to_string()),
+                    )
+                })
+                .collect()
+        }
+        TaskFormat::Csv => csv::Reader::from_reader(input.as_bytes())
+            .deserialize::<ClaimRecord>()
+            .enumerate()
+            .map(|(index, record)| (index + 2, record.map_err(|err| err.to_string())))
+            .collect(),
+    };
+
+    let mut claims = vec![];
+    let mut errors = vec![];
+    for (row, claim) in rows {
+        let claim = claim.and_then(|claim| match &claim.signature {
+            Some(signature) if is_transaction_signature(signature) => Ok(claim),
+            Some(_) => Err("signature is not a base58 transaction signature.".to_string()),
+            None => Err("signature is missing.".to_string()),
+        });
+        match claim {
+            Ok(claim) => claims.push(claim),
+            Err(error) => errors.push(RowError { row, error }),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ImportError::Invalid(errors));
+    }
+    Ok(claims)
+}
+
+/// The program derived address for `seeds`, searching bumps down from 255 like
+/// `Pubkey::find_program_address`.
+pub fn find_program_address(seeds: &[&[u8]], program_id: &SolanaAddress) -> (SolanaAddress, u8) {
+    for bump in (0..=u8::MAX).rev() {
+        let mut hasher = Sha256::new();
+        for seed in seeds {
+            hasher.update(seed);
+        }
+        hasher.update([bump]);
+        hasher.update(program_id.to_bytes());
+        hasher.update(b"ProgramDerivedAddress");
+        let address = SolanaAddress::from_bytes(hasher.finalize().into());
+        if !address.is_on_curve() {
+            return (address, bump);
+        }
+    }
+    unreachable!("a bump off the curve always exists")
+}
+
+/// The account the merkle distributor creates when leaf `index` is claimed.
+pub fn claim_status_address(
+    program_id: &SolanaAddress,
+    distributor: &SolanaAddress,
+    index: u64,
+) -> SolanaAddress {
+    let (address, _) = find_program_address(
+        &[
+            b"ClaimStatus",
+            &index.to_le_bytes(),
+            &distributor.to_bytes(),
+        ],
+        program_id,
+    );
+    address
+}
+
+/// Asks the node which of `indexes` have been claimed from `distributor` and by which
+/// transaction.
+pub async fn fetch_claims(
+    rpc: &SolanaRpc,
+    program_id: &SolanaAddress,
+    distributor: &SolanaAddress,
+    indexes: &[u64],
+) -> Result<Vec<ClaimRecord>, RpcError> {
+    let mut claims = vec![];
+    for batch in indexes.chunks(ACCOUNTS_PER_REQUEST) {
+        let addresses: Vec<SolanaAddress> = batch
+            .iter()
+            .map(|index| claim_status_address(program_id, distributor, *index))
+            .collect();
+        let exist = rpc.accounts_exist(&addresses).await?;
+
+        for ((index, address), exists) in batch.iter().zip(&addresses).zip(exist) {
+            if !exists {
+                continue;
+            }
+            let signature = rpc.first_signature(address).await?;
+            claims.push(ClaimRecord {
+                index: *index,
+                claimed_at: signature
+                    .as_ref()
+                    .and_then(|signature| signature.block_time)
+                    .and_then(|block_time| DateTime::from_timestamp(block_time, 0)),
+                signature: signature.map(|signature| signature.signature),
+            });
+        }
+    }
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, sync::Arc};
+
+    use axum::{routing::post, Json, Router};
+    use serde_json::json;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    fn signature(byte: u8) -> String {
+        bs58::encode([byte; 64]).into_string()
+    }
+
+    #[test]
+    fn test_parse_claims() {
+        let csv = format!(
+            "index,signature,claimed_at\n0,{},2024-05-01T12:00:00Z\n3,{},\n",
+            signature(1),
+            signature(2)
+        );
+        let claims = parse_claims(TaskFormat::Csv, &csv).unwrap();
+        assert_eq!(claims.len(), 2);
+        assert_eq!(claims[0].signature, Some(signature(1)));
+        assert!(claims[0].claimed_at.is_some());
+        assert_eq!((claims[1].index, claims[1].claimed_at), (3, None));
+
+        let json = format!(
+            r#"[{{"index": 1, "signature": "{}"}}, {{"index": 2, "signature": "frog"}}, {{"index": 4}}, {{"index": -1, "signature": "{}"}}]"#,
+            signature(3),
+            signature(4)
+        );
+        let Err(ImportError::Invalid(errors)) = parse_claims(TaskFormat::Json, &json) else {
+            panic!("expected row errors");
+        };
+        let rows: Vec<usize> = errors.iter().map(|error| error.row).collect();
+        assert_eq!(rows, vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn test_claim_status_address() {
+        let program_id = SolanaAddress::random();
+        let distributor = SolanaAddress::random();
+        let address = claim_status_address(&program_id, &distributor, 7);
+        assert!(!address.is_on_curve());
+        assert_eq!(address, claim_status_address(&program_id, &distributor, 7));
+        assert_ne!(address, claim_status_address(&program_id, &distributor, 8));
+
+        let (pda, bump) = find_program_address(&[b"seed"], &program_id);
+        let (again, _) = find_program_address(&[b"seed"], &program_id);
+        assert_eq!(pda, again);
+        let mut hasher = Sha256::new();
+        hasher.update(b"seed");
+        hasher.update([bump]);
+        hasher.update(program_id.to_bytes());
+        hasher.update(b"ProgramDerivedAddress");
+        assert_eq!(pda.to_bytes(), <[u8; 32]>::from(hasher.finalize()));
+    }
+
+    /// A JSON-RPC node where the claim status accounts of `claimed` exist.
+    async fn mock_rpc(claimed: HashSet<String>) -> String {
+        let claimed = Arc::new(claimed);
+        let app = Router::new().route(
+            "/",
+            post(move |Json(request): Json<Value>| {
+                let claimed = claimed.clone();
+                async move {
+                    let params = &request["params"];
+                    let result = match request["method"].as_str().unwrap() {
+                        "getMultipleAccounts" => {
+                            assert_eq!(params[1]["dataSlice"]["length"], 0);
+                            let accounts: Vec<Value> = params[0]
+                                .as_array()
+                                .unwrap()
+                                .iter()
+                                .map(|address| match claimed.contains(address.as_str().unwrap()) {
+                                    true => json!({ "lamports": 1, "data": ["", "base64"] }),
+                                    false => Value::Null,
+                                })
+                                .collect();
+                            json!({ "context": { "slot": 1 }, "value": accounts })
+                        }
+                        "getSignaturesForAddress" => json!([
+                            { "signature": signature(9), "err": null, "blockTime": 1_714_600_000 },
+                            { "signature": signature(8), "err": null, "blockTime": 1_714_500_000 },
+                            { "signature": signature(7), "err": { "InstructionError": [0, "Custom"] }, "blockTime": 1_714_400_000 }
+                        ]),
+                        method => panic!("unexpected method {method}"),
+                    };
+                    Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+                }
+            }),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{address}")
+    }
+
+    #[tokio::test]
+    async fn test_fetch_claims_from_rpc() {
+        let program_id = SolanaAddress::random();
+        let distributor = SolanaAddress::random();
+        let claimed: HashSet<String> = [2, 150]
+            .iter()
+            .map(|index| claim_status_address(&program_id, &distributor, *index).to_string())
+            .collect();
+        let rpc = SolanaRpc::new(reqwest::Client::new(), mock_rpc(claimed).await);
+
+        let indexes: Vec<u64> = (0..200).collect();
+        let claims = fetch_claims(&rpc, &program_id, &distributor, &indexes)
+            .await
+            .unwrap();
+        assert_eq!(claims.len(), 2);
+        assert_eq!(claims[0].index, 2);
+        assert_eq!(claims[1].index, 150);
+        // The oldest successful transaction, the failed attempt before it doesn't count.
+        assert_eq!(claims[0].signature, Some(signature(8)));
+        assert_eq!(
+            claims[0].claimed_at,
+            DateTime::from_timestamp(1_714_500_000, 0)
+        );
+    }
+}
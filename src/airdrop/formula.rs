@@ -0,0 +1,240 @@
+use std::cmp::Reverse;
+
+use serde::{Deserialize, Serialize};
+
+use crate::validation::ValidationErrors;
+
+/// Square root weights are scaled by this so small balances still differ.
+const SQRT_SCALE: u128 = 1_000_000_000_000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Tier {
+    /// Points needed to reach the tier.
+    pub min_points: i64,
+    pub weight: u64,
+}
+
+/// How points turn into a weight in the pool.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Curve {
+    #[default]
+    Linear,
+    Sqrt,
+    /// Everyone in a tier weighs the same, the highest tier reached counts.
+    Tiered(Vec<Tier>),
+}
+
+/// Strategy for sharing a token pool out over points, stored with every airdrop.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct AllocationFormula {
+    pub curve: Curve,
+    /// Users below it get nothing, users without positive points never do.
+    pub min_points: i64,
+    /// Most a single wallet receives, what a cap cuts off goes to the others.
+    pub max_amount_per_wallet: Option<u64>,
+}
+
+impl AllocationFormula {
+    pub fn weight(&self, points: i64) -> u128 {
+        if points <= 0 || points < self.min_points {
+            return 0;
+        }
+        match &self.curve {
+            Curve::Linear => points as u128,
+            Curve::Sqrt => (points as u128 * SQRT_SCALE).isqrt(),
+            Curve::Tiered(tiers) => tiers
+                .iter()
+                .filter(|tier| tier.min_points <= points)
+                .max_by_key(|tier| tier.min_points)
+                .map_or(0, |tier| tier.weight as u128),
+        }
+    }
+
+    pub fn validate(&self, field: &str, errors: &mut ValidationErrors) {
+        if let Curve::Tiered(tiers) = &self.curve {
+            errors.check(
+                &format!("{field}.curve"),
+                !tiers.is_empty(),
+                "Must have at least one tier.",
+            );
+            errors.check(
+                &format!("{field}.curve"),
+                tiers.windows(2).all(|w| w[0].min_points < w[1].min_points),
+                "Tiers must be ordered by strictly increasing min_points.",
+            );
+        }
+        errors.check(
+            &format!("{field}.max_amount_per_wallet"),
+            self.max_amount_per_wallet != Some(0),
+            "Must be positive.",
+        );
+    }
+
+    /// Shares `total_amount` out in proportion to the weight of each user's points.
+    ///
+    /// Amounts are exact integers: everyone gets the floor of their share and the units
+    /// left by rounding go one each to the largest remainders, earlier entries winning
+    /// ties. Capped wallets are fixed at the cap and the rest is shared again among the
+    /// others. Only when every receiving wallet is capped does part of the pool stay
+    /// undistributed.
+    pub fn allocate(&self, points: &[i64], total_amount: u64) -> Vec<u64> {
+        let weights: Vec<u128> = points.iter().map(|points| self.weight(*points)).collect();
+        let mut amounts = vec![0u64; points.len()];
+        let mut open: Vec<usize> = (0..points.len()).filter(|i| weights[*i] > 0).collect();
+        let mut pool = total_amount as u128;
+
+        if let Some(cap) = self.max_amount_per_wallet {
+            loop {
+                let total_weight: u128 = open.iter().map(|i| weights[*i]).sum();
+                let (capped, uncapped): (Vec<usize>, Vec<usize>) = open
+                    .iter()
+                    .partition(|i| pool * weights[**i] / total_weight >= cap as u128);
+                if capped.is_empty() {
+                    break;
+                }
+                for i in capped {
+                    amounts[i] = cap;
+                    pool -= cap as u128;
+                }
+                open = uncapped;
+                if open.is_empty() {
+                    return amounts;
+                }
+            }
+        }
+
+        let total_weight: u128 = open.iter().map(|i| weights[*i]).sum();
+        if total_weight == 0 {
+            return amounts;
+        }
+        let mut remainders = Vec::with_capacity(open.len());
+        let mut distributed = 0u128;
+        for i in open {
+            let share = pool * weights[i];
+            amounts[i] = (share / total_weight) as u64;
+            distributed += share / total_weight;
+            remainders.push((share % total_weight, i));
+        }
+        // Ties keep entry order since the sort is stable.
+        remainders.sort_by_key(|(remainder, _)| Reverse(*remainder));
+        for (_, i) in remainders.into_iter().take((pool - distributed) as usize) {
+            amounts[i] += 1;
+        }
+        amounts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tiered() -> AllocationFormula {
+        AllocationFormula {
+            curve: Curve::Tiered(vec![
+                Tier {
+                    min_points: 10,
+                    weight: 1,
+                },
+                Tier {
+                    min_points: 100,
+                    weight: 3,
+                },
+            ]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_linear_hands_out_dust_by_remainder() {
+        let formula = AllocationFormula::default();
+        // 1000/3 = 333.33.., 2000/3 = 666.66.., the last unit goes to the larger remainder.
+        assert_eq!(formula.allocate(&[1, 2, 0, -5], 1000), vec![333, 667, 0, 0]);
+        // Equal remainders, earlier entries first.
+        assert_eq!(formula.allocate(&[1, 1, 1], 10), vec![4, 3, 3]);
+        assert_eq!(formula.allocate(&[0], 1000), vec![0]);
+        assert_eq!(
+            formula.allocate(&[i64::MAX, i64::MAX], u64::MAX),
+            vec![u64::MAX / 2 + 1, u64::MAX / 2]
+        );
+    }
+
+    #[test]
+    fn test_sqrt() {
+        let formula = AllocationFormula {
+            curve: Curve::Sqrt,
+            ..Default::default()
+        };
+        assert_eq!(formula.weight(4), 2_000_000);
+        assert_eq!(formula.weight(2), 1_414_213);
+        assert_eq!(formula.allocate(&[1, 4, 9], 600), vec![100, 200, 300]);
+    }
+
+    #[test]
+    fn test_tiers_and_threshold() {
+        let formula = tiered();
+        assert_eq!(formula.weight(9), 0);
+        assert_eq!(formula.weight(10), 1);
+        assert_eq!(formula.weight(5000), 3);
+        assert_eq!(formula.allocate(&[5, 50, 500], 400), vec![0, 100, 300]);
+
+        let formula = AllocationFormula {
+            min_points: 20,
+            ..Default::default()
+        };
+        assert_eq!(formula.allocate(&[10, 20, 30], 100), vec![0, 40, 60]);
+    }
+
+    #[test]
+    fn test_caps_redistribute() {
+        let formula = AllocationFormula {
+            max_amount_per_wallet: Some(300),
+            ..Default::default()
+        };
+        // The whale is held at 300, the other 400 is shared by the rest.
+        assert_eq!(formula.allocate(&[90, 5, 5], 700), vec![300, 200, 200]);
+        // Everyone capped, the rest of the pool stays put.
+        assert_eq!(formula.allocate(&[1, 1], 1000), vec![300, 300]);
+        // Capping one user can push another over the cap.
+        assert_eq!(
+            formula.allocate(&[60, 30, 5, 5], 1000),
+            vec![300, 300, 200, 200]
+        );
+        let amounts = formula.allocate(&[7, 11, 13, 17, 19], 1000);
+        assert_eq!(amounts.iter().sum::<u64>(), 1000);
+        assert!(amounts.iter().all(|amount| *amount <= 300));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut errors = ValidationErrors::default();
+        tiered().validate("formula", &mut errors);
+        assert!(errors.is_empty());
+
+        let formula = AllocationFormula {
+            curve: Curve::Tiered(vec![
+                Tier {
+                    min_points: 10,
+                    weight: 1,
+                },
+                Tier {
+                    min_points: 10,
+                    weight: 2,
+                },
+            ]),
+            min_points: 0,
+            max_amount_per_wallet: Some(0),
+        };
+        formula.validate("formula", &mut errors);
+        assert_eq!(errors.0.len(), 2);
+        assert_eq!(errors.0[0].field, "formula.curve");
+
+        let formula: AllocationFormula =
+            serde_json::from_str(r#"{"curve": {"tiered": [{"min_points": 1, "weight": 2}]}}"#)
+                .unwrap();
+        assert_eq!(formula.weight(1), 2);
+        let formula: AllocationFormula = serde_json::from_str(r#"{"curve": "sqrt"}"#).unwrap();
+        assert_eq!(formula.curve, Curve::Sqrt);
+    }
+}
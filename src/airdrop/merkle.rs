@@ -0,0 +1,144 @@
+use sha3::{Digest, Keccak256};
+
+use crate::models::SolanaAddress;
+
+pub type Hash = [u8; 32];
+
+fn keccak(parts: &[&[u8]]) -> Hash {
+    let mut hasher = Keccak256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+/// Leaf of the saber merkle-distributor program,
+/// `keccak256(index as u64 LE || claimant || amount as u64 LE)`.
+pub fn leaf_hash(index: u64, claimant: &SolanaAddress, amount: u64) -> Hash {
+    keccak(&[
+        &index.to_le_bytes(),
+        &claimant.to_bytes(),
+        &amount.to_le_bytes(),
+    ])
+}
+
+/// Pairs are hashed in sorted order, so a proof doesn't need to say which side each
+/// sibling is on.
+fn hash_pair(a: &Hash, b: &Hash) -> Hash {
+    if a <= b {
+        keccak(&[a, b])
+    } else {
+        keccak(&[b, a])
+    }
+}
+
+/// Built like the distributor's `BalanceTree`: leaves are sorted and an odd node at
+/// the end of a layer moves up unchanged.
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    layers: Vec<Vec<Hash>>,
+}
+
+impl MerkleTree {
+    /// `None` without leaves, an empty tree has no root.
+    pub fn new(mut leaves: Vec<Hash>) -> Option<MerkleTree> {
+        if leaves.is_empty() {
+            return None;
+        }
+        leaves.sort_unstable();
+        leaves.dedup();
+
+        let mut layers = vec![leaves];
+        while layers[layers.len() - 1].len() > 1 {
+            let next = layers[layers.len() - 1]
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => hash_pair(a, b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+            layers.push(next);
+        }
+        Some(MerkleTree { layers })
+    }
+
+    pub fn root(&self) -> Hash {
+        self.layers[self.layers.len() - 1][0]
+    }
+
+    /// Siblings from the leaf up to the root, `None` if the leaf isn't in the tree.
+    pub fn proof(&self, leaf: &Hash) -> Option<Vec<Hash>> {
+        let mut index = self.layers[0].binary_search(leaf).ok()?;
+        let mut proof = vec![];
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        Some(proof)
+    }
+}
+
+/// What the on-chain program checks before paying out a claim.
+pub fn verify(proof: &[Hash], root: &Hash, leaf: Hash) -> bool {
+    let computed = proof
+        .iter()
+        .fold(leaf, |computed, sibling| hash_pair(&computed, sibling));
+    computed == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: u64) -> Vec<Hash> {
+        (0..count)
+            .map(|index| leaf_hash(index, &SolanaAddress::random(), 100 + index))
+            .collect()
+    }
+
+    #[test]
+    fn test_every_proof_verifies() {
+        for count in 1..=9 {
+            let leaves = leaves(count);
+            let tree = MerkleTree::new(leaves.clone()).unwrap();
+            for leaf in &leaves {
+                let proof = tree.proof(leaf).unwrap();
+                assert!(verify(&proof, &tree.root(), *leaf), "{count} leaves");
+            }
+        }
+    }
+
+    #[test]
+    fn test_proof_rejects_other_leaves() {
+        let leaves = leaves(5);
+        let tree = MerkleTree::new(leaves.clone()).unwrap();
+        let proof = tree.proof(&leaves[0]).unwrap();
+        assert!(!verify(&proof, &tree.root(), leaves[1]));
+
+        let claimant = SolanaAddress::random();
+        assert!(tree.proof(&leaf_hash(0, &claimant, 100)).is_none());
+    }
+
+    #[test]
+    fn test_root_of_two_leaves() {
+        let leaves = leaves(2);
+        let tree = MerkleTree::new(leaves.clone()).unwrap();
+        assert_eq!(tree.root(), hash_pair(&leaves[1], &leaves[0]));
+        assert_eq!(MerkleTree::new(vec![leaves[0]]).unwrap().root(), leaves[0]);
+        assert!(MerkleTree::new(vec![]).is_none());
+    }
+
+    #[test]
+    fn test_leaf_layout() {
+        let claimant = SolanaAddress::from_bytes([1; 32]);
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&3u64.to_le_bytes());
+        bytes.extend_from_slice(&[1; 32]);
+        bytes.extend_from_slice(&500u64.to_le_bytes());
+        let expected: Hash = Keccak256::digest(&bytes).into();
+        assert_eq!(leaf_hash(3, &claimant, 500), expected);
+    }
+}
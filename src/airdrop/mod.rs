@@ -0,0 +1,123 @@
+mod claims;
+mod formula;
+mod merkle;
+mod rpc;
+
+pub use claims::*;
+pub use formula::*;
+pub use merkle::*;
+pub use rpc::*;
+
+use crate::models::SolanaAddress;
+
+/// A user's points and the wallet their tokens go to.
+#[derive(Debug, Clone)]
+pub struct AirdropEntry {
+    pub user_id: i32,
+    pub wallet: SolanaAddress,
+    pub points: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Allocation {
+    /// Position of the leaf, the claim program tracks claims by it.
+    pub index: u64,
+    pub user_id: i32,
+    pub wallet: SolanaAddress,
+    pub points: i64,
+    pub amount: u64,
+    pub proof: Vec<Hash>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Airdrop {
+    pub merkle_root: Hash,
+    pub total_amount: u64,
+    pub formula: AllocationFormula,
+    pub allocations: Vec<Allocation>,
+}
+
+impl Airdrop {
+    /// What actually goes out, rounding leaves the rest undistributed.
+    pub fn allocated_amount(&self) -> u64 {
+        self.allocations
+            .iter()
+            .map(|allocation| allocation.amount)
+            .sum()
+    }
+}
+
+/// Allocates the pool and builds the distributor tree, one leaf per wallet that
+/// receives something. `None` if nobody does.
+pub fn build_airdrop(
+    entries: Vec<AirdropEntry>,
+    total_amount: u64,
+    formula: &AllocationFormula,
+) -> Option<Airdrop> {
+    let points: Vec<i64> = entries.iter().map(|entry| entry.points).collect();
+    let amounts = formula.allocate(&points, total_amount);
+    let mut allocations: Vec<Allocation> = entries
+        .into_iter()
+        .zip(amounts)
+        .filter(|(_, amount)| *amount > 0)
+        .enumerate()
+        .map(|(index, (entry, amount))| Allocation {
+            index: index as u64,
+            user_id: entry.user_id,
+            wallet: entry.wallet,
+            points: entry.points,
+            amount,
+            proof: vec![],
+        })
+        .collect();
+
+    let leaves: Vec<Hash> = allocations
+        .iter()
+        .map(|allocation| leaf_hash(allocation.index, &allocation.wallet, allocation.amount))
+        .collect();
+    let tree = MerkleTree::new(leaves.clone())?;
+    for (allocation, leaf) in allocations.iter_mut().zip(&leaves) {
+        allocation.proof = tree.proof(leaf)?;
+        debug_assert!(verify(&allocation.proof, &tree.root(), *leaf));
+    }
+
+    Some(Airdrop {
+        merkle_root: tree.root(),
+        total_amount,
+        formula: formula.clone(),
+        allocations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(user_id: i32, points: i64) -> AirdropEntry {
+        AirdropEntry {
+            user_id,
+            wallet: SolanaAddress::random(),
+            points,
+        }
+    }
+
+    #[test]
+    fn test_build_airdrop_proofs_verify() {
+        let entries = vec![entry(1, 10), entry(2, 0), entry(3, 30), entry(4, 60)];
+        let airdrop = build_airdrop(entries, 1_000_000, &AllocationFormula::default()).unwrap();
+
+        assert_eq!(airdrop.allocations.len(), 3);
+        assert_eq!(airdrop.allocated_amount(), 1_000_000);
+        let indexes: Vec<u64> = airdrop.allocations.iter().map(|a| a.index).collect();
+        assert_eq!(indexes, vec![0, 1, 2]);
+        for allocation in &airdrop.allocations {
+            let leaf = leaf_hash(allocation.index, &allocation.wallet, allocation.amount);
+            assert!(verify(&allocation.proof, &airdrop.merkle_root, leaf));
+            // A claim for more than the allocation doesn't verify.
+            let inflated = leaf_hash(allocation.index, &allocation.wallet, allocation.amount + 1);
+            assert!(!verify(&allocation.proof, &airdrop.merkle_root, inflated));
+        }
+
+        assert!(build_airdrop(vec![entry(1, 0)], 1_000, &AllocationFormula::default()).is_none());
+    }
+}
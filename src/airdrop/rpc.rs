@@ -0,0 +1,141 @@
+use std::env;
+
+use serde_json::{json, Value};
+
+use crate::models::SolanaAddress;
+
+const DEFAULT_RPC_URL: &str = "https://api.mainnet-beta.solana.com";
+
+#[derive(Debug)]
+pub enum RpcError {
+    Request { error: String },
+    Rpc { code: i64, message: String },
+    Malformed { method: String },
+}
+
+impl std::fmt::Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Request { error } => write!(f, "RPC request failed: {error}"),
+            Self::Rpc { code, message } => write!(f, "RPC error {code}: {message}"),
+            Self::Malformed { method } => write!(f, "Unexpected RPC response to {method}"),
+        }
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+impl From<reqwest::Error> for RpcError {
+    fn from(value: reqwest::Error) -> Self {
+        Self::Request {
+            error: value.to_string(),
+        }
+    }
+}
+
+/// A transaction that touched an account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureInfo {
+    pub signature: String,
+    /// Unix seconds, missing for transactions the node has no time for.
+    pub block_time: Option<i64>,
+}
+
+/// Minimal Solana JSON-RPC client, only what claim tracking reads.
+#[derive(Clone)]
+pub struct SolanaRpc {
+    http: reqwest::Client,
+    url: String,
+}
+
+impl SolanaRpc {
+    pub fn new(http: reqwest::Client, url: String) -> Self {
+        Self { http, url }
+    }
+
+    /// Reads `SOLANA_RPC_URL`, the public mainnet endpoint without it.
+    pub fn from_env(http: reqwest::Client) -> Self {
+        let url = env::var("SOLANA_RPC_URL").unwrap_or_else(|_| DEFAULT_RPC_URL.to_string());
+        Self::new(http, url)
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        let response: Value = self
+            .http
+            .post(&self.url)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if let Some(error) = response.get("error") {
+            return Err(RpcError::Rpc {
+                code: error["code"].as_i64().unwrap_or_default(),
+                message: error["message"].as_str().unwrap_or_default().to_string(),
+            });
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| RpcError::Malformed {
+                method: method.to_string(),
+            })
+    }
+
+    /// Whether each account exists, in the order asked. Nodes take up to 100 at a time.
+    pub async fn accounts_exist(&self, addresses: &[SolanaAddress]) -> Result<Vec<bool>, RpcError> {
+        let method = "getMultipleAccounts";
+        let addresses: Vec<&str> = addresses.iter().map(SolanaAddress::as_str).collect();
+        // Only existence matters, so no account data is sent back.
+        let result = self
+            .call(
+                method,
+                json!([addresses, {
+                    "encoding": "base64",
+                    "dataSlice": { "offset": 0, "length": 0 }
+                }]),
+            )
+            .await?;
+
+        let accounts = result["value"]
+            .as_array()
+            .filter(|accounts| accounts.len() == addresses.len())
+            .ok_or_else(|| RpcError::Malformed {
+                method: method.to_string(),
+            })?;
+        Ok(accounts.iter().map(|account| !account.is_null()).collect())
+    }
+
+    /// The oldest successful transaction among the latest 1000 that touched `address`.
+    pub async fn first_signature(
+        &self,
+        address: &SolanaAddress,
+    ) -> Result<Option<SignatureInfo>, RpcError> {
+        let method = "getSignaturesForAddress";
+        let result = self
+            .call(method, json!([address.as_str(), { "limit": 1000 }]))
+            .await?;
+
+        let signatures = result.as_array().ok_or_else(|| RpcError::Malformed {
+            method: method.to_string(),
+        })?;
+        // Newest first, so the last successful one created the account.
+        Ok(signatures
+            .iter()
+            .rev()
+            .filter(|signature| signature["err"].is_null())
+            .find_map(|signature| {
+                Some(SignatureInfo {
+                    signature: signature["signature"].as_str()?.to_string(),
+                    block_time: signature["blockTime"].as_i64(),
+                })
+            }))
+    }
+}
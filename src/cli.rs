@@ -0,0 +1,220 @@
+use std::{env, fs, path::Path, time::Duration};
+
+use crate::{
+    airdrop::{fetch_claims, parse_claims, SolanaRpc, ACCOUNTS_PER_REQUEST},
+    constants::SOLANA_RPC_TIMEOUT,
+    db::{
+        _get_airdrop, _get_tasks, _get_unclaimed_leaf_indexes, _import_tasks,
+        _record_airdrop_claims, Database,
+    },
+    models::{SolanaAddress, TaskFilterDTO},
+    task_io::{export_tasks, parse_tasks, ImportError, RowError, TaskFormat},
+};
+
+const USAGE: &str = "Usage:
+  twitter-points-farmer import-tasks <tasks.csv|tasks.json> [--dry-run]
+  twitter-points-farmer export-tasks <tasks.csv|tasks.json>
+  twitter-points-farmer import-claims <airdrop_id> <claims.csv|claims.json>
+  twitter-points-farmer sync-claims <airdrop_id> <distributor> [--every <seconds>]
+
+sync-claims asks SOLANA_RPC_URL for the claim status accounts of the program in
+MERKLE_DISTRIBUTOR_PROGRAM_ID, with --every it keeps polling.";
+
+/// Runs a maintenance command instead of the server, returns the exit code.
+pub async fn run(db: &Database, args: &[String]) -> i32 {
+    let result = match args {
+        [command, path, flags @ ..] if command == "import-tasks" => {
+            import(
+                db,
+                Path::new(path),
+                flags.iter().any(|flag| flag == "--dry-run"),
+            )
+            .await
+        }
+        [command, path] if command == "export-tasks" => export(db, Path::new(path)).await,
+        [command, airdrop_id, path] if command == "import-claims" => {
+            import_claims(db, airdrop_id, Path::new(path)).await
+        }
+        [command, airdrop_id, distributor] if command == "sync-claims" => {
+            sync_claims(db, airdrop_id, distributor, None).await
+        }
+        [command, airdrop_id, distributor, flag, seconds]
+            if command == "sync-claims" && flag == "--every" =>
+        {
+            match seconds.parse() {
+                Ok(seconds) => {
+                    sync_claims(
+                        db,
+                        airdrop_id,
+                        distributor,
+                        Some(Duration::from_secs(seconds)),
+                    )
+                    .await
+                }
+                Err(_) => Err(USAGE.to_string()),
+            }
+        }
+        _ => Err(USAGE.to_string()),
+    };
+
+    match result {
+        Ok(message) => {
+            println!("{message}");
+            0
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            1
+        }
+    }
+}
+
+fn format_of(path: &Path) -> Result<TaskFormat, String> {
+    TaskFormat::from_path(path)
+        .ok_or_else(|| format!("{} should end in .csv or .json", path.display()))
+}
+
+async fn import(db: &Database, path: &Path, dry_run: bool) -> Result<String, String> {
+    let format = format_of(path)?;
+    let input = fs::read_to_string(path).map_err(|err| err.to_string())?;
+
+    let tasks = parse_tasks(format, &input).map_err(|err| err.to_string())?;
+    let task_ids = _import_tasks(db, tasks, dry_run, None)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    if dry_run {
+        return Ok(format!(
+            "{} tasks are valid, nothing imported.",
+            task_ids.len()
+        ));
+    }
+    Ok(format!("Imported {} tasks: {task_ids:?}", task_ids.len()))
+}
+
+async fn export(db: &Database, path: &Path) -> Result<String, String> {
+    let format = format_of(path)?;
+    let filter = TaskFilterDTO {
+        task_type: None,
+        category: None,
+        status: None,
+        deleted: false,
+    };
+    let tasks = _get_tasks(db, filter, true)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    let count = tasks.len();
+    fs::write(path, export_tasks(format, tasks)?).map_err(|err| err.to_string())?;
+    Ok(format!("Exported {count} tasks to {}.", path.display()))
+}
+
+fn parse_airdrop_id(airdrop_id: &str) -> Result<i32, String> {
+    airdrop_id
+        .parse()
+        .map_err(|_| format!("{airdrop_id} is not an airdrop id"))
+}
+
+async fn import_claims(db: &Database, airdrop_id: &str, path: &Path) -> Result<String, String> {
+    let airdrop_id = parse_airdrop_id(airdrop_id)?;
+    let format = format_of(path)?;
+    let input = fs::read_to_string(path).map_err(|err| err.to_string())?;
+
+    let airdrop = _get_airdrop(db, airdrop_id)
+        .await
+        .map_err(|err| err.to_string())?
+        .ok_or_else(|| format!("Airdrop {airdrop_id} not found."))?;
+    let claims = parse_claims(format, &input).map_err(|err| err.to_string())?;
+    // Rows as `parse_claims` numbers them, a CSV header is row 1.
+    let first_row = match format {
+        TaskFormat::Json => 1,
+        TaskFormat::Csv => 2,
+    };
+    let errors: Vec<RowError> = claims
+        .iter()
+        .enumerate()
+        .filter(|(_, claim)| claim.index >= airdrop.num_nodes as u64)
+        .map(|(index, claim)| RowError {
+            row: index + first_row,
+            error: format!(
+                "index {} is not a leaf of airdrop {airdrop_id}.",
+                claim.index
+            ),
+        })
+        .collect();
+    if !errors.is_empty() {
+        return Err(ImportError::Invalid(errors).to_string());
+    }
+
+    let recorded = _record_airdrop_claims(db, airdrop_id, &claims)
+        .await
+        .map_err(|err| err.to_string())?;
+    Ok(format!(
+        "Recorded {recorded} of {} claims, the rest were known.",
+        claims.len()
+    ))
+}
+
+async fn sync_claims(
+    db: &Database,
+    airdrop_id: &str,
+    distributor: &str,
+    every: Option<Duration>,
+) -> Result<String, String> {
+    let airdrop_id = parse_airdrop_id(airdrop_id)?;
+    let distributor = SolanaAddress::parse(distributor).map_err(|err| err.to_string())?;
+    _get_airdrop(db, airdrop_id)
+        .await
+        .map_err(|err| err.to_string())?
+        .ok_or_else(|| format!("Airdrop {airdrop_id} not found."))?;
+    let program_id = env::var("MERKLE_DISTRIBUTOR_PROGRAM_ID")
+        .map_err(|_| "Missing required environment variable: MERKLE_DISTRIBUTOR_PROGRAM_ID")?;
+    let program_id = SolanaAddress::parse(&program_id).map_err(|err| err.to_string())?;
+    let http = reqwest::Client::builder()
+        .timeout(SOLANA_RPC_TIMEOUT)
+        .build()
+        .map_err(|err| err.to_string())?;
+    let rpc = SolanaRpc::from_env(http);
+
+    loop {
+        let result = sync_claims_once(db, &rpc, airdrop_id, &program_id, &distributor).await;
+        let Some(every) = every else {
+            return result;
+        };
+        // A failed round is retried with the next one.
+        match result {
+            Ok(message) => println!("{message}"),
+            Err(error) => eprintln!("{error}"),
+        }
+        tokio::time::sleep(every).await;
+    }
+}
+
+async fn sync_claims_once(
+    db: &Database,
+    rpc: &SolanaRpc,
+    airdrop_id: i32,
+    program_id: &SolanaAddress,
+    distributor: &SolanaAddress,
+) -> Result<String, String> {
+    let indexes: Vec<u64> = _get_unclaimed_leaf_indexes(db, airdrop_id)
+        .await
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .map(|index| index as u64)
+        .collect();
+    // Each batch is recorded before the next call, a failing call keeps what came before.
+    let mut recorded = 0;
+    for batch in indexes.chunks(ACCOUNTS_PER_REQUEST) {
+        let claims = fetch_claims(rpc, program_id, distributor, batch)
+            .await
+            .map_err(|err| format!("{err}, recorded {recorded} new claims before it."))?;
+        recorded += _record_airdrop_claims(db, airdrop_id, &claims)
+            .await
+            .map_err(|err| err.to_string())?;
+    }
+    Ok(format!(
+        "Checked {} unclaimed allocations, recorded {recorded} new claims.",
+        indexes.len()
+    ))
+}
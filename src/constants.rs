@@ -3,3 +3,30 @@ use std::time::Duration;
 
 pub const REQUESTS_AMOUNT_LIMIT: u8 = 20;
 pub const REQUESTS_AMOUNT_TIME_FRAME: Duration = Duration::from_secs(20);
+
+pub const SIGNATURE_REPLAY_WINDOW: Duration = Duration::from_secs(300);
+pub const MAX_SIGNED_BODY_SIZE: usize = 1024 * 1024;
+
+pub const API_KEY_RATE_LIMIT_TIME_FRAME: Duration = Duration::from_secs(60);
+
+pub const STREAK_BONUS_STEP: i32 = 10;
+pub const STREAK_BONUS_CAP: i32 = 100;
+pub const STREAK_FREEZE_PRICE: i32 = 200;
+pub const MAX_STREAK_FREEZES: i32 = 3;
+
+pub const VERIFIER_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub const TWITTER_CACHE_TTL: Duration = Duration::from_secs(60);
+pub const TWITTER_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(60);
+pub const TWITTER_MAX_PAGES: usize = 5;
+pub const TWITTER_OAUTH_STATE_TTL: Duration = Duration::from_secs(600);
+
+pub const WALLET_LINK_CHALLENGE_TTL: Duration = Duration::from_secs(600);
+
+pub const SOLANA_RPC_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub const QUIZ_MAX_ATTEMPTS: i64 = 3;
+pub const QUIZ_MIN_SCORE_PERCENT: i64 = 50;
+
+pub const MAX_USER_MULTIPLIER: i32 = 100;
+pub const PASSWORD_MIN_LENGTH: usize = 8;
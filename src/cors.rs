@@ -0,0 +1,221 @@
+use std::{env, time::Duration};
+
+use axum::http::{header, HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::middlewares::{
+    API_KEY_HEADER, SIGNATURE_HEADER, SIGNATURE_KEY_HEADER, SIGNATURE_NONCE_HEADER,
+    SIGNATURE_TIMESTAMP_HEADER,
+};
+
+const DEFAULT_ALLOWED_ORIGIN: &str = "https://farm.frogofroggins.wtf";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorsSettings {
+    /// Exact origins, or patterns such as `https://*.vercel.app` where `*` stands
+    /// for a single subdomain label.
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<Method>,
+    pub allowed_headers: Vec<HeaderName>,
+    pub allow_credentials: bool,
+    pub max_age: Option<Duration>,
+    /// Reflects any request origin back. Selected with `APP_ENV=development`.
+    pub dev_mode: bool,
+}
+
+impl CorsSettings {
+    pub fn from_env() -> Self {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let dev_mode = var("APP_ENV").is_some_and(|value| value == "development");
+
+        let allowed_origins = var("CORS_ALLOWED_ORIGINS")
+            .map(|value| split_list(&value))
+            .unwrap_or_else(|| vec![DEFAULT_ALLOWED_ORIGIN.to_string()]);
+
+        let allowed_methods = var("CORS_ALLOWED_METHODS")
+            .map(|value| {
+                split_list(&value)
+                    .iter()
+                    .map(|method| {
+                        method.to_uppercase().parse::<Method>().unwrap_or_else(|_| {
+                            panic!("Invalid method in CORS_ALLOWED_METHODS: {method}")
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_else(|| {
+                vec![
+                    Method::GET,
+                    Method::POST,
+                    Method::PUT,
+                    Method::PATCH,
+                    Method::DELETE,
+                ]
+            });
+
+        let allowed_headers = var("CORS_ALLOWED_HEADERS")
+            .map(|value| split_list(&value))
+            .unwrap_or_else(|| {
+                [
+                    "Content-Type",
+                    "Authorization",
+                    "If-Match",
+                    API_KEY_HEADER,
+                    SIGNATURE_KEY_HEADER,
+                    SIGNATURE_TIMESTAMP_HEADER,
+                    SIGNATURE_NONCE_HEADER,
+                    SIGNATURE_HEADER,
+                ]
+                .iter()
+                .map(|header| header.to_string())
+                .collect()
+            })
+            .iter()
+            .map(|header| {
+                header
+                    .parse::<HeaderName>()
+                    .unwrap_or_else(|_| panic!("Invalid header in CORS_ALLOWED_HEADERS: {header}"))
+            })
+            .collect();
+
+        let allow_credentials = var("CORS_ALLOW_CREDENTIALS").is_some_and(|value| value == "true");
+
+        let max_age = var("CORS_MAX_AGE").map(|value| {
+            Duration::from_secs(
+                value
+                    .parse()
+                    .unwrap_or_else(|_| panic!("Invalid CORS_MAX_AGE: {value}")),
+            )
+        });
+
+        Self {
+            allowed_origins,
+            allowed_methods,
+            allowed_headers,
+            allow_credentials,
+            max_age,
+            dev_mode,
+        }
+    }
+
+    pub fn is_origin_allowed(&self, origin: &str) -> bool {
+        self.allowed_origins
+            .iter()
+            .any(|pattern| origin_matches(pattern, origin))
+    }
+
+    pub fn layer(&self) -> CorsLayer {
+        let allow_origin = if self.dev_mode {
+            AllowOrigin::mirror_request()
+        } else {
+            let settings = self.clone();
+            AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+                origin
+                    .to_str()
+                    .is_ok_and(|origin| settings.is_origin_allowed(origin))
+            })
+        };
+
+        let mut layer = CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods(self.allowed_methods.clone())
+            .allow_headers(self.allowed_headers.clone())
+            .expose_headers([header::ETAG])
+            .allow_credentials(self.allow_credentials);
+
+        if let Some(max_age) = self.max_age {
+            layer = layer.max_age(max_age);
+        }
+
+        layer
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+pub fn origin_matches(pattern: &str, origin: &str) -> bool {
+    let pattern = pattern.trim_end_matches('/');
+    match pattern.split_once("*.") {
+        None => pattern.eq_ignore_ascii_case(origin),
+        Some((scheme, suffix)) => {
+            let host = match origin.strip_prefix(scheme) {
+                Some(host) => host,
+                None => return false,
+            };
+            match host.strip_suffix(suffix) {
+                Some(label) => {
+                    let label = match label.strip_suffix('.') {
+                        Some(label) => label,
+                        None => return false,
+                    };
+                    !label.is_empty()
+                        && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                }
+                None => false,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_origin() {
+        assert!(origin_matches(
+            "https://farm.frogofroggins.wtf",
+            "https://farm.frogofroggins.wtf"
+        ));
+        assert!(!origin_matches(
+            "https://farm.frogofroggins.wtf",
+            "http://farm.frogofroggins.wtf"
+        ));
+    }
+
+    #[test]
+    fn test_wildcard_subdomain() {
+        let pattern = "https://*.vercel.app";
+        assert!(origin_matches(pattern, "https://farm-git-pr-12.vercel.app"));
+        assert!(!origin_matches(pattern, "https://vercel.app"));
+        assert!(!origin_matches(pattern, "https://a.b.vercel.app"));
+        assert!(!origin_matches(pattern, "http://preview.vercel.app"));
+        assert!(!origin_matches(pattern, "https://evilvercel.app"));
+    }
+
+    #[test]
+    fn test_settings_from_vars() {
+        let settings = CorsSettings::from_vars(|name| match name {
+            "CORS_ALLOWED_ORIGINS" => Some("https://a.com, https://*.b.com".to_string()),
+            "CORS_ALLOWED_METHODS" => Some("get,post".to_string()),
+            "CORS_ALLOW_CREDENTIALS" => Some("true".to_string()),
+            "CORS_MAX_AGE" => Some("600".to_string()),
+            _ => None,
+        });
+        assert!(!settings.dev_mode);
+        assert!(settings.allow_credentials);
+        assert_eq!(settings.allowed_methods, vec![Method::GET, Method::POST]);
+        assert_eq!(settings.max_age, Some(Duration::from_secs(600)));
+        assert!(settings.is_origin_allowed("https://pr-1.b.com"));
+        assert!(!settings.is_origin_allowed("https://c.com"));
+    }
+
+    #[test]
+    fn test_dev_mode() {
+        let settings = CorsSettings::from_vars(|name| match name {
+            "APP_ENV" => Some("development".to_string()),
+            _ => None,
+        });
+        assert!(settings.dev_mode);
+        assert_eq!(settings.allowed_origins, vec![DEFAULT_ALLOWED_ORIGIN]);
+    }
+}
@@ -0,0 +1,324 @@
+use serde_json::{json, Value};
+
+use crate::{
+    airdrop::{Airdrop, ClaimRecord},
+    db::Database,
+    models::{AirdropAllocation, AirdropClaim, AirdropClaimStatus, AirdropDistribution},
+};
+
+const AIRDROP_COLUMNS: &str =
+    "id, merkle_root, total_amount, allocated_amount, num_nodes, formula, snapshot_id, created_by, created_at";
+const ALLOCATION_COLUMNS: &str = "airdrop_id, leaf_index, user_id, wallet, points, amount, proof";
+const CLAIM_COLUMNS: &str = "airdrop_id, leaf_index, signature, claimed_at, recorded_at";
+/// Allocations inserted per statement.
+const ALLOCATION_BATCH_SIZE: usize = 5000;
+
+/// Stores the airdrop with every allocation and its proof.
+pub async fn _create_airdrop(
+    db: &Database,
+    airdrop: &Airdrop,
+    snapshot_id: Option<i32>,
+    created_by: Option<i32>,
+) -> Result<AirdropDistribution, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let distribution: AirdropDistribution = sqlx::query_as(&format!(
+        "INSERT INTO airdrops (merkle_root, total_amount, allocated_amount, num_nodes, formula, snapshot_id, created_by) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING {AIRDROP_COLUMNS}"
+    ))
+    .bind(hex::encode(airdrop.merkle_root))
+    .bind(airdrop.total_amount as i64)
+    .bind(airdrop.allocated_amount() as i64)
+    .bind(airdrop.allocations.len() as i32)
+    .bind(serde_json::to_value(&airdrop.formula).unwrap_or_default())
+    .bind(snapshot_id)
+    .bind(created_by)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    for batch in airdrop.allocations.chunks(ALLOCATION_BATCH_SIZE) {
+        let leaf_indexes: Vec<i64> = batch.iter().map(|a| a.index as i64).collect();
+        let user_ids: Vec<i32> = batch.iter().map(|a| a.user_id).collect();
+        let wallets: Vec<&str> = batch.iter().map(|a| a.wallet.as_str()).collect();
+        let points: Vec<i64> = batch.iter().map(|a| a.points).collect();
+        let amounts: Vec<i64> = batch.iter().map(|a| a.amount as i64).collect();
+        let proofs: Vec<Value> = batch
+            .iter()
+            .map(|a| json!(a.proof.iter().map(hex::encode).collect::<Vec<_>>()))
+            .collect();
+
+        sqlx::query(
+            "INSERT INTO airdrop_allocations (airdrop_id, leaf_index, user_id, wallet, points, amount, proof) SELECT $1, * FROM UNNEST($2::BIGINT[], $3::INTEGER[], $4::VARCHAR[], $5::BIGINT[], $6::BIGINT[], $7::JSONB[])",
+        )
+        .bind(distribution.id)
+        .bind(leaf_indexes)
+        .bind(user_ids)
+        .bind(wallets)
+        .bind(points)
+        .bind(amounts)
+        .bind(proofs)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(distribution)
+}
+
+pub async fn _get_airdrop(
+    db: &Database,
+    airdrop_id: i32,
+) -> Result<Option<AirdropDistribution>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {AIRDROP_COLUMNS} FROM airdrops WHERE id = $1"
+    ))
+    .bind(airdrop_id)
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn _get_latest_airdrop(
+    db: &Database,
+) -> Result<Option<AirdropDistribution>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {AIRDROP_COLUMNS} FROM airdrops ORDER BY id DESC LIMIT 1"
+    ))
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn _get_airdrop_allocation(
+    db: &Database,
+    airdrop_id: i32,
+    user_id: i32,
+) -> Result<Option<AirdropAllocation>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {ALLOCATION_COLUMNS} FROM airdrop_allocations WHERE airdrop_id = $1 AND user_id = $2"
+    ))
+    .bind(airdrop_id)
+    .bind(user_id)
+    .fetch_optional(db)
+    .await
+}
+
+/// Leaf indexes of the allocations nobody has claimed yet, in order.
+pub async fn _get_unclaimed_leaf_indexes(
+    db: &Database,
+    airdrop_id: i32,
+) -> Result<Vec<i64>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT leaf_index FROM airdrop_allocations WHERE airdrop_id = $1 AND NOT EXISTS (SELECT 1 FROM airdrop_claims WHERE airdrop_claims.airdrop_id = airdrop_allocations.airdrop_id AND airdrop_claims.leaf_index = airdrop_allocations.leaf_index) ORDER BY leaf_index",
+    )
+    .bind(airdrop_id)
+    .fetch_all(db)
+    .await
+}
+
+/// Marks the allocations as claimed, returns how many claims were new or completed.
+/// Indexes outside the airdrop are ignored. A known claim only has what it lacks,
+/// signature or time, filled in.
+pub async fn _record_airdrop_claims(
+    db: &Database,
+    airdrop_id: i32,
+    claims: &[ClaimRecord],
+) -> Result<u64, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let mut recorded = 0;
+
+    for batch in claims.chunks(ALLOCATION_BATCH_SIZE) {
+        let leaf_indexes: Vec<i64> = batch.iter().map(|claim| claim.index as i64).collect();
+        let signatures: Vec<Option<&str>> = batch
+            .iter()
+            .map(|claim| claim.signature.as_deref())
+            .collect();
+        let claimed_ats: Vec<_> = batch.iter().map(|claim| claim.claimed_at).collect();
+
+        recorded += sqlx::query(
+            "INSERT INTO airdrop_claims (airdrop_id, leaf_index, signature, claimed_at) SELECT $1, claims.* FROM UNNEST($2::BIGINT[], $3::VARCHAR[], $4::TIMESTAMPTZ[]) AS claims (leaf_index, signature, claimed_at) WHERE EXISTS (SELECT 1 FROM airdrop_allocations WHERE airdrop_id = $1 AND leaf_index = claims.leaf_index) ON CONFLICT (airdrop_id, leaf_index) DO UPDATE SET signature = COALESCE(airdrop_claims.signature, EXCLUDED.signature), claimed_at = COALESCE(airdrop_claims.claimed_at, EXCLUDED.claimed_at) WHERE (airdrop_claims.signature IS NULL AND EXCLUDED.signature IS NOT NULL) OR (airdrop_claims.claimed_at IS NULL AND EXCLUDED.claimed_at IS NOT NULL)",
+        )
+        .bind(airdrop_id)
+        .bind(leaf_indexes)
+        .bind(signatures)
+        .bind(claimed_ats)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+
+    tx.commit().await?;
+    Ok(recorded)
+}
+
+pub async fn _get_airdrop_claim(
+    db: &Database,
+    airdrop_id: i32,
+    leaf_index: i64,
+) -> Result<Option<AirdropClaim>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {CLAIM_COLUMNS} FROM airdrop_claims WHERE airdrop_id = $1 AND leaf_index = $2"
+    ))
+    .bind(airdrop_id)
+    .bind(leaf_index)
+    .fetch_optional(db)
+    .await
+}
+
+/// Every allocation of the user with its claim, newest airdrop first.
+pub async fn _get_user_claim_statuses(
+    db: &Database,
+    user_id: i32,
+) -> Result<Vec<AirdropClaimStatus>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT airdrop_allocations.airdrop_id, airdrops.merkle_root, airdrop_allocations.leaf_index, airdrop_allocations.wallet, airdrop_allocations.amount, airdrop_claims.leaf_index IS NOT NULL AS claimed, airdrop_claims.signature, airdrop_claims.claimed_at FROM airdrop_allocations JOIN airdrops ON airdrops.id = airdrop_allocations.airdrop_id LEFT JOIN airdrop_claims ON airdrop_claims.airdrop_id = airdrop_allocations.airdrop_id AND airdrop_claims.leaf_index = airdrop_allocations.leaf_index WHERE airdrop_allocations.user_id = $1 ORDER BY airdrop_allocations.airdrop_id DESC",
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::{
+        airdrop::{build_airdrop, leaf_hash, verify, AirdropEntry, AllocationFormula, Hash},
+        db::_create_user,
+        models::{CreateUserDTO, SolanaAddress},
+    };
+    use password_encryptor::PasswordEncryptor;
+    use sqlx::postgres::PgPoolOptions;
+
+    fn decode_hash(value: &str) -> Hash {
+        hex::decode(value).unwrap().try_into().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_stored_proofs_verify() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let suffix = chrono::Local::now().to_string();
+        let mut entries = vec![];
+        for (name, points) in [("whale", 700), ("shrimp", 300), ("lurker", 0)] {
+            let user = _create_user(
+                &pool,
+                CreateUserDTO {
+                    twitter_id: format!("{name}{suffix}"),
+                    reffer_code: None,
+                    solana_adr: SolanaAddress::random(),
+                    password: "123".to_string(),
+                },
+                PasswordEncryptor::new(vec![1, 2, 3], None),
+                "salt",
+            )
+            .await
+            .unwrap();
+            entries.push(AirdropEntry {
+                user_id: user.id,
+                wallet: user.wallet_address.parse().unwrap(),
+                points,
+            });
+        }
+        let user_ids: Vec<i32> = entries.iter().map(|entry| entry.user_id).collect();
+
+        let airdrop = build_airdrop(entries, 1_000_000, &AllocationFormula::default()).unwrap();
+        let distribution = _create_airdrop(&pool, &airdrop, None, None).await.unwrap();
+        assert_eq!(distribution.num_nodes, 2);
+        assert_eq!(distribution.allocated_amount, 1_000_000);
+
+        let root = decode_hash(&distribution.merkle_root);
+        for user_id in &user_ids[..2] {
+            let allocation = _get_airdrop_allocation(&pool, distribution.id, *user_id)
+                .await
+                .unwrap()
+                .unwrap();
+            let proof: Vec<Hash> = allocation
+                .proof
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|hash| decode_hash(hash.as_str().unwrap()))
+                .collect();
+            let leaf = leaf_hash(
+                allocation.leaf_index as u64,
+                &allocation.wallet.parse().unwrap(),
+                allocation.amount as u64,
+            );
+            assert!(verify(&proof, &root, leaf));
+        }
+        assert!(_get_airdrop_allocation(&pool, distribution.id, user_ids[2])
+            .await
+            .unwrap()
+            .is_none());
+
+        let unclaimed = _get_unclaimed_leaf_indexes(&pool, distribution.id)
+            .await
+            .unwrap();
+        assert_eq!(unclaimed, vec![0, 1]);
+        let claims = vec![
+            ClaimRecord {
+                index: 1,
+                signature: None,
+                claimed_at: None,
+            },
+            // Not a leaf of this airdrop.
+            ClaimRecord {
+                index: 5,
+                signature: Some("sig".to_string()),
+                claimed_at: None,
+            },
+        ];
+        assert_eq!(
+            _record_airdrop_claims(&pool, distribution.id, &claims)
+                .await
+                .unwrap(),
+            1
+        );
+        // Seen again with its signature, only the missing part is filled in.
+        let claims = vec![ClaimRecord {
+            index: 1,
+            signature: Some("sig".to_string()),
+            claimed_at: None,
+        }];
+        for recorded in [1, 0] {
+            assert_eq!(
+                _record_airdrop_claims(&pool, distribution.id, &claims)
+                    .await
+                    .unwrap(),
+                recorded
+            );
+        }
+        assert_eq!(
+            _get_unclaimed_leaf_indexes(&pool, distribution.id)
+                .await
+                .unwrap(),
+            vec![0]
+        );
+        let claim = _get_airdrop_claim(&pool, distribution.id, 1)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(claim.signature.as_deref(), Some("sig"));
+
+        let claimed_user = airdrop
+            .allocations
+            .iter()
+            .find(|allocation| allocation.index == 1)
+            .unwrap()
+            .user_id;
+        let statuses = _get_user_claim_statuses(&pool, claimed_user).await.unwrap();
+        assert_eq!(statuses.len(), 1);
+        assert!(statuses[0].claimed);
+        assert_eq!(statuses[0].airdrop_id, distribution.id);
+        let statuses = _get_user_claim_statuses(&pool, user_ids[2]).await.unwrap();
+        assert!(statuses.is_empty());
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{
+    db::Database,
+    models::{ApiClient, ApiClientWithSecret, CreateApiClientDTO},
+};
+
+pub async fn _create_api_client(
+    db: &Database,
+    create_api_client_dto: CreateApiClientDTO,
+    key_id: &str,
+    secret: &str,
+) -> Result<ApiClientWithSecret, sqlx::Error> {
+    let client = sqlx::query_as::<_, ApiClientWithSecret>(
+        "INSERT INTO api_clients (name, key_id, secret) VALUES ($1, $2, $3) RETURNING id, name, key_id, secret, created_at, revoked_at",
+    )
+    .bind(create_api_client_dto.name)
+    .bind(key_id)
+    .bind(secret)
+    .fetch_one(db)
+    .await?;
+    Ok(client)
+}
+
+pub async fn _get_api_clients(db: &Database) -> Result<Vec<ApiClient>, sqlx::Error> {
+    let clients: Vec<ApiClient> = sqlx::query_as(
+        "SELECT id, name, key_id, created_at, revoked_at FROM api_clients ORDER BY id",
+    )
+    .fetch_all(db)
+    .await?;
+    Ok(clients)
+}
+
+/// Secret of a client that has not been revoked, used to verify request signatures.
+pub async fn _get_active_api_client_secret(
+    db: &Database,
+    key_id: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    let secret = sqlx::query_as::<_, (String,)>(
+        "SELECT secret FROM api_clients WHERE key_id = $1 AND revoked_at IS NULL",
+    )
+    .bind(key_id)
+    .fetch_optional(db)
+    .await?;
+    Ok(secret.map(|row| row.0))
+}
+
+pub async fn _revoke_api_client(db: &Database, key_id: &str) -> Result<(), sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE api_clients SET revoked_at = NOW() WHERE key_id = $1 AND revoked_at IS NULL",
+    )
+    .bind(key_id)
+    .execute(db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    Ok(())
+}
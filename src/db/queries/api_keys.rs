@@ -0,0 +1,64 @@
+use crate::{
+    db::Database,
+    models::{ApiKey, CreateApiKeyDTO},
+};
+
+pub async fn _create_api_key(
+    db: &Database,
+    create_api_key_dto: CreateApiKeyDTO,
+    key_prefix: &str,
+    key_hash: &str,
+) -> Result<ApiKey, sqlx::Error> {
+    let scopes: Vec<&str> = create_api_key_dto
+        .scopes
+        .iter()
+        .map(|scope| scope.as_str())
+        .collect();
+
+    let api_key = sqlx::query_as::<_, ApiKey>(
+        "INSERT INTO api_keys (name, key_prefix, key_hash, scopes, rate_limit, expires_at) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id, name, key_prefix, scopes, rate_limit, expires_at, last_used_at, created_at, revoked_at",
+    )
+    .bind(create_api_key_dto.name)
+    .bind(key_prefix)
+    .bind(key_hash)
+    .bind(scopes)
+    .bind(create_api_key_dto.rate_limit)
+    .bind(create_api_key_dto.expires_at)
+    .fetch_one(db)
+    .await?;
+    Ok(api_key)
+}
+
+pub async fn _get_api_keys(db: &Database) -> Result<Vec<ApiKey>, sqlx::Error> {
+    let api_keys: Vec<ApiKey> = sqlx::query_as(
+        "SELECT id, name, key_prefix, scopes, rate_limit, expires_at, last_used_at, created_at, revoked_at FROM api_keys ORDER BY id",
+    )
+    .fetch_all(db)
+    .await?;
+    Ok(api_keys)
+}
+
+/// Looks up a key that is neither revoked nor expired and marks it as used.
+pub async fn _use_api_key(db: &Database, key_hash: &str) -> Result<Option<ApiKey>, sqlx::Error> {
+    let api_key: Option<ApiKey> = sqlx::query_as(
+        "UPDATE api_keys SET last_used_at = NOW() WHERE key_hash = $1 AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > NOW()) RETURNING id, name, key_prefix, scopes, rate_limit, expires_at, last_used_at, created_at, revoked_at",
+    )
+    .bind(key_hash)
+    .fetch_optional(db)
+    .await?;
+    Ok(api_key)
+}
+
+pub async fn _revoke_api_key(db: &Database, id: i32) -> Result<(), sqlx::Error> {
+    let result =
+        sqlx::query("UPDATE api_keys SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL")
+            .bind(id)
+            .execute(db)
+            .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    Ok(())
+}
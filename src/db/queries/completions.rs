@@ -0,0 +1,726 @@
+use chrono::{NaiveDate, Utc};
+use chrono_tz::Tz;
+use sqlx::{Postgres, Transaction};
+
+use crate::{
+    constants::REFERRAL_BONUS_PRECENT,
+    db::Database,
+    models::{FinishTaskDTO, QuizScore, Task, TaskCapacity, TaskType, User},
+    recurrence::{period_key, RecurrenceError},
+    streaks::{check_in, StreakState},
+    verification::{Verdict, Verifiers},
+};
+
+use super::{
+    _create_submission, _get_quiz_questions, _get_task_by_id, _get_user_by_wallet_address,
+    _record_verification, _take_quiz,
+};
+
+pub type Result<T> = core::result::Result<T, CompletionError>;
+
+#[derive(Debug)]
+pub enum CompletionError {
+    Database { error: String },
+    UserNotFound,
+    TaskNotFound,
+    TaskNotAvailable,
+    TaskLocked,
+    TaskExhausted,
+    SubmissionNotFound,
+    NoAttemptsLeft,
+    ProofRejected { reason: String },
+    VerifierFailed { error: String },
+    Recurrence { error: String },
+}
+
+pub enum FinishOutcome {
+    /// Holds what is left of the task's caps.
+    Completed(TaskCapacity),
+    PendingReview {
+        submission_id: i32,
+    },
+    /// Points are the task points scaled by the score.
+    QuizPassed {
+        score: QuizScore,
+        points: i32,
+        capacity: TaskCapacity,
+    },
+}
+
+impl From<sqlx::Error> for CompletionError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Database {
+            error: value.to_string(),
+        }
+    }
+}
+
+impl From<RecurrenceError> for CompletionError {
+    fn from(value: RecurrenceError) -> Self {
+        Self::Recurrence {
+            error: value.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for CompletionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Database { error } => write!(f, "Database error: {error}"),
+            Self::UserNotFound => write!(f, "User not found."),
+            Self::TaskNotFound => write!(f, "Task not found."),
+            Self::TaskNotAvailable => write!(f, "Task is not available."),
+            Self::TaskLocked => write!(f, "Complete the prerequisite tasks first."),
+            Self::TaskExhausted => write!(f, "Task has no completions left."),
+            Self::SubmissionNotFound => write!(f, "No pending submission found."),
+            Self::NoAttemptsLeft => write!(f, "No attempts left for this quiz."),
+            Self::ProofRejected { reason } => write!(f, "{reason}"),
+            Self::VerifierFailed { error } => write!(f, "{error}"),
+            Self::Recurrence { error } => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for CompletionError {}
+
+/// Adds points to the user and the referral share to whoever referred them.
+pub async fn _credit_points(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: i32,
+    points: i32,
+) -> Result<()> {
+    let (referrer_id,): (Option<i32>,) = sqlx::query_as(
+        "UPDATE users SET total_points = total_points + $1 WHERE id = $2 RETURNING referrer_id",
+    )
+    .bind(points)
+    .bind(user_id)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    if let Some(referrer_id) = referrer_id {
+        let points_for_referral = points * REFERRAL_BONUS_PRECENT as i32 / 100;
+        sqlx::query(
+            "UPDATE users SET total_points = total_points + $1, referral_points = referral_points + $1 WHERE id = $2",
+        )
+        .bind(points_for_referral)
+        .bind(referrer_id)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Marks every quest containing `task_id` that the user has now fully completed and
+/// returns the sum of their bonuses. Archived tasks no longer have to be completed.
+async fn _complete_quests(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: i32,
+    task_id: i32,
+) -> Result<i32> {
+    let bonuses: Vec<(i32,)> = sqlx::query_as(
+        "INSERT INTO quest_completions (user_id, quest_id, bonus_awarded) SELECT $1, quests.id, quests.bonus_points FROM quests JOIN quest_tasks ON quest_tasks.quest_id = quests.id AND quest_tasks.task_id = $2 WHERE NOT EXISTS (SELECT 1 FROM quest_tasks AS remaining JOIN tasks ON tasks.id = remaining.task_id WHERE remaining.quest_id = quests.id AND tasks.archived_at IS NULL AND NOT EXISTS (SELECT 1 FROM task_completions WHERE task_completions.user_id = $1 AND task_completions.task_id = remaining.task_id)) ON CONFLICT DO NOTHING RETURNING bonus_awarded",
+    )
+    .bind(user_id)
+    .bind(task_id)
+    .fetch_all(&mut **tx)
+    .await?;
+    Ok(bonuses.iter().map(|row| row.0).sum())
+}
+
+/// Books a payout against the task's caps, closing the task once the next payout
+/// wouldn't fit anymore. The row lock serializes concurrent completions, so caps can't
+/// be overrun. Returns `None` if the task is already exhausted.
+async fn _reserve_capacity(
+    tx: &mut Transaction<'_, Postgres>,
+    task_id: i32,
+    points: i32,
+) -> Result<Option<TaskCapacity>> {
+    let row: Option<(Option<i32>, Option<i32>)> = sqlx::query_as(
+        "UPDATE tasks SET completions_count = completions_count + 1, points_spent = points_spent + $2, status = CASE WHEN completions_count + 1 >= max_completions OR points_budget - (points_spent + $2) < points THEN 'exhausted' ELSE status END WHERE id = $1 AND status IN ('active', 'scheduled') AND (max_completions IS NULL OR completions_count < max_completions) AND (points_budget IS NULL OR points_spent + $2 <= points_budget) RETURNING max_completions - completions_count, points_budget - points_spent",
+    )
+    .bind(task_id)
+    .bind(points)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    Ok(
+        row.map(|(remaining_completions, remaining_budget)| TaskCapacity {
+            remaining_completions,
+            remaining_budget,
+        }),
+    )
+}
+
+/// Moves the user's streak forward and returns the streak bonus to pay out.
+async fn _apply_check_in(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: i32,
+    today: NaiveDate,
+) -> Result<i32> {
+    let (current_streak, longest_streak, last_check_in, streak_freezes): (
+        i32,
+        i32,
+        Option<NaiveDate>,
+        i32,
+    ) = sqlx::query_as(
+        "SELECT current_streak, longest_streak, last_check_in, streak_freezes FROM users WHERE id = $1 FOR UPDATE",
+    )
+    .bind(user_id)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    let state = StreakState {
+        current_streak,
+        longest_streak,
+        last_check_in,
+        streak_freezes,
+    };
+
+    // A second check-in task on the same day keeps the streak as it is.
+    let check_in = match check_in(state, today) {
+        Some(check_in) => check_in,
+        None => return Ok(0),
+    };
+
+    sqlx::query(
+        "UPDATE users SET current_streak = $1, longest_streak = $2, last_check_in = $3, streak_freezes = $4 WHERE id = $5",
+    )
+    .bind(check_in.state.current_streak)
+    .bind(check_in.state.longest_streak)
+    .bind(check_in.state.last_check_in)
+    .bind(check_in.state.streak_freezes)
+    .bind(user_id)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(check_in.bonus)
+}
+
+/// Verifies the user's proof and, if it holds, completes the task for the current
+/// period. Tasks reviewed by hand are queued instead. Completing a task again in the
+/// same period is a no-op.
+pub async fn _finish_task(
+    db: &Database,
+    verifiers: &Verifiers,
+    finish_task_dto: FinishTaskDTO,
+    timezone: Tz,
+) -> Result<FinishOutcome> {
+    let user: User = _get_user_by_wallet_address(db, &finish_task_dto.wallet)
+        .await?
+        .ok_or(CompletionError::UserNotFound)?
+        .into();
+
+    let task = _get_task_by_id(db, finish_task_dto.task_id)
+        .await?
+        .ok_or(CompletionError::TaskNotFound)?;
+
+    let now = Utc::now();
+    if !task.is_available(now) {
+        return Err(CompletionError::TaskNotAvailable);
+    }
+
+    let period_key = period_key(
+        task.recurrence,
+        task.recurrence_schedule.as_deref(),
+        now,
+        timezone,
+    )?;
+
+    // Both checks run before verifying so external verifiers aren't called for
+    // nothing. Neither can flip back, completions are never taken away.
+    let (locked, completed): (bool, bool) = sqlx::query_as(
+        "SELECT EXISTS (SELECT 1 FROM task_prerequisites JOIN tasks ON tasks.id = task_prerequisites.prerequisite_task_id WHERE task_prerequisites.task_id = $2 AND tasks.archived_at IS NULL AND NOT EXISTS (SELECT 1 FROM task_completions WHERE task_completions.user_id = $1 AND task_completions.task_id = task_prerequisites.prerequisite_task_id)), EXISTS (SELECT 1 FROM task_completions WHERE user_id = $1 AND task_id = $2 AND period_key = $3)",
+    )
+    .bind(user.id)
+    .bind(task.id)
+    .bind(&period_key)
+    .fetch_one(db)
+    .await?;
+    if locked {
+        return Err(CompletionError::TaskLocked);
+    }
+    if completed {
+        return Ok(FinishOutcome::Completed(task.capacity()));
+    }
+
+    let today = now.with_timezone(&timezone).date_naive();
+    let proof = finish_task_dto.proof;
+    if task.task_type == TaskType::Quiz {
+        // Quizzes without stored questions check a single free-text answer instead.
+        let questions = _get_quiz_questions(db, task.id).await?;
+        if !questions.is_empty() {
+            let answers = proof.quiz_answers.unwrap_or_default();
+            return _take_quiz(db, user.id, &task, &questions, &period_key, &answers, today).await;
+        }
+    }
+    if task.requires_review() {
+        let submission_id = _create_submission(db, user.id, task.id, &period_key, &proof).await?;
+        return Ok(FinishOutcome::PendingReview { submission_id });
+    }
+
+    let verifier = verifiers.for_task(&task);
+    let verdict = match verifier.verify(&task, &user, &proof).await {
+        Ok(verdict) => verdict,
+        Err(err) => {
+            let error = err.to_string();
+            _record_verification(
+                db,
+                user.id,
+                task.id,
+                verifier.name(),
+                &Verdict::rejected(error.as_str()),
+                &proof,
+            )
+            .await?;
+            return Err(CompletionError::VerifierFailed { error });
+        }
+    };
+    let verification_id =
+        _record_verification(db, user.id, task.id, verifier.name(), &verdict, &proof).await?;
+    if !verdict.verified {
+        return Err(CompletionError::ProofRejected {
+            reason: verdict
+                .reason
+                .unwrap_or_else(|| "Proof was rejected.".to_string()),
+        });
+    }
+
+    let mut tx = db.begin().await?;
+    let completion = _credit_completion(
+        &mut tx,
+        user.id,
+        &task,
+        &period_key,
+        Some(verification_id),
+        task.points,
+        today,
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(FinishOutcome::Completed(match completion {
+        Some((_, capacity)) => capacity,
+        None => task.capacity(),
+    }))
+}
+
+/// Records the completion and pays out `points`, usually the task points, plus streak
+/// and quest bonuses. Returns the completion id and what is left of the task's caps,
+/// or `None` if the task was already completed in this period.
+pub async fn _credit_completion(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: i32,
+    task: &Task,
+    period_key: &str,
+    verification_id: Option<i32>,
+    points: i32,
+    today: NaiveDate,
+) -> Result<Option<(i32, TaskCapacity)>> {
+    // The unique (user, task, period) constraint makes concurrent attempts safe. The
+    // latest revision is the task as the user saw it, later edits don't change it.
+    let completion: Option<(i32,)> = sqlx::query_as(
+        "INSERT INTO task_completions (user_id, task_id, period_key, points_awarded, verification_id, task_revision_id) VALUES ($1, $2, $3, $4, $5, (SELECT MAX(id) FROM task_revisions WHERE task_id = $2)) ON CONFLICT DO NOTHING RETURNING id",
+    )
+    .bind(user_id)
+    .bind(task.id)
+    .bind(period_key)
+    .bind(points)
+    .bind(verification_id)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let completion_id = match completion {
+        Some((id,)) => id,
+        None => return Ok(None),
+    };
+
+    sqlx::query(
+        "UPDATE users SET finished_tasks = array_append(finished_tasks, $1) WHERE id = $2 AND NOT ($1 = ANY(finished_tasks))",
+    )
+    .bind(task.id)
+    .bind(user_id)
+    .execute(&mut **tx)
+    .await?;
+
+    let mut points = points;
+    if task.task_type == TaskType::CheckIn {
+        points += _apply_check_in(tx, user_id, today).await?;
+
+        sqlx::query("UPDATE task_completions SET points_awarded = $1 WHERE id = $2")
+            .bind(points)
+            .bind(completion_id)
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    let capacity = _reserve_capacity(tx, task.id, points)
+        .await?
+        .ok_or(CompletionError::TaskExhausted)?;
+
+    _credit_points(tx, user_id, points).await?;
+
+    let quest_bonus = _complete_quests(tx, user_id, task.id).await?;
+    if quest_bonus > 0 {
+        _credit_points(tx, user_id, quest_bonus).await?;
+    }
+
+    Ok(Some((completion_id, capacity)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::{
+        db::{_create_task, _create_user, _get_user_by_id},
+        models::{
+            CreateTaskDTO, CreateUserDTO, SolanaAddress, TaskProof, TaskRecurrence, TaskStatus,
+        },
+    };
+    use password_encryptor::PasswordEncryptor;
+    use sqlx::postgres::PgPoolOptions;
+
+    #[tokio::test]
+    async fn test_finish_task_pays_once_per_period_and_credits_referrer() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let suffix = chrono::Local::now().to_string();
+        let create_user = |name: &str, reffer_code: Option<String>| CreateUserDTO {
+            twitter_id: format!("{name}{suffix}"),
+            solana_adr: SolanaAddress::random(),
+            password: "123".to_string(),
+            reffer_code,
+        };
+
+        let referrer = _create_user(
+            &pool,
+            create_user("referrer", None),
+            PasswordEncryptor::new(vec![1, 2, 3], None),
+            "salt",
+        )
+        .await
+        .unwrap();
+        let user = _create_user(
+            &pool,
+            create_user("farmer", Some(referrer.referral_code.clone())),
+            PasswordEncryptor::new(vec![1, 2, 3], None),
+            "salt",
+        )
+        .await
+        .unwrap();
+
+        let task_id = _create_task(
+            &pool,
+            CreateTaskDTO {
+                description: "Daily check in".to_string(),
+                points: 50,
+                task_button_text: "Check in".to_string(),
+                link: None,
+                task_type: None,
+                category: None,
+                icon_url: None,
+                display_order: None,
+                metadata: None,
+                status: None,
+                starts_at: None,
+                ends_at: None,
+                recurrence: Some(TaskRecurrence::Daily),
+                recurrence_schedule: None,
+                max_completions: None,
+                points_budget: None,
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        for _ in 0..2 {
+            _finish_task(
+                &pool,
+                &Verifiers::new(reqwest::Client::new(), None),
+                FinishTaskDTO {
+                    task_id,
+                    wallet: user.wallet_address.parse().unwrap(),
+                    proof: TaskProof::default(),
+                },
+                Tz::UTC,
+            )
+            .await
+            .unwrap();
+        }
+
+        let user = _get_user_by_id(&pool, user.id).await.unwrap().unwrap();
+        assert_eq!(user.total_points, 50);
+        assert_eq!(user.finished_tasks, vec![task_id]);
+
+        let referrer = _get_user_by_id(&pool, referrer.id).await.unwrap().unwrap();
+        assert_eq!(referrer.referral_points, 10);
+        assert_eq!(referrer.total_points, 10);
+    }
+
+    #[tokio::test]
+    async fn test_check_in_pays_streak_bonus() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let suffix = chrono::Local::now().to_string();
+        let user = _create_user(
+            &pool,
+            CreateUserDTO {
+                twitter_id: format!("streaker{suffix}"),
+                solana_adr: SolanaAddress::random(),
+                password: "123".to_string(),
+                reffer_code: None,
+            },
+            PasswordEncryptor::new(vec![1, 2, 3], None),
+            "salt",
+        )
+        .await
+        .unwrap();
+
+        let task_id = _create_task(
+            &pool,
+            CreateTaskDTO {
+                description: "Check in".to_string(),
+                points: 5,
+                task_button_text: "Check in".to_string(),
+                link: None,
+                task_type: Some(TaskType::CheckIn),
+                category: None,
+                icon_url: None,
+                display_order: None,
+                metadata: None,
+                status: None,
+                starts_at: None,
+                ends_at: None,
+                recurrence: Some(TaskRecurrence::Daily),
+                recurrence_schedule: None,
+                max_completions: None,
+                points_budget: None,
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        _finish_task(
+            &pool,
+            &Verifiers::new(reqwest::Client::new(), None),
+            FinishTaskDTO {
+                task_id,
+                wallet: user.wallet_address.parse().unwrap(),
+                proof: TaskProof::default(),
+            },
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        let user = _get_user_by_id(&pool, user.id).await.unwrap().unwrap();
+        assert_eq!(user.current_streak, 1);
+        assert_eq!(user.total_points, 15);
+    }
+
+    #[tokio::test]
+    async fn test_capped_task_closes_when_exhausted() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let suffix = chrono::Local::now().to_string();
+        let mut wallets = vec![];
+        for name in ["first", "second"] {
+            let user = _create_user(
+                &pool,
+                CreateUserDTO {
+                    twitter_id: format!("{name}{suffix}"),
+                    solana_adr: SolanaAddress::random(),
+                    password: "123".to_string(),
+                    reffer_code: None,
+                },
+                PasswordEncryptor::new(vec![1, 2, 3], None),
+                "salt",
+            )
+            .await
+            .unwrap();
+            wallets.push(user.wallet_address);
+        }
+
+        let task_id = _create_task(
+            &pool,
+            CreateTaskDTO {
+                description: "First come, first served".to_string(),
+                points: 30,
+                task_button_text: "Claim".to_string(),
+                link: None,
+                task_type: None,
+                category: None,
+                icon_url: None,
+                display_order: None,
+                metadata: None,
+                status: None,
+                starts_at: None,
+                ends_at: None,
+                recurrence: None,
+                recurrence_schedule: None,
+                max_completions: Some(5),
+                points_budget: Some(50),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        let outcome = _finish_task(
+            &pool,
+            &Verifiers::new(reqwest::Client::new(), None),
+            FinishTaskDTO {
+                task_id,
+                wallet: wallets[0].parse().unwrap(),
+                proof: TaskProof::default(),
+            },
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+        let FinishOutcome::Completed(capacity) = outcome else {
+            panic!("task isn't reviewed by hand");
+        };
+        assert_eq!(capacity.remaining_completions, Some(4));
+        assert_eq!(capacity.remaining_budget, Some(20));
+
+        let result = _finish_task(
+            &pool,
+            &Verifiers::new(reqwest::Client::new(), None),
+            FinishTaskDTO {
+                task_id,
+                wallet: wallets[1].parse().unwrap(),
+                proof: TaskProof::default(),
+            },
+            Tz::UTC,
+        )
+        .await;
+        assert!(matches!(result, Err(CompletionError::TaskNotAvailable)));
+
+        let task = _get_task_by_id(&pool, task_id).await.unwrap().unwrap();
+        assert_eq!(task.status, TaskStatus::Exhausted);
+        assert_eq!(task.completions_count, 1);
+        assert_eq!(task.points_spent, 30);
+    }
+
+    #[tokio::test]
+    async fn test_finish_task_verifies_proof_before_crediting() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let suffix = chrono::Local::now().to_string();
+        let user = _create_user(
+            &pool,
+            CreateUserDTO {
+                twitter_id: format!("prover{suffix}"),
+                solana_adr: SolanaAddress::random(),
+                password: "123".to_string(),
+                reffer_code: None,
+            },
+            PasswordEncryptor::new(vec![1, 2, 3], None),
+            "salt",
+        )
+        .await
+        .unwrap();
+
+        let task_id = _create_task(
+            &pool,
+            CreateTaskDTO {
+                description: "Say the word".to_string(),
+                points: 25,
+                task_button_text: "Submit".to_string(),
+                link: None,
+                task_type: None,
+                category: None,
+                icon_url: None,
+                display_order: None,
+                metadata: Some(serde_json::json!({ "code_word": "ribbit" })),
+                status: None,
+                starts_at: None,
+                ends_at: None,
+                recurrence: None,
+                recurrence_schedule: None,
+                max_completions: None,
+                points_budget: None,
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        for (code, expected_points) in [("croak", 0), ("Ribbit", 25)] {
+            let result = _finish_task(
+                &pool,
+                &Verifiers::new(reqwest::Client::new(), None),
+                FinishTaskDTO {
+                    task_id,
+                    wallet: user.wallet_address.parse().unwrap(),
+                    proof: TaskProof {
+                        code: Some(code.to_string()),
+                        ..Default::default()
+                    },
+                },
+                Tz::UTC,
+            )
+            .await;
+            assert_eq!(result.is_ok(), expected_points > 0);
+
+            let user = _get_user_by_id(&pool, user.id).await.unwrap().unwrap();
+            assert_eq!(user.total_points, expected_points);
+        }
+
+        let verifications: Vec<(String, bool)> = sqlx::query_as(
+            "SELECT verifier, verified FROM task_verifications WHERE task_id = $1 ORDER BY id",
+        )
+        .bind(task_id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            verifications,
+            vec![
+                ("code_word".to_string(), false),
+                ("code_word".to_string(), true)
+            ]
+        );
+    }
+}
@@ -1,5 +1,27 @@
+mod airdrops;
+mod api_clients;
+mod api_keys;
+mod completions;
+mod quests;
+mod quizzes;
+mod snapshots;
+mod submissions;
+mod task_revisions;
 mod tasks;
 mod users;
+mod verifications;
+mod wallets;
 
+pub use airdrops::*;
+pub use api_clients::*;
+pub use api_keys::*;
+pub use completions::*;
+pub use quests::*;
+pub use quizzes::*;
+pub use snapshots::*;
+pub use submissions::*;
+pub use task_revisions::*;
 pub use tasks::*;
 pub use users::*;
+pub use verifications::*;
+pub use wallets::*;
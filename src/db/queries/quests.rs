@@ -0,0 +1,357 @@
+use sqlx::{Postgres, Transaction};
+
+use crate::{
+    db::Database,
+    models::{CreateQuestDTO, Quest, SetPrerequisitesDTO},
+    prerequisites::{chain_edges, has_cycle},
+};
+
+/// Advisory lock held while the prerequisite graph is checked for cycles and written,
+/// so two concurrent changes can't each pass the check and close a cycle together.
+const PREREQUISITES_LOCK: i64 = 0x7072_6572_6571;
+
+const QUEST_SELECT: &str = "SELECT quests.id, quests.name, quests.description, quests.bonus_points, COALESCE(array_agg(quest_tasks.task_id ORDER BY quest_tasks.position) FILTER (WHERE quest_tasks.task_id IS NOT NULL), '{}') AS task_ids FROM quests LEFT JOIN quest_tasks ON quest_tasks.quest_id = quests.id";
+
+pub async fn _get_prerequisite_edges(db: &Database) -> Result<Vec<(i32, i32)>, sqlx::Error> {
+    let edges: Vec<(i32, i32)> =
+        sqlx::query_as("SELECT task_id, prerequisite_task_id FROM task_prerequisites")
+            .fetch_all(db)
+            .await?;
+    Ok(edges)
+}
+
+/// Takes the prerequisites lock for the rest of the transaction and reads the graph.
+async fn lock_prerequisite_edges(
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<Vec<(i32, i32)>, sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(PREREQUISITES_LOCK)
+        .execute(&mut **tx)
+        .await?;
+    let edges: Vec<(i32, i32)> =
+        sqlx::query_as("SELECT task_id, prerequisite_task_id FROM task_prerequisites")
+            .fetch_all(&mut **tx)
+            .await?;
+    Ok(edges)
+}
+
+/// Tasks the user can't complete yet because a prerequisite was never completed.
+/// Archived prerequisites keep their edges for a restore but no longer lock anything.
+pub async fn _get_locked_task_ids(db: &Database, user_id: i32) -> Result<Vec<i32>, sqlx::Error> {
+    let rows: Vec<(i32,)> = sqlx::query_as(
+        "SELECT DISTINCT task_prerequisites.task_id FROM task_prerequisites JOIN tasks ON tasks.id = task_prerequisites.prerequisite_task_id WHERE tasks.archived_at IS NULL AND NOT EXISTS (SELECT 1 FROM task_completions WHERE task_completions.user_id = $1 AND task_completions.task_id = task_prerequisites.prerequisite_task_id)",
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+    Ok(rows.into_iter().map(|row| row.0).collect())
+}
+
+async fn insert_prerequisites(
+    tx: &mut Transaction<'_, Postgres>,
+    edges: &[(i32, i32)],
+) -> Result<(), sqlx::Error> {
+    for &(task_id, prerequisite_task_id) in edges {
+        sqlx::query(
+            "INSERT INTO task_prerequisites (task_id, prerequisite_task_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        )
+        .bind(task_id)
+        .bind(prerequisite_task_id)
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Replaces the prerequisites of a task. Returns `Ok(false)` and changes nothing if
+/// the new edges would form a cycle.
+pub async fn _set_task_prerequisites(
+    db: &Database,
+    set_prerequisites_dto: SetPrerequisitesDTO,
+) -> Result<bool, sqlx::Error> {
+    let task_id = set_prerequisites_dto.task_id;
+    let new_edges: Vec<(i32, i32)> = set_prerequisites_dto
+        .prerequisite_task_ids
+        .iter()
+        .map(|&prerequisite_task_id| (task_id, prerequisite_task_id))
+        .collect();
+
+    let mut tx = db.begin().await?;
+    let mut edges: Vec<(i32, i32)> = lock_prerequisite_edges(&mut tx)
+        .await?
+        .into_iter()
+        .filter(|edge| edge.0 != task_id)
+        .collect();
+    edges.extend(&new_edges);
+    if has_cycle(&edges) {
+        return Ok(false);
+    }
+
+    sqlx::query("DELETE FROM task_prerequisites WHERE task_id = $1")
+        .bind(task_id)
+        .execute(&mut *tx)
+        .await?;
+    insert_prerequisites(&mut tx, &new_edges).await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Creates a quest, chaining its tasks when it's sequential. Returns `Ok(None)` if
+/// chaining would form a prerequisite cycle.
+pub async fn _create_quest(
+    db: &Database,
+    create_quest_dto: CreateQuestDTO,
+) -> Result<Option<i32>, sqlx::Error> {
+    let new_edges = if create_quest_dto.sequential.unwrap_or_default() {
+        chain_edges(&create_quest_dto.task_ids)
+    } else {
+        vec![]
+    };
+
+    let mut tx = db.begin().await?;
+    let mut edges = lock_prerequisite_edges(&mut tx).await?;
+    edges.extend(&new_edges);
+    if has_cycle(&edges) {
+        return Ok(None);
+    }
+
+    let (quest_id,): (i32,) = sqlx::query_as(
+        "INSERT INTO quests (name, description, bonus_points) VALUES ($1, $2, $3) RETURNING id",
+    )
+    .bind(create_quest_dto.name)
+    .bind(create_quest_dto.description)
+    .bind(create_quest_dto.bonus_points)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    for (position, task_id) in create_quest_dto.task_ids.iter().enumerate() {
+        sqlx::query("INSERT INTO quest_tasks (quest_id, task_id, position) VALUES ($1, $2, $3)")
+            .bind(quest_id)
+            .bind(task_id)
+            .bind(position as i32)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    insert_prerequisites(&mut tx, &new_edges).await?;
+    tx.commit().await?;
+    Ok(Some(quest_id))
+}
+
+pub async fn _get_quests(db: &Database) -> Result<Vec<Quest>, sqlx::Error> {
+    let quests: Vec<Quest> = sqlx::query_as(&format!(
+        "{QUEST_SELECT} GROUP BY quests.id ORDER BY quests.id"
+    ))
+    .fetch_all(db)
+    .await?;
+    Ok(quests)
+}
+
+pub async fn _delete_quest(db: &Database, quest_id: i32) -> Result<(), sqlx::Error> {
+    let result = sqlx::query("DELETE FROM quests WHERE id = $1")
+        .bind(quest_id)
+        .execute(db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::{
+        db::{
+            _create_task, _create_user, _delete_task, _finish_task, _get_user_by_id,
+            CompletionError,
+        },
+        models::{
+            CreateTaskDTO, CreateUserDTO, DeleteTaskDTO, FinishTaskDTO, SolanaAddress, TaskProof,
+        },
+        verification::Verifiers,
+    };
+    use chrono_tz::Tz;
+    use password_encryptor::PasswordEncryptor;
+    use sqlx::postgres::PgPoolOptions;
+
+    async fn create_task(db: &Database, description: &str, points: i32) -> i32 {
+        _create_task(
+            db,
+            CreateTaskDTO {
+                description: description.to_string(),
+                points,
+                task_button_text: "Go".to_string(),
+                link: None,
+                task_type: None,
+                category: None,
+                icon_url: None,
+                display_order: None,
+                metadata: None,
+                status: None,
+                starts_at: None,
+                ends_at: None,
+                recurrence: None,
+                recurrence_schedule: None,
+                max_completions: None,
+                points_budget: None,
+            },
+            None,
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn finish(db: &Database, task_id: i32, wallet: &str) -> Result<(), CompletionError> {
+        _finish_task(
+            db,
+            &Verifiers::new(reqwest::Client::new(), None),
+            FinishTaskDTO {
+                task_id,
+                wallet: wallet.parse().unwrap(),
+                proof: TaskProof::default(),
+            },
+            Tz::UTC,
+        )
+        .await
+        .map(|_| ())
+    }
+
+    #[tokio::test]
+    async fn test_sequential_quest_locks_tasks_and_pays_bonus() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let suffix = chrono::Local::now().to_string();
+        let user = _create_user(
+            &pool,
+            CreateUserDTO {
+                twitter_id: format!("quester{suffix}"),
+                solana_adr: SolanaAddress::random(),
+                password: "123".to_string(),
+                reffer_code: None,
+            },
+            PasswordEncryptor::new(vec![1, 2, 3], None),
+            "salt",
+        )
+        .await
+        .unwrap();
+
+        let first = create_task(&pool, "Follow", 10).await;
+        let second = create_task(&pool, "Retweet", 20).await;
+        let quest_id = _create_quest(
+            &pool,
+            CreateQuestDTO {
+                name: format!("Onboarding {suffix}"),
+                description: None,
+                bonus_points: 100,
+                task_ids: vec![first, second],
+                sequential: Some(true),
+            },
+        )
+        .await
+        .unwrap();
+        assert!(quest_id.is_some());
+
+        // Going back around the chain would close a cycle.
+        let changed = _set_task_prerequisites(
+            &pool,
+            SetPrerequisitesDTO {
+                task_id: first,
+                prerequisite_task_ids: vec![second],
+            },
+        )
+        .await
+        .unwrap();
+        assert!(!changed);
+
+        assert!(_get_locked_task_ids(&pool, user.id)
+            .await
+            .unwrap()
+            .contains(&second));
+        let result = finish(&pool, second, &user.wallet_address).await;
+        assert!(matches!(result, Err(CompletionError::TaskLocked)));
+
+        finish(&pool, first, &user.wallet_address).await.unwrap();
+        assert!(!_get_locked_task_ids(&pool, user.id)
+            .await
+            .unwrap()
+            .contains(&second));
+        finish(&pool, second, &user.wallet_address).await.unwrap();
+
+        let user = _get_user_by_id(&pool, user.id).await.unwrap().unwrap();
+        assert_eq!(user.total_points, 130);
+    }
+
+    #[tokio::test]
+    async fn test_archived_task_no_longer_blocks_quest() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let suffix = chrono::Local::now().to_string();
+        let user = _create_user(
+            &pool,
+            CreateUserDTO {
+                twitter_id: format!("archivist{suffix}"),
+                solana_adr: SolanaAddress::random(),
+                password: "123".to_string(),
+                reffer_code: None,
+            },
+            PasswordEncryptor::new(vec![1, 2, 3], None),
+            "salt",
+        )
+        .await
+        .unwrap();
+
+        let first = create_task(&pool, "Follow", 10).await;
+        let second = create_task(&pool, "Retweet", 20).await;
+        let third = create_task(&pool, "Reply", 30).await;
+        _create_quest(
+            &pool,
+            CreateQuestDTO {
+                name: format!("Engagement {suffix}"),
+                description: None,
+                bonus_points: 100,
+                task_ids: vec![first, second, third],
+                sequential: Some(true),
+            },
+        )
+        .await
+        .unwrap();
+
+        finish(&pool, first, &user.wallet_address).await.unwrap();
+        _delete_task(&pool, DeleteTaskDTO { task_id: second }, None)
+            .await
+            .unwrap();
+
+        // The archived prerequisite neither locks the next task nor holds up the bonus.
+        assert!(!_get_locked_task_ids(&pool, user.id)
+            .await
+            .unwrap()
+            .contains(&third));
+        finish(&pool, third, &user.wallet_address).await.unwrap();
+
+        let user = _get_user_by_id(&pool, user.id).await.unwrap().unwrap();
+        assert_eq!(user.total_points, 140);
+    }
+}
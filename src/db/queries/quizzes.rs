@@ -0,0 +1,312 @@
+use chrono::NaiveDate;
+
+use crate::{
+    db::Database,
+    models::{
+        CreateQuizQuestionDTO, PutQuizQuestionDTO, QuizAnswer, QuizQuestion, QuizScore, Task,
+    },
+    quiz::{grade, max_attempts, passed, scaled_points},
+};
+
+use super::{_credit_completion, CompletionError, FinishOutcome};
+
+const QUESTION_COLUMNS: &str = "id, task_id, question, options, correct_option, position";
+
+pub async fn _get_quiz_questions(
+    db: &Database,
+    task_id: i32,
+) -> Result<Vec<QuizQuestion>, sqlx::Error> {
+    let questions: Vec<QuizQuestion> = sqlx::query_as(&format!(
+        "SELECT {QUESTION_COLUMNS} FROM quiz_questions WHERE task_id = $1 ORDER BY position, id"
+    ))
+    .bind(task_id)
+    .fetch_all(db)
+    .await?;
+    Ok(questions)
+}
+
+pub async fn _create_quiz_question(
+    db: &Database,
+    create_quiz_question_dto: CreateQuizQuestionDTO,
+) -> Result<i32, sqlx::Error> {
+    let (id,): (i32,) = sqlx::query_as(
+        "INSERT INTO quiz_questions (task_id, question, options, correct_option, position) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+    )
+    .bind(create_quiz_question_dto.task_id)
+    .bind(create_quiz_question_dto.question)
+    .bind(create_quiz_question_dto.options)
+    .bind(create_quiz_question_dto.correct_option)
+    .bind(create_quiz_question_dto.position.unwrap_or_default())
+    .fetch_one(db)
+    .await?;
+    Ok(id)
+}
+
+pub async fn _get_quiz_question_by_id(
+    db: &Database,
+    question_id: i32,
+) -> Result<Option<QuizQuestion>, sqlx::Error> {
+    let question: Option<QuizQuestion> = sqlx::query_as(&format!(
+        "SELECT {QUESTION_COLUMNS} FROM quiz_questions WHERE id = $1"
+    ))
+    .bind(question_id)
+    .fetch_optional(db)
+    .await?;
+    Ok(question)
+}
+
+/// Saves the merged question, the caller validates it first.
+pub async fn _put_quiz_question(
+    db: &Database,
+    question: &QuizQuestion,
+    put_quiz_question_dto: PutQuizQuestionDTO,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE quiz_questions SET question = $1, options = $2, correct_option = $3, position = $4 WHERE id = $5",
+    )
+    .bind(
+        put_quiz_question_dto
+            .question
+            .unwrap_or_else(|| question.question.clone()),
+    )
+    .bind(
+        put_quiz_question_dto
+            .options
+            .unwrap_or_else(|| question.options.clone()),
+    )
+    .bind(
+        put_quiz_question_dto
+            .correct_option
+            .unwrap_or(question.correct_option),
+    )
+    .bind(put_quiz_question_dto.position.unwrap_or(question.position))
+    .bind(question.id)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+pub async fn _delete_quiz_question(db: &Database, question_id: i32) -> Result<(), sqlx::Error> {
+    let result = sqlx::query("DELETE FROM quiz_questions WHERE id = $1")
+        .bind(question_id)
+        .execute(db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    Ok(())
+}
+
+/// Grades an attempt and credits points scaled by the score if it passed. Every
+/// attempt counts towards the task's `max_attempts` for the period, passed or not.
+pub async fn _take_quiz(
+    db: &Database,
+    user_id: i32,
+    task: &Task,
+    questions: &[QuizQuestion],
+    period_key: &str,
+    answers: &[QuizAnswer],
+    today: NaiveDate,
+) -> Result<FinishOutcome, CompletionError> {
+    let mut tx = db.begin().await?;
+
+    // Serializes attempts of the same user so the limit holds.
+    sqlx::query("SELECT id FROM users WHERE id = $1 FOR UPDATE")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let (attempts,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM quiz_attempts WHERE user_id = $1 AND task_id = $2 AND period_key = $3",
+    )
+    .bind(user_id)
+    .bind(task.id)
+    .bind(period_key)
+    .fetch_one(&mut *tx)
+    .await?;
+    let attempts_left = max_attempts(task) - attempts;
+    if attempts_left <= 0 {
+        return Err(CompletionError::NoAttemptsLeft);
+    }
+
+    let score: QuizScore = grade(questions, answers);
+    sqlx::query(
+        "INSERT INTO quiz_attempts (user_id, task_id, period_key, correct, total, answers) VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(user_id)
+    .bind(task.id)
+    .bind(period_key)
+    .bind(score.correct)
+    .bind(score.total)
+    .bind(sqlx::types::Json(answers))
+    .execute(&mut *tx)
+    .await?;
+
+    if !passed(task, score) {
+        tx.commit().await?;
+        return Err(CompletionError::ProofRejected {
+            reason: format!(
+                "Scored {} of {}, {} attempts left.",
+                score.correct,
+                score.total,
+                attempts_left - 1
+            ),
+        });
+    }
+
+    let points = scaled_points(task.points, score);
+    let completion =
+        _credit_completion(&mut tx, user_id, task, period_key, None, points, today).await?;
+    tx.commit().await?;
+
+    Ok(FinishOutcome::QuizPassed {
+        score,
+        points,
+        capacity: match completion {
+            Some((_, capacity)) => capacity,
+            None => task.capacity(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::{
+        db::{_create_task, _create_user, _finish_task, _get_user_by_id},
+        models::{CreateTaskDTO, CreateUserDTO, FinishTaskDTO, SolanaAddress, TaskProof, TaskType},
+        verification::Verifiers,
+    };
+    use chrono_tz::Tz;
+    use password_encryptor::PasswordEncryptor;
+    use sqlx::postgres::PgPoolOptions;
+
+    #[tokio::test]
+    async fn test_take_quiz() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let suffix = chrono::Local::now().to_string();
+        let user = _create_user(
+            &pool,
+            CreateUserDTO {
+                twitter_id: format!("quizzer{suffix}"),
+                solana_adr: SolanaAddress::random(),
+                password: "123".to_string(),
+                reffer_code: None,
+            },
+            PasswordEncryptor::new(vec![1, 2, 3], None),
+            "salt",
+        )
+        .await
+        .unwrap();
+
+        let task_id = _create_task(
+            &pool,
+            CreateTaskDTO {
+                description: "Frog trivia".to_string(),
+                points: 90,
+                task_button_text: "Answer".to_string(),
+                link: None,
+                task_type: Some(TaskType::Quiz),
+                category: None,
+                icon_url: None,
+                display_order: None,
+                metadata: Some(serde_json::json!({ "max_attempts": 2 })),
+                status: None,
+                starts_at: None,
+                ends_at: None,
+                recurrence: None,
+                recurrence_schedule: None,
+                max_completions: None,
+                points_budget: None,
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        let mut question_ids = vec![];
+        for correct_option in [0, 1, 2] {
+            question_ids.push(
+                _create_quiz_question(
+                    &pool,
+                    CreateQuizQuestionDTO {
+                        task_id,
+                        question: format!("Question {correct_option}"),
+                        options: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+                        correct_option,
+                        position: None,
+                    },
+                )
+                .await
+                .unwrap(),
+            );
+        }
+
+        let verifiers = Verifiers::new(reqwest::Client::new(), None);
+        let answer = |options: [i32; 3]| {
+            _finish_task(
+                &pool,
+                &verifiers,
+                FinishTaskDTO {
+                    task_id,
+                    wallet: user.wallet_address.parse().unwrap(),
+                    proof: TaskProof {
+                        quiz_answers: Some(
+                            question_ids
+                                .iter()
+                                .zip(options)
+                                .map(|(&question_id, option)| QuizAnswer {
+                                    question_id,
+                                    option,
+                                })
+                                .collect(),
+                        ),
+                        ..Default::default()
+                    },
+                },
+                Tz::UTC,
+            )
+        };
+
+        assert!(matches!(
+            answer([2, 2, 0]).await,
+            Err(CompletionError::ProofRejected { .. })
+        ));
+        match answer([0, 1, 0]).await.unwrap() {
+            FinishOutcome::QuizPassed { score, points, .. } => {
+                assert_eq!(
+                    score,
+                    QuizScore {
+                        correct: 2,
+                        total: 3
+                    }
+                );
+                assert_eq!(points, 60);
+            }
+            _ => panic!("quiz wasn't graded"),
+        }
+
+        let user = _get_user_by_id(&pool, user.id).await.unwrap().unwrap();
+        assert_eq!(user.total_points, 60);
+        assert_eq!(user.finished_tasks, vec![task_id]);
+        // A passed quiz isn't graded again.
+        assert!(matches!(
+            answer([0, 1, 2]).await,
+            Ok(FinishOutcome::Completed(_))
+        ));
+    }
+}
@@ -0,0 +1,333 @@
+use futures_util::{stream, Stream};
+use sqlx::PgConnection;
+
+use crate::{
+    db::Database,
+    models::{Snapshot, SnapshotEntry, WalletChain},
+    snapshots::{content_hash, SnapshotParameters},
+};
+
+const SNAPSHOT_COLUMNS: &str =
+    "id, chain, parameters, content_hash, num_entries, total_points, created_by, created_at";
+/// Live entries after user `$1` with their account wallet, up to `$2` of them. Points
+/// are `POINTS_FORMULA`, `referral_points` is already part of `total_points`, it only
+/// tracks how much of it came from referrals.
+const ACCOUNT_WALLET_PAGE: &str = "SELECT users.id AS user_id, users.twitter_id, users.wallet_address, users.total_points::BIGINT * users.multiplier AS points FROM users WHERE users.id > $1 ORDER BY users.id LIMIT $2";
+/// Same as `ACCOUNT_WALLET_PAGE` with the wallet linked for chain `$3`, users without
+/// one are left out.
+const CHAIN_WALLET_PAGE: &str = "SELECT users.id AS user_id, users.twitter_id, user_wallets.address AS wallet_address, users.total_points::BIGINT * users.multiplier AS points FROM users JOIN user_wallets ON user_wallets.user_id = users.id AND user_wallets.chain = $3 WHERE users.id > $1 ORDER BY users.id LIMIT $2";
+/// Users read per page.
+const SNAPSHOT_PAGE_SIZE: i64 = 1000;
+/// Entries inserted per statement.
+const ENTRY_BATCH_SIZE: usize = 5000;
+
+/// The page of live entries after `last_user_id`, see `ACCOUNT_WALLET_PAGE`.
+async fn snapshot_page(
+    conn: &mut PgConnection,
+    chain: Option<WalletChain>,
+    last_user_id: i32,
+) -> Result<Vec<SnapshotEntry>, sqlx::Error> {
+    let query = match chain {
+        Some(chain) => sqlx::query_as(CHAIN_WALLET_PAGE)
+            .bind(last_user_id)
+            .bind(SNAPSHOT_PAGE_SIZE)
+            .bind(chain),
+        None => sqlx::query_as(ACCOUNT_WALLET_PAGE)
+            .bind(last_user_id)
+            .bind(SNAPSHOT_PAGE_SIZE),
+    };
+    query.fetch_all(conn).await
+}
+
+/// Where the page after `entries` starts, `None` once it was the last one.
+fn next_page(entries: &[SnapshotEntry]) -> Option<i32> {
+    match entries.last() {
+        Some(entry) if entries.len() as i64 == SNAPSHOT_PAGE_SIZE => Some(entry.user_id),
+        _ => None,
+    }
+}
+
+/// Live points of every user ordered by user, read in pages so the whole user base is
+/// never held at once. With a chain the wallet linked for it is used and users without
+/// one are left out, otherwise the account wallet.
+///
+/// Each page takes a pool connection only while it runs, so a slow download doesn't
+/// hold one, and sees the users as they are then. Anything stored is built from
+/// `_compute_snapshot` instead.
+pub fn _stream_snapshot(
+    db: Database,
+    chain: Option<WalletChain>,
+) -> impl Stream<Item = Result<Vec<SnapshotEntry>, sqlx::Error>> + Send + 'static {
+    stream::try_unfold(Some(0), move |last_user_id: Option<i32>| {
+        let db = db.clone();
+        async move {
+            let Some(last_user_id) = last_user_id else {
+                return Ok(None);
+            };
+            let mut conn = db.acquire().await?;
+            let entries = snapshot_page(&mut conn, chain, last_user_id).await?;
+            if entries.is_empty() {
+                return Ok(None);
+            }
+            let next = next_page(&entries);
+            Ok(Some((entries, next)))
+        }
+    })
+}
+
+/// The entries of `_stream_snapshot` as of one moment. Every page is read in a single
+/// `REPEATABLE READ` transaction, so a frozen snapshot or an airdrop never mixes points
+/// or wallets from before and after a concurrent change.
+pub async fn _compute_snapshot(
+    db: &Database,
+    chain: Option<WalletChain>,
+) -> Result<Vec<SnapshotEntry>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut *tx)
+        .await?;
+
+    let mut entries = vec![];
+    let mut last_user_id = Some(0);
+    while let Some(after) = last_user_id {
+        let page = snapshot_page(&mut tx, chain, after).await?;
+        last_user_id = next_page(&page);
+        entries.extend(page);
+    }
+
+    tx.commit().await?;
+    Ok(entries)
+}
+
+/// Stores the entries as they are now so the distribution can be reproduced later.
+pub async fn _freeze_snapshot(
+    db: &Database,
+    parameters: &SnapshotParameters,
+    entries: &[SnapshotEntry],
+    created_by: Option<i32>,
+) -> Result<Snapshot, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let snapshot: Snapshot = sqlx::query_as(&format!(
+        "INSERT INTO snapshots (chain, parameters, content_hash, num_entries, total_points, created_by) VALUES ($1, $2, $3, $4, $5, $6) RETURNING {SNAPSHOT_COLUMNS}"
+    ))
+    .bind(parameters.chain)
+    .bind(serde_json::to_value(parameters).unwrap_or_default())
+    .bind(content_hash(entries))
+    .bind(entries.len() as i32)
+    .bind(entries.iter().map(|entry| entry.points).sum::<i64>())
+    .bind(created_by)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    for batch in entries.chunks(ENTRY_BATCH_SIZE) {
+        let user_ids: Vec<i32> = batch.iter().map(|entry| entry.user_id).collect();
+        let twitter_ids: Vec<&str> = batch
+            .iter()
+            .map(|entry| entry.twitter_id.as_str())
+            .collect();
+        let wallets: Vec<&str> = batch
+            .iter()
+            .map(|entry| entry.wallet_address.as_str())
+            .collect();
+        let points: Vec<i64> = batch.iter().map(|entry| entry.points).collect();
+
+        sqlx::query(
+            "INSERT INTO snapshot_entries (snapshot_id, user_id, twitter_id, wallet_address, points) SELECT $1, * FROM UNNEST($2::INTEGER[], $3::VARCHAR[], $4::VARCHAR[], $5::BIGINT[])",
+        )
+        .bind(snapshot.id)
+        .bind(user_ids)
+        .bind(twitter_ids)
+        .bind(wallets)
+        .bind(points)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(snapshot)
+}
+
+/// Newest first.
+pub async fn _get_snapshots(db: &Database) -> Result<Vec<Snapshot>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {SNAPSHOT_COLUMNS} FROM snapshots ORDER BY id DESC"
+    ))
+    .fetch_all(db)
+    .await
+}
+
+pub async fn _get_snapshot(
+    db: &Database,
+    snapshot_id: i32,
+) -> Result<Option<Snapshot>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {SNAPSHOT_COLUMNS} FROM snapshots WHERE id = $1"
+    ))
+    .bind(snapshot_id)
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn _get_snapshot_entries(
+    db: &Database,
+    snapshot_id: i32,
+) -> Result<Vec<SnapshotEntry>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT user_id, twitter_id, wallet_address, points FROM snapshot_entries WHERE snapshot_id = $1 ORDER BY user_id",
+    )
+    .bind(snapshot_id)
+    .fetch_all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use futures_util::TryStreamExt;
+
+    use super::*;
+    use crate::{
+        db::{_create_user, _link_wallet, _set_user_multiplier},
+        models::{CreateUserDTO, EvmAddress, SolanaAddress},
+        snapshots::{diff, POINTS_FORMULA},
+    };
+    use password_encryptor::PasswordEncryptor;
+    use sqlx::postgres::PgPoolOptions;
+
+    #[tokio::test]
+    async fn test_freeze_and_diff() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let suffix = chrono::Local::now().to_string();
+        let user = _create_user(
+            &pool,
+            CreateUserDTO {
+                twitter_id: format!("freezer{suffix}"),
+                reffer_code: None,
+                solana_adr: SolanaAddress::random(),
+                password: "123".to_string(),
+            },
+            PasswordEncryptor::new(vec![1, 2, 3], None),
+            "salt",
+        )
+        .await
+        .unwrap();
+        sqlx::query("UPDATE users SET total_points = 50 WHERE id = $1")
+            .bind(user.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let parameters = SnapshotParameters {
+            chain: None,
+            formula: POINTS_FORMULA.to_string(),
+        };
+        let entries = _compute_snapshot(&pool, None).await.unwrap();
+        let first = _freeze_snapshot(&pool, &parameters, &entries, None)
+            .await
+            .unwrap();
+        assert_eq!(first.num_entries as usize, entries.len());
+        assert_eq!(first.content_hash, content_hash(&entries));
+
+        let stored = _get_snapshot_entries(&pool, first.id).await.unwrap();
+        assert_eq!(stored, entries);
+        assert_eq!(
+            _get_snapshot(&pool, first.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .parameters,
+            serde_json::to_value(&parameters).unwrap()
+        );
+
+        _set_user_multiplier(&pool, user.id, 3).await.unwrap();
+        let entries = _compute_snapshot(&pool, None).await.unwrap();
+        let second = _freeze_snapshot(&pool, &parameters, &entries, None)
+            .await
+            .unwrap();
+        assert_ne!(first.content_hash, second.content_hash);
+        assert_eq!(_get_snapshots(&pool).await.unwrap()[0].id, second.id);
+
+        let diff = diff(
+            &_get_snapshot_entries(&pool, first.id).await.unwrap(),
+            &_get_snapshot_entries(&pool, second.id).await.unwrap(),
+        );
+        let change = diff
+            .changed
+            .iter()
+            .find(|change| change.user_id == user.id)
+            .unwrap();
+        assert_eq!((change.points_from, change.points_to), (50, 150));
+    }
+
+    #[tokio::test]
+    async fn test_stream_snapshot_for_chain() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let suffix = chrono::Local::now().to_string();
+        let user = _create_user(
+            &pool,
+            CreateUserDTO {
+                twitter_id: format!("streamer{suffix}"),
+                reffer_code: None,
+                solana_adr: SolanaAddress::random(),
+                password: "123".to_string(),
+            },
+            PasswordEncryptor::new(vec![1, 2, 3], None),
+            "salt",
+        )
+        .await
+        .unwrap();
+        let mut address = [0u8; 20];
+        address[..4].copy_from_slice(&user.id.to_be_bytes());
+        address[4] = 0x5a;
+        let address = EvmAddress::from_bytes(address);
+        _link_wallet(&pool, user.id, WalletChain::Base, address.as_str())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let batches: Vec<Vec<SnapshotEntry>> =
+            _stream_snapshot(pool.clone(), Some(WalletChain::Base))
+                .try_collect()
+                .await
+                .unwrap();
+        assert!(batches
+            .iter()
+            .all(|batch| !batch.is_empty() && batch.len() as i64 <= SNAPSHOT_PAGE_SIZE));
+        let entries: Vec<SnapshotEntry> = batches.into_iter().flatten().collect();
+        assert!(entries.windows(2).all(|w| w[0].user_id < w[1].user_id));
+        let entry = entries
+            .iter()
+            .find(|entry| entry.user_id == user.id)
+            .unwrap();
+        assert_eq!(entry.wallet_address, address.as_str());
+
+        let entries = _compute_snapshot(&pool, None).await.unwrap();
+        let entry = entries
+            .iter()
+            .find(|entry| entry.user_id == user.id)
+            .unwrap();
+        assert_eq!(entry.wallet_address, user.wallet_address);
+    }
+}
@@ -0,0 +1,295 @@
+use chrono_tz::Tz;
+
+use crate::{
+    db::Database,
+    models::{SubmissionFilterDTO, SubmissionStatus, TaskProof, TaskSubmission},
+};
+
+use super::{_credit_completion, _get_task_by_id, CompletionError};
+
+const SUBMISSION_COLUMNS: &str = "id, user_id, task_id, period_key, status, url, text, screenshot_url, reason, reviewed_by, reviewed_at, created_at";
+
+/// Queues a submission for review. Submitting again while one is pending for the
+/// same period returns the pending one.
+pub async fn _create_submission(
+    db: &Database,
+    user_id: i32,
+    task_id: i32,
+    period_key: &str,
+    proof: &TaskProof,
+) -> Result<i32, CompletionError> {
+    if proof.url.is_none() && proof.text.is_none() && proof.screenshot_url.is_none() {
+        return Err(CompletionError::ProofRejected {
+            reason: "A submission needs a URL, text or screenshot.".to_string(),
+        });
+    }
+
+    let inserted: Option<(i32,)> = sqlx::query_as(
+        "INSERT INTO task_submissions (user_id, task_id, period_key, url, text, screenshot_url) VALUES ($1, $2, $3, $4, $5, $6) ON CONFLICT (user_id, task_id, period_key) WHERE status = 'pending' DO NOTHING RETURNING id",
+    )
+    .bind(user_id)
+    .bind(task_id)
+    .bind(period_key)
+    .bind(&proof.url)
+    .bind(&proof.text)
+    .bind(&proof.screenshot_url)
+    .fetch_optional(db)
+    .await?;
+    if let Some((id,)) = inserted {
+        return Ok(id);
+    }
+
+    let (id,): (i32,) = sqlx::query_as(
+        "SELECT id FROM task_submissions WHERE user_id = $1 AND task_id = $2 AND period_key = $3 AND status = 'pending'",
+    )
+    .bind(user_id)
+    .bind(task_id)
+    .bind(period_key)
+    .fetch_one(db)
+    .await?;
+    Ok(id)
+}
+
+/// Oldest first, so the queue is worked through in order.
+pub async fn _get_submissions(
+    db: &Database,
+    filter: SubmissionFilterDTO,
+) -> Result<Vec<TaskSubmission>, sqlx::Error> {
+    let submissions: Vec<TaskSubmission> = sqlx::query_as(&format!(
+        "SELECT {SUBMISSION_COLUMNS} FROM task_submissions WHERE ($1::submission_status IS NULL OR status = $1) AND ($2::integer IS NULL OR task_id = $2) ORDER BY created_at, id"
+    ))
+    .bind(filter.status)
+    .bind(filter.task_id)
+    .fetch_all(db)
+    .await?;
+    Ok(submissions)
+}
+
+pub async fn _get_user_submissions(
+    db: &Database,
+    user_id: i32,
+) -> Result<Vec<TaskSubmission>, sqlx::Error> {
+    let submissions: Vec<TaskSubmission> = sqlx::query_as(&format!(
+        "SELECT {SUBMISSION_COLUMNS} FROM task_submissions WHERE user_id = $1 ORDER BY created_at DESC, id DESC"
+    ))
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+    Ok(submissions)
+}
+
+/// Approves a pending submission and credits it like any other completion, in the
+/// period it was submitted in. `reviewed_by` is the reviewer's api key, `None` for the
+/// dev secret.
+pub async fn _approve_submission(
+    db: &Database,
+    submission_id: i32,
+    reviewed_by: Option<i32>,
+    timezone: Tz,
+) -> Result<(), CompletionError> {
+    let mut tx = db.begin().await?;
+
+    let submission: TaskSubmission = sqlx::query_as(&format!(
+        "SELECT {SUBMISSION_COLUMNS} FROM task_submissions WHERE id = $1 AND status = 'pending' FOR UPDATE"
+    ))
+    .bind(submission_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(CompletionError::SubmissionNotFound)?;
+
+    let task = _get_task_by_id(db, submission.task_id)
+        .await?
+        .ok_or(CompletionError::TaskNotFound)?;
+
+    let today = submission.created_at.with_timezone(&timezone).date_naive();
+    let completion = _credit_completion(
+        &mut tx,
+        submission.user_id,
+        &task,
+        &submission.period_key,
+        None,
+        task.points,
+        today,
+    )
+    .await?;
+
+    sqlx::query(
+        "UPDATE task_submissions SET status = $1, reviewed_by = $2, reviewed_at = NOW(), completion_id = $3 WHERE id = $4",
+    )
+    .bind(SubmissionStatus::Approved)
+    .bind(reviewed_by)
+    .bind(completion.map(|(completion_id, _)| completion_id))
+    .bind(submission_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Rejects a pending submission. Returns `false` if there is no such submission.
+pub async fn _reject_submission(
+    db: &Database,
+    submission_id: i32,
+    reviewed_by: Option<i32>,
+    reason: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE task_submissions SET status = $1, reviewed_by = $2, reviewed_at = NOW(), reason = $3 WHERE id = $4 AND status = 'pending'",
+    )
+    .bind(SubmissionStatus::Rejected)
+    .bind(reviewed_by)
+    .bind(reason)
+    .bind(submission_id)
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::db::FinishOutcome;
+    use crate::{
+        db::{_create_api_key, _create_task, _create_user, _finish_task, _get_user_by_id},
+        models::{
+            ApiScope, CreateApiKeyDTO, CreateTaskDTO, CreateUserDTO, FinishTaskDTO, SolanaAddress,
+        },
+        verification::Verifiers,
+    };
+    use password_encryptor::PasswordEncryptor;
+    use sqlx::postgres::PgPoolOptions;
+
+    #[tokio::test]
+    async fn test_review_queue() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let suffix = chrono::Local::now().to_string();
+        let user = _create_user(
+            &pool,
+            CreateUserDTO {
+                twitter_id: format!("memer{suffix}"),
+                solana_adr: SolanaAddress::random(),
+                password: "123".to_string(),
+                reffer_code: None,
+            },
+            PasswordEncryptor::new(vec![1, 2, 3], None),
+            "salt",
+        )
+        .await
+        .unwrap();
+
+        let reviewer = _create_api_key(
+            &pool,
+            CreateApiKeyDTO {
+                name: "reviewer".to_string(),
+                scopes: vec![ApiScope::SubmissionsReview],
+                rate_limit: None,
+                expires_at: None,
+            },
+            "fk_test",
+            &format!("hash{suffix}"),
+        )
+        .await
+        .unwrap();
+
+        let task_id = _create_task(
+            &pool,
+            CreateTaskDTO {
+                description: "Post a meme".to_string(),
+                points: 40,
+                task_button_text: "Submit".to_string(),
+                link: None,
+                task_type: None,
+                category: None,
+                icon_url: None,
+                display_order: None,
+                metadata: Some(serde_json::json!({ "manual_review": true })),
+                status: None,
+                starts_at: None,
+                ends_at: None,
+                recurrence: None,
+                recurrence_schedule: None,
+                max_completions: None,
+                points_budget: None,
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        let verifiers = Verifiers::new(reqwest::Client::new(), None);
+        let submit = || {
+            _finish_task(
+                &pool,
+                &verifiers,
+                FinishTaskDTO {
+                    task_id,
+                    wallet: user.wallet_address.parse().unwrap(),
+                    proof: TaskProof {
+                        url: Some("https://x.com/frog/status/1".to_string()),
+                        ..Default::default()
+                    },
+                },
+                Tz::UTC,
+            )
+        };
+
+        let mut submission_ids = vec![];
+        for _ in 0..2 {
+            match submit().await.unwrap() {
+                FinishOutcome::PendingReview { submission_id } => {
+                    submission_ids.push(submission_id)
+                }
+                _ => panic!("submission wasn't queued"),
+            }
+        }
+        assert_eq!(submission_ids[0], submission_ids[1]);
+
+        let pending = _get_submissions(
+            &pool,
+            SubmissionFilterDTO {
+                status: Some(SubmissionStatus::Pending),
+                task_id: Some(task_id),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(pending.len(), 1);
+
+        let user_before = _get_user_by_id(&pool, user.id).await.unwrap().unwrap();
+        assert_eq!(user_before.total_points, 0);
+
+        _approve_submission(&pool, submission_ids[0], Some(reviewer.id), Tz::UTC)
+            .await
+            .unwrap();
+        assert!(matches!(
+            _approve_submission(&pool, submission_ids[0], Some(reviewer.id), Tz::UTC).await,
+            Err(CompletionError::SubmissionNotFound)
+        ));
+        assert!(
+            !_reject_submission(&pool, submission_ids[0], Some(reviewer.id), "late")
+                .await
+                .unwrap()
+        );
+
+        let user_after = _get_user_by_id(&pool, user.id).await.unwrap().unwrap();
+        assert_eq!(user_after.total_points, 40);
+        assert_eq!(user_after.finished_tasks, vec![task_id]);
+
+        let submissions = _get_user_submissions(&pool, user.id).await.unwrap();
+        assert_eq!(submissions[0].status, SubmissionStatus::Approved);
+        assert_eq!(submissions[0].reviewed_by, Some(reviewer.id));
+    }
+}
@@ -0,0 +1,290 @@
+use sqlx::{Postgres, Transaction};
+
+use crate::{
+    db::Database,
+    models::{CreateTaskDTO, Task, TaskRevision, TaskRevisionAction, TaskStatus},
+    task_history::{diff, snapshot},
+};
+
+use super::tasks::TASK_COLUMNS;
+
+const REVISION_COLUMNS: &str =
+    "id, task_id, revision, action, snapshot, diff, changed_by, created_at";
+
+/// Appends a revision for `after`, the task has to be locked or new so revision
+/// numbers can't collide.
+pub async fn _record_task_revision(
+    tx: &mut Transaction<'_, Postgres>,
+    before: Option<&Task>,
+    after: &Task,
+    action: TaskRevisionAction,
+    changed_by: Option<i32>,
+) -> Result<i32, sqlx::Error> {
+    let snapshot = snapshot(after);
+    let diff = diff(before.map(self::snapshot).as_ref(), &snapshot);
+
+    let row: (i32,) = sqlx::query_as(
+        "INSERT INTO task_revisions (task_id, revision, action, snapshot, diff, changed_by) VALUES ($1, (SELECT COALESCE(MAX(revision), 0) + 1 FROM task_revisions WHERE task_id = $1), $2, $3, $4, $5) RETURNING id",
+    )
+    .bind(after.id)
+    .bind(action)
+    .bind(snapshot)
+    .bind(diff)
+    .bind(changed_by)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok(row.0)
+}
+
+/// Newest first.
+pub async fn _get_task_revisions(
+    db: &Database,
+    task_id: i32,
+) -> Result<Vec<TaskRevision>, sqlx::Error> {
+    let revisions: Vec<TaskRevision> = sqlx::query_as(&format!(
+        "SELECT {REVISION_COLUMNS} FROM task_revisions WHERE task_id = $1 ORDER BY revision DESC"
+    ))
+    .bind(task_id)
+    .fetch_all(db)
+    .await?;
+    Ok(revisions)
+}
+
+/// Puts the task back the way it was after `revision`, undeleting it if needed. The
+/// restore is a revision of its own, so it can be undone the same way.
+pub async fn _restore_task_revision(
+    db: &Database,
+    task_id: i32,
+    revision: i32,
+    changed_by: Option<i32>,
+) -> Result<Option<Task>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let before: Option<Task> = sqlx::query_as(&format!(
+        "SELECT {TASK_COLUMNS} FROM tasks WHERE id = $1 FOR UPDATE"
+    ))
+    .bind(task_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(before) = before else {
+        return Ok(None);
+    };
+
+    let snapshot: Option<(serde_json::Value,)> =
+        sqlx::query_as("SELECT snapshot FROM task_revisions WHERE task_id = $1 AND revision = $2")
+            .bind(task_id)
+            .bind(revision)
+            .fetch_optional(&mut *tx)
+            .await?;
+    let Some((snapshot,)) = snapshot else {
+        return Ok(None);
+    };
+    let task: CreateTaskDTO =
+        serde_json::from_value(snapshot).map_err(|err| sqlx::Error::Decode(Box::new(err)))?;
+
+    let after: Task = sqlx::query_as(&format!("UPDATE tasks SET description = $1, points = $2, link = $3, task_button_text = $4, task_type = COALESCE($5, task_type), category = $6, icon_url = $7, display_order = COALESCE($8, 0), metadata = COALESCE($9, '{{}}'::jsonb), status = COALESCE($10, $11), starts_at = $12, ends_at = $13, recurrence = COALESCE($14, 'none'), recurrence_schedule = $15, max_completions = $16, points_budget = $17, archived_at = NULL, version = version + 1 WHERE id = $18 RETURNING {TASK_COLUMNS}"))
+        .bind(task.description)
+        .bind(task.points)
+        .bind(task.link)
+        .bind(task.task_button_text)
+        .bind(task.task_type)
+        .bind(task.category)
+        .bind(task.icon_url)
+        .bind(task.display_order)
+        .bind(task.metadata)
+        .bind(task.status)
+        .bind(TaskStatus::Active)
+        .bind(task.starts_at)
+        .bind(task.ends_at)
+        .bind(task.recurrence)
+        .bind(task.recurrence_schedule)
+        .bind(task.max_completions)
+        .bind(task.points_budget)
+        .bind(task_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    _record_task_revision(
+        &mut tx,
+        Some(&before),
+        &after,
+        TaskRevisionAction::Restored,
+        changed_by,
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(Some(after))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::{
+        db::{_create_task, _create_user, _delete_task, _finish_task, _get_tasks, _put_task},
+        models::{
+            CreateUserDTO, DeleteTaskDTO, FinishTaskDTO, PutTaskDTO, SolanaAddress, TaskFilterDTO,
+        },
+        verification::Verifiers,
+    };
+    use chrono_tz::Tz;
+    use password_encryptor::PasswordEncryptor;
+    use sqlx::postgres::PgPoolOptions;
+
+    #[tokio::test]
+    async fn test_task_history() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let suffix = chrono::Local::now().to_string();
+        let user = _create_user(
+            &pool,
+            CreateUserDTO {
+                twitter_id: format!("historian{suffix}"),
+                solana_adr: SolanaAddress::random(),
+                password: "123".to_string(),
+                reffer_code: None,
+            },
+            PasswordEncryptor::new(vec![1, 2, 3], None),
+            "salt",
+        )
+        .await
+        .unwrap();
+
+        let category = format!("history{suffix}");
+        let task_id = _create_task(
+            &pool,
+            CreateTaskDTO {
+                description: "Visit the site".to_string(),
+                points: 10,
+                task_button_text: "Visit".to_string(),
+                link: None,
+                task_type: None,
+                category: Some(category.clone()),
+                icon_url: None,
+                display_order: None,
+                metadata: None,
+                status: None,
+                starts_at: None,
+                ends_at: None,
+                recurrence: None,
+                recurrence_schedule: None,
+                max_completions: None,
+                points_budget: None,
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        let put = |points: Option<i32>| PutTaskDTO {
+            task_id,
+            description: None,
+            points,
+            link: None,
+            task_button_text: None,
+            task_type: None,
+            category: None,
+            icon_url: None,
+            display_order: None,
+            metadata: None,
+            status: None,
+            starts_at: None,
+            ends_at: None,
+            recurrence: None,
+            recurrence_schedule: None,
+            max_completions: None,
+            points_budget: None,
+        };
+        _put_task(&pool, put(Some(25)), None, None).await.unwrap();
+        // Nothing changed, so no revision.
+        _put_task(&pool, put(None), None, None).await.unwrap();
+
+        let verifiers = Verifiers::new(reqwest::Client::new(), None);
+        _finish_task(
+            &pool,
+            &verifiers,
+            FinishTaskDTO {
+                task_id,
+                wallet: user.wallet_address.parse().unwrap(),
+                proof: Default::default(),
+            },
+            Tz::UTC,
+        )
+        .await
+        .unwrap();
+
+        _delete_task(&pool, DeleteTaskDTO { task_id }, None)
+            .await
+            .unwrap();
+        let filter = |deleted: bool| TaskFilterDTO {
+            task_type: None,
+            category: Some(category.clone()),
+            status: None,
+            deleted,
+        };
+        assert!(_get_tasks(&pool, filter(false), true)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            _get_tasks(&pool, filter(true), true).await.unwrap().len(),
+            1
+        );
+
+        let task = _restore_task_revision(&pool, task_id, 1, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(task.points, 10);
+        assert_eq!(task.archived_at, None);
+        assert!(_restore_task_revision(&pool, task_id, 99, None)
+            .await
+            .unwrap()
+            .is_none());
+
+        let revisions = _get_task_revisions(&pool, task_id).await.unwrap();
+        let actions: Vec<TaskRevisionAction> =
+            revisions.iter().map(|revision| revision.action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                TaskRevisionAction::Restored,
+                TaskRevisionAction::Deleted,
+                TaskRevisionAction::Updated,
+                TaskRevisionAction::Created,
+            ]
+        );
+        assert_eq!(
+            revisions[2].diff,
+            serde_json::json!({ "points": { "from": 10, "to": 25 } })
+        );
+        assert_eq!(
+            revisions[0].diff,
+            serde_json::json!({ "points": { "from": 25, "to": 10 } })
+        );
+
+        // The completion remembers the task as it was when the points were awarded.
+        let (points_awarded, task_revision_id): (i32, Option<i32>) = sqlx::query_as(
+            "SELECT points_awarded, task_revision_id FROM task_completions WHERE user_id = $1 AND task_id = $2",
+        )
+        .bind(user.id)
+        .bind(task_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(points_awarded, 25);
+        assert_eq!(task_revision_id, Some(revisions[2].id));
+    }
+}
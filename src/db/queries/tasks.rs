@@ -1,96 +1,605 @@
+use std::fmt;
+
+use sqlx::{Postgres, Transaction};
+
 use crate::{
     db::Database,
-    models::{CreateTaskDTO, DeleteTaskDTO, PutTaskDTO, Task, TaskPoints},
+    models::{
+        CreateTaskDTO, DeleteTaskDTO, PutTaskDTO, Task, TaskFilterDTO, TaskRecurrence,
+        TaskRevisionAction, TaskStatus, TaskType,
+    },
+    task_history::{diff, snapshot},
+    task_io::{ImportError, RowError},
+    validation::{Validate, ValidationErrors},
 };
 
+use super::_record_task_revision;
+
+pub(super) const TASK_COLUMNS: &str = "id, description, points, link, task_button_text, task_type, category, icon_url, display_order, metadata, status, starts_at, ends_at, recurrence, recurrence_schedule, max_completions, points_budget, completions_count, points_spent, archived_at, version";
+
+/// SQL counterpart of `Task::is_available`.
+const AVAILABLE_TASK_CONDITION: &str = "archived_at IS NULL AND status IN ('active', 'scheduled') AND (starts_at IS NULL OR starts_at <= NOW()) AND (ends_at IS NULL OR ends_at > NOW())";
+
+/// `changed_by` is the api key recorded in the task's history.
 pub async fn _create_task(
     db: &Database,
     create_task_dto: CreateTaskDTO,
+    changed_by: Option<i32>,
 ) -> Result<i32, sqlx::Error> {
-    let row: (i32,) = sqlx::query_as(
-        "INSERT INTO tasks (description, points, link, task_button_text) VALUES ($1, $2, $3, $4) RETURNING id",
-    )
+    let mut tx = db.begin().await?;
+    let task_id = insert_task(&mut tx, create_task_dto, changed_by).await?;
+    tx.commit().await?;
+
+    Ok(task_id)
+}
+
+/// Inserts every task or none. A dry run rolls back after the last insert, so
+/// database constraints get checked too; its ids are never handed out again. A failed
+/// insert is reported at the row `parse_tasks` gave the task.
+pub async fn _import_tasks(
+    db: &Database,
+    tasks: Vec<(usize, CreateTaskDTO)>,
+    dry_run: bool,
+    changed_by: Option<i32>,
+) -> Result<Vec<i32>, ImportError> {
+    let mut tx = db.begin().await?;
+
+    let mut task_ids = vec![];
+    for (row, task) in tasks {
+        match insert_task(&mut tx, task, changed_by).await {
+            Ok(task_id) => task_ids.push(task_id),
+            Err(err) => {
+                return Err(ImportError::Invalid(vec![RowError {
+                    row,
+                    error: err.to_string(),
+                }]))
+            }
+        }
+    }
+
+    if dry_run {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+    }
+    Ok(task_ids)
+}
+
+async fn insert_task(
+    tx: &mut Transaction<'_, Postgres>,
+    create_task_dto: CreateTaskDTO,
+    changed_by: Option<i32>,
+) -> Result<i32, sqlx::Error> {
+    let task: Task = sqlx::query_as(&format!(
+        "INSERT INTO tasks (description, points, link, task_button_text, task_type, category, icon_url, display_order, metadata, status, starts_at, ends_at, recurrence, recurrence_schedule, max_completions, points_budget) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16) RETURNING {TASK_COLUMNS}",
+    ))
     .bind(create_task_dto.description)
     .bind(create_task_dto.points)
     .bind(create_task_dto.link)
     .bind(create_task_dto.task_button_text)
-    .fetch_one(db)
+    .bind(create_task_dto.task_type.unwrap_or(TaskType::Custom))
+    .bind(create_task_dto.category)
+    .bind(create_task_dto.icon_url)
+    .bind(create_task_dto.display_order.unwrap_or_default())
+    .bind(create_task_dto.metadata.unwrap_or_else(|| serde_json::json!({})))
+    .bind(create_task_dto.status.unwrap_or(TaskStatus::Active))
+    .bind(create_task_dto.starts_at)
+    .bind(create_task_dto.ends_at)
+    .bind(create_task_dto.recurrence.unwrap_or(TaskRecurrence::None))
+    .bind(create_task_dto.recurrence_schedule)
+    .bind(create_task_dto.max_completions)
+    .bind(create_task_dto.points_budget)
+    .fetch_one(&mut **tx)
     .await?;
 
-    Ok(row.0)
+    _record_task_revision(tx, None, &task, TaskRevisionAction::Created, changed_by).await?;
+
+    Ok(task.id)
 }
 
+/// Soft deletes the task, it disappears from every list but completions and
+/// revisions keep pointing to it.
 pub async fn _delete_task(
     db: &Database,
     delete_task_dto: DeleteTaskDTO,
+    changed_by: Option<i32>,
 ) -> Result<(), sqlx::Error> {
-    let result = sqlx::query("DELETE FROM tasks WHERE id = $1")
-        .bind(delete_task_dto.task_id)
-        .execute(db)
-        .await?;
+    let mut tx = db.begin().await?;
 
-    if result.rows_affected() == 0 {
-        return Err(sqlx::Error::RowNotFound);
-    }
+    let before = lock_task(&mut tx, delete_task_dto.task_id).await?;
+    let after: Task = sqlx::query_as(&format!(
+        "UPDATE tasks SET archived_at = NOW(), version = version + 1 WHERE id = $1 RETURNING {TASK_COLUMNS}"
+    ))
+    .bind(delete_task_dto.task_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    _record_task_revision(
+        &mut tx,
+        Some(&before),
+        &after,
+        TaskRevisionAction::Deleted,
+        changed_by,
+    )
+    .await?;
+    tx.commit().await?;
 
     Ok(())
 }
 
-pub async fn _get_tasks(db: &Database) -> Result<Vec<Task>, sqlx::Error> {
-    let tasks: Vec<Task> =
-        sqlx::query_as("SELECT id, description, points, link, task_button_text FROM tasks")
-            .fetch_all(db)
-            .await?;
+/// Locks a task that isn't deleted for the rest of the transaction.
+pub(super) async fn lock_task(
+    tx: &mut Transaction<'_, Postgres>,
+    task_id: i32,
+) -> Result<Task, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {TASK_COLUMNS} FROM tasks WHERE id = $1 AND archived_at IS NULL FOR UPDATE"
+    ))
+    .bind(task_id)
+    .fetch_one(&mut **tx)
+    .await
+}
+
+/// Users only get tasks that are currently available, admins get every task and may
+/// filter by status or list deleted tasks.
+pub async fn _get_tasks(
+    db: &Database,
+    filter: TaskFilterDTO,
+    include_unavailable: bool,
+) -> Result<Vec<Task>, sqlx::Error> {
+    let availability = if include_unavailable {
+        let deleted = if filter.deleted {
+            "archived_at IS NOT NULL"
+        } else {
+            "archived_at IS NULL"
+        };
+        format!("{deleted} AND ($3::task_status IS NULL OR status = $3)")
+    } else {
+        format!("$3::task_status IS NULL AND {AVAILABLE_TASK_CONDITION}")
+    };
+    let status = if include_unavailable {
+        filter.status
+    } else {
+        None
+    };
+
+    let tasks: Vec<Task> = sqlx::query_as(&format!(
+        "SELECT {TASK_COLUMNS} FROM tasks WHERE ($1::task_type IS NULL OR task_type = $1) AND ($2::varchar IS NULL OR category = $2) AND {availability} ORDER BY display_order, id"
+    ))
+    .bind(filter.task_type)
+    .bind(filter.category)
+    .bind(status)
+    .fetch_all(db)
+    .await?;
     Ok(tasks)
 }
 
-pub async fn _get_points_for_task(db: &Database, task_id: i32) -> Result<i32, sqlx::Error> {
-    let task = sqlx::query_as::<_, TaskPoints>("SELECT points FROM tasks WHERE id = $1")
-        .bind(task_id)
-        .fetch_one(db)
-        .await?;
-    Ok(task.points)
+pub async fn _get_task_by_id(db: &Database, task_id: i32) -> Result<Option<Task>, sqlx::Error> {
+    let task: Option<Task> =
+        sqlx::query_as(&format!("SELECT {TASK_COLUMNS} FROM tasks WHERE id = $1"))
+            .bind(task_id)
+            .fetch_optional(db)
+            .await?;
+    Ok(task)
 }
 
-pub async fn _put_task(db: &Database, put_task_dto: PutTaskDTO) -> Result<(), sqlx::Error> {
-    let mut description = put_task_dto.description;
-    let mut points = put_task_dto.points;
-    let mut link = put_task_dto.link;
-    let mut task_button_text = put_task_dto.task_button_text;
+/// Applies the update to the latest version of the task, or to `expected_version`
+/// only when given. Returns the task as saved.
+pub async fn _put_task(
+    db: &Database,
+    put_task_dto: PutTaskDTO,
+    expected_version: Option<i32>,
+    changed_by: Option<i32>,
+) -> Result<Task, TaskUpdateError> {
+    let mut tx = db.begin().await?;
+    let before = match lock_task(&mut tx, put_task_dto.task_id).await {
+        Ok(task) => task,
+        Err(sqlx::Error::RowNotFound) => return Err(TaskUpdateError::NotFound),
+        Err(err) => return Err(err.into()),
+    };
+    if expected_version.is_some_and(|version| version != before.version) {
+        return Err(TaskUpdateError::VersionMismatch {
+            current: before.version,
+        });
+    }
+
+    let task = put_task_dto.apply(before.clone());
+    CreateTaskDTO::from(task.clone())
+        .validate()
+        .map_err(TaskUpdateError::Invalid)?;
+
+    // Saving the task unchanged doesn't make a revision.
+    if diff(Some(&snapshot(&before)), &snapshot(&task)) == serde_json::json!({}) {
+        return Ok(before);
+    }
 
-    let task = sqlx::query_as::<_, Task>(
-        "SELECT id, description, points, link, task_button_text FROM tasks WHERE id = $1",
+    let after: Task = sqlx::query_as(&format!("UPDATE tasks SET description = $1, points = $2, link = $3, task_button_text = $4, task_type = $5, category = $6, icon_url = $7, display_order = $8, metadata = $9, status = $10, starts_at = $11, ends_at = $12, recurrence = $13, recurrence_schedule = $14, max_completions = $15, points_budget = $16, version = version + 1 WHERE id = $17 RETURNING {TASK_COLUMNS}"))
+        .bind(task.description)
+        .bind(task.points)
+        .bind(task.link)
+        .bind(task.task_button_text)
+        .bind(task.task_type)
+        .bind(task.category)
+        .bind(task.icon_url)
+        .bind(task.display_order)
+        .bind(task.metadata)
+        .bind(task.status)
+        .bind(task.starts_at)
+        .bind(task.ends_at)
+        .bind(task.recurrence)
+        .bind(task.recurrence_schedule)
+        .bind(task.max_completions)
+        .bind(task.points_budget)
+        .bind(task.id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    _record_task_revision(
+        &mut tx,
+        Some(&before),
+        &after,
+        TaskRevisionAction::Updated,
+        changed_by,
     )
-    .bind(put_task_dto.task_id)
-    .fetch_one(db)
     .await?;
+    tx.commit().await?;
+
+    Ok(after)
+}
+
+#[derive(Debug)]
+pub enum TaskUpdateError {
+    Database {
+        error: String,
+    },
+    NotFound,
+    /// Someone else changed the task since the given version was read.
+    VersionMismatch {
+        current: i32,
+    },
+    Invalid(ValidationErrors),
+}
 
-    if description.is_none() {
-        description = Some(task.description);
+impl From<sqlx::Error> for TaskUpdateError {
+    fn from(error: sqlx::Error) -> Self {
+        TaskUpdateError::Database {
+            error: error.to_string(),
+        }
     }
+}
 
-    if points.is_none() {
-        points = Some(task.points);
+impl fmt::Display for TaskUpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskUpdateError::Database { error } => write!(f, "Database error: {error}"),
+            TaskUpdateError::NotFound => write!(f, "Task not found."),
+            TaskUpdateError::VersionMismatch { current } => {
+                write!(
+                    f,
+                    "Task was changed meanwhile, current version is {current}."
+                )
+            }
+            TaskUpdateError::Invalid(errors) => write!(f, "Invalid task: {errors:?}"),
+        }
     }
+}
+
+impl std::error::Error for TaskUpdateError {}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
 
-    if link.is_none() {
-        link = Some(task.link.unwrap_or_default());
+    use super::*;
+    use crate::task_io::{parse_tasks, TaskFormat};
+    use sqlx::postgres::PgPoolOptions;
+
+    #[tokio::test]
+    async fn test_get_tasks_filters_by_type_and_category() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let category = "category".to_string() + chrono::Local::now().to_string().as_str();
+
+        let task_id = _create_task(
+            &pool,
+            CreateTaskDTO {
+                description: "Follow us".to_string(),
+                points: 10,
+                task_button_text: "Follow".to_string(),
+                link: None,
+                task_type: Some(TaskType::Follow),
+                category: Some(category.clone()),
+                icon_url: None,
+                display_order: None,
+                metadata: Some(serde_json::json!({ "target": "frog" })),
+                status: None,
+                starts_at: None,
+                ends_at: None,
+                recurrence: None,
+                recurrence_schedule: None,
+                max_completions: None,
+                points_budget: None,
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        let tasks = _get_tasks(
+            &pool,
+            TaskFilterDTO {
+                task_type: Some(TaskType::Follow),
+                category: Some(category.clone()),
+                status: None,
+                deleted: false,
+            },
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, task_id);
+        assert_eq!(tasks[0].metadata["target"], "frog");
+
+        let tasks = _get_tasks(
+            &pool,
+            TaskFilterDTO {
+                task_type: Some(TaskType::Quiz),
+                category: Some(category),
+                status: None,
+                deleted: false,
+            },
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(tasks.is_empty());
     }
 
-    if task_button_text.is_none() {
-        task_button_text = Some(task.task_button_text.unwrap_or_default());
+    #[tokio::test]
+    async fn test_unavailable_tasks_are_hidden_from_users() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let category = "window".to_string() + chrono::Local::now().to_string().as_str();
+
+        for (status, starts_at) in [
+            (TaskStatus::Active, None),
+            (TaskStatus::Draft, None),
+            (
+                TaskStatus::Scheduled,
+                Some(chrono::Utc::now() + chrono::Duration::days(1)),
+            ),
+        ] {
+            _create_task(
+                &pool,
+                CreateTaskDTO {
+                    description: "Campaign".to_string(),
+                    points: 10,
+                    task_button_text: "Go".to_string(),
+                    link: None,
+                    task_type: None,
+                    category: Some(category.clone()),
+                    icon_url: None,
+                    display_order: None,
+                    metadata: None,
+                    status: Some(status),
+                    starts_at,
+                    ends_at: None,
+                    recurrence: None,
+                    recurrence_schedule: None,
+                    max_completions: None,
+                    points_budget: None,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        }
+
+        let filter = || TaskFilterDTO {
+            task_type: None,
+            category: Some(category.clone()),
+            status: None,
+            deleted: false,
+        };
+
+        let user_tasks = _get_tasks(&pool, filter(), false).await.unwrap();
+        assert_eq!(user_tasks.len(), 1);
+        assert_eq!(user_tasks[0].status, TaskStatus::Active);
+
+        let admin_tasks = _get_tasks(&pool, filter(), true).await.unwrap();
+        assert_eq!(admin_tasks.len(), 3);
     }
 
-    let text = task_button_text.clone();
+    #[tokio::test]
+    async fn test_import_tasks_is_all_or_nothing() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
 
-    sqlx::query("UPDATE tasks SET description = $1, points = $2, link = $3, task_button_text = $4 WHERE id = $5")
-        .bind(description)
-        .bind(points)
-        .bind(link)
-        .bind(text)
-        .bind(put_task_dto.task_id)
-        .execute(db)
-        .await?;
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
 
-    Ok(())
+        let category = "import".to_string() + chrono::Local::now().to_string().as_str();
+        let task = |points: i32, max_completions: Option<i32>| CreateTaskDTO {
+            description: "Imported".to_string(),
+            points,
+            task_button_text: "Go".to_string(),
+            link: None,
+            task_type: None,
+            category: Some(category.clone()),
+            icon_url: None,
+            display_order: None,
+            metadata: None,
+            status: None,
+            starts_at: None,
+            ends_at: None,
+            recurrence: None,
+            recurrence_schedule: None,
+            max_completions,
+            points_budget: None,
+        };
+        let imported = || async {
+            _get_tasks(
+                &pool,
+                TaskFilterDTO {
+                    task_type: None,
+                    category: Some(category.clone()),
+                    status: None,
+                    deleted: false,
+                },
+                true,
+            )
+            .await
+            .unwrap()
+            .len()
+        };
+
+        // The second row breaks the caps check constraint, so the first one is dropped too.
+        match _import_tasks(
+            &pool,
+            vec![(1, task(10, None)), (2, task(20, Some(-1)))],
+            false,
+            None,
+        )
+        .await
+        {
+            Err(ImportError::Invalid(errors)) => assert_eq!(errors[0].row, 2),
+            _ => panic!("bad row was imported"),
+        }
+        assert_eq!(imported().await, 0);
+
+        // A CSV row keeps the line `parse_tasks` found it on, past a quoted line break.
+        let csv = format!(
+            "description,points,task_button_text,category\n\"Two\nlines\",10,Go,{category}\nCapped,20,Go,{category}\n"
+        );
+        let mut tasks = parse_tasks(TaskFormat::Csv, &csv).unwrap();
+        tasks[1].1.max_completions = Some(-1);
+        match _import_tasks(&pool, tasks, false, None).await {
+            Err(ImportError::Invalid(errors)) => assert_eq!(errors[0].row, 4),
+            _ => panic!("bad row was imported"),
+        }
+        assert_eq!(imported().await, 0);
+
+        let tasks = || vec![(1, task(10, None)), (2, task(20, None))];
+        let task_ids = _import_tasks(&pool, tasks(), true, None).await.unwrap();
+        assert_eq!(task_ids.len(), 2);
+        assert_eq!(imported().await, 0);
+
+        _import_tasks(&pool, tasks(), false, None).await.unwrap();
+        assert_eq!(imported().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_put_task_patch_semantics() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let task_id = _create_task(
+            &pool,
+            CreateTaskDTO {
+                description: "Read the docs".to_string(),
+                points: 10,
+                task_button_text: "Read".to_string(),
+                link: Some("https://docs.frog.wtf".to_string()),
+                task_type: None,
+                category: Some("learn".to_string()),
+                icon_url: None,
+                display_order: None,
+                metadata: None,
+                status: None,
+                starts_at: None,
+                ends_at: None,
+                recurrence: None,
+                recurrence_schedule: None,
+                max_completions: Some(100),
+                points_budget: None,
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        let put = |body: serde_json::Value| -> PutTaskDTO {
+            let mut body = body;
+            body["task_id"] = task_id.into();
+            serde_json::from_value(body).unwrap()
+        };
+
+        // `link` is cleared, `category` is left alone.
+        let task = _put_task(
+            &pool,
+            put(serde_json::json!({ "link": null, "max_completions": null, "points": 15 })),
+            Some(1),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(task.link, None);
+        assert_eq!(task.max_completions, None);
+        assert_eq!(task.category.as_deref(), Some("learn"));
+        assert_eq!(task.points, 15);
+        assert_eq!(task.version, 2);
+
+        assert!(matches!(
+            _put_task(
+                &pool,
+                put(serde_json::json!({ "points": 20 })),
+                Some(1),
+                None
+            )
+            .await,
+            Err(TaskUpdateError::VersionMismatch { current: 2 })
+        ));
+
+        match _put_task(
+            &pool,
+            put(serde_json::json!({ "points": -5, "link": "not a url" })),
+            None,
+            None,
+        )
+        .await
+        {
+            Err(TaskUpdateError::Invalid(errors)) => {
+                let fields: Vec<&str> = errors.0.iter().map(|error| error.field.as_str()).collect();
+                assert_eq!(fields, vec!["points", "link"]);
+            }
+            _ => panic!("invalid update was saved"),
+        }
+
+        // Nothing changes, so the version stays.
+        let task = _put_task(&pool, put(serde_json::json!({ "points": 15 })), None, None)
+            .await
+            .unwrap();
+        assert_eq!(task.version, 2);
+    }
 }
@@ -1,14 +1,20 @@
 use crate::{
+    constants::{MAX_STREAK_FREEZES, STREAK_FREEZE_PRICE, TWITTER_OAUTH_STATE_TTL},
     db::Database,
-    models::{BindWalletAddressDTO, CreateUserDTO, FinishTaskDTO, User, UserWithEncryptedPassword},
+    models::{
+        BindWalletAddressDTO, CreateUserDTO, SolanaAddress, User, UserWithEncryptedPassword,
+        WalletChain,
+    },
     password::encrypt_password,
+    twitter::TwitterAccount,
 };
 
 use hex::encode;
 use password_encryptor::PasswordEncryptor;
 use sha3_rust::*;
+use sqlx::{Postgres, Transaction};
 
-use super::_get_points_for_task;
+const USER_COLUMNS: &str = "id, wallet_address, wallet_address_invalid, twitter_id, referral_code, total_points, finished_tasks, referral_points, referred_by, referrer_id, multiplier, current_streak, longest_streak, last_check_in, streak_freezes, twitter_user_id, twitter_handle";
 
 pub async fn _save_last_created_user_id(db: &Database, user_id: i32) -> Result<(), sqlx::Error> {
     sqlx::query("INSERT INTO last_created_user (user_id) VALUES ($1)")
@@ -45,7 +51,7 @@ pub async fn _create_user(
     )
     .bind(create_user_dto.twitter_id)
     .bind(referral_code_string)
-    .bind(create_user_dto.solana_adr)
+    .bind(&create_user_dto.solana_adr)
     .bind(encrypted_password)
     .fetch_one(db)
     .await?;
@@ -53,6 +59,10 @@ pub async fn _create_user(
     let user_id = create_user_result.0;
 
     _save_last_created_user_id(db, user_id).await?;
+    // A wallet someone else linked stays theirs, the new user can link another one.
+    let mut wallet_tx = db.begin().await?;
+    add_solana_wallet(&mut wallet_tx, user_id, create_user_dto.solana_adr.as_str()).await?;
+    wallet_tx.commit().await?;
 
     if let Some(ref_code) = create_user_dto.reffer_code {
         let result_refered = _get_user_by_referral_code(db, ref_code.to_string()).await?;
@@ -74,9 +84,9 @@ pub async fn _create_user(
                     .await?;
             }
             None => {
-                let user = sqlx::query_as::<_, User>(
-                    "SELECT id, wallet_address, twitter_id, referral_code, total_points, finished_tasks, referral_points, referred_by, referrer_id, multiplier FROM users WHERE id = $1"
-                )
+                let user = sqlx::query_as::<_, User>(&format!(
+                    "SELECT {USER_COLUMNS} FROM users WHERE id = $1"
+                ))
                 .bind(create_user_result.0)
                 .fetch_one(db)
                 .await?;
@@ -87,109 +97,89 @@ pub async fn _create_user(
     }
     tx.commit().await?;
 
-    let user: User = sqlx::query_as(
-        "SELECT id, wallet_address, twitter_id, referral_code, total_points, finished_tasks, referral_points, referred_by, multiplier, referrer_id FROM users WHERE id = $1"
-    )
-    .bind(create_user_result.0)
-    .fetch_one(db)
-    .await?;
+    let user: User = sqlx::query_as(&format!("SELECT {USER_COLUMNS} FROM users WHERE id = $1"))
+        .bind(create_user_result.0)
+        .fetch_one(db)
+        .await?;
 
     Ok(user)
 }
+/// Binds a new account wallet, which also becomes the user's Solana airdrop wallet.
+/// Returns `None` and changes nothing if the wallet belongs to someone else.
 pub async fn _bind_wallet_address(
     db: &Database,
     bind_wallet_address: BindWalletAddressDTO,
-) -> Result<i32, sqlx::Error> {
+) -> Result<Option<i32>, sqlx::Error> {
     let wallet_address = bind_wallet_address.wallet_address;
     let twitter_id = bind_wallet_address.twitter_id;
 
-    let query = "UPDATE users SET wallet_address = $1 WHERE twitter_id = $2 RETURNING id";
+    let query = "UPDATE users SET wallet_address = $1, wallet_address_invalid = FALSE WHERE twitter_id = $2 RETURNING id";
 
+    let mut tx = db.begin().await?;
     let result = sqlx::query_as::<_, (i32,)>(query)
-        .bind(wallet_address)
+        .bind(&wallet_address)
         .bind(twitter_id)
-        .fetch_one(db)
-        .await?;
+        .fetch_one(&mut *tx)
+        .await;
+    let user_id = match result {
+        Ok((user_id,)) => user_id,
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    if !add_solana_wallet(&mut tx, user_id, wallet_address.as_str()).await? {
+        return Ok(None);
+    }
+    tx.commit().await?;
 
-    Ok(result.0)
+    Ok(Some(user_id))
 }
 
-pub async fn _get_users(db: &Database) -> Result<Vec<User>, sqlx::Error> {
-    let users: Vec<User> =
-        sqlx::query_as("SELECT id, wallet_address, twitter_id, referral_code, total_points, finished_tasks, referral_points, referred_by, referrer_id, multiplier from users")
-            .fetch_all(db)
-            .await?;
-    Ok(users)
+/// Makes `address` the user's Solana airdrop wallet, replacing the one they had until
+/// it's verified again. Returns `false` if someone else already has it.
+async fn add_solana_wallet(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: i32,
+    address: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO user_wallets (user_id, chain, address) SELECT $1, $2, $3 WHERE NOT EXISTS (SELECT 1 FROM user_wallets WHERE chain = $2 AND address = $3 AND user_id <> $1) ON CONFLICT (user_id, chain) DO UPDATE SET address = EXCLUDED.address, verified_at = NULL",
+    )
+    .bind(user_id)
+    .bind(WalletChain::Solana)
+    .bind(address)
+    .execute(&mut **tx)
+    .await;
+
+    match result {
+        Ok(result) => Ok(result.rows_affected() > 0),
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => Ok(false),
+        Err(err) => Err(err),
+    }
 }
 
-pub async fn _finish_task(
-    db: &Database,
-    finish_task_dto: FinishTaskDTO,
-) -> Result<(), sqlx::Error> {
-    // This can be optimized. We dont need to query all!
-    let mut user = _get_user_by_wallet_address(db, finish_task_dto.wallet.as_str())
-        .await?
-        .ok_or_else(|| sqlx::Error::RowNotFound)?;
-
-    // Check if the task is already finished
-    if !user.finished_tasks.contains(&finish_task_dto.task_id) {
-        // This also checks if tasks exists
-        let points_to_add = _get_points_for_task(db, finish_task_dto.task_id).await?;
-
-        let points_for_referral =
-            points_to_add * crate::constants::REFERRAL_BONUS_PRECENT as i32 / 100;
-        // Add the task to the finished tasks
-        user.finished_tasks.push(finish_task_dto.task_id);
-
-        // Update the total points
-        user.total_points += points_to_add;
-
-        // Update the user in the database
-        sqlx::query(
-            "UPDATE users SET finished_tasks = $1, total_points = $2 WHERE wallet_address = $3",
-        )
-        .bind(&user.finished_tasks)
-        .bind(user.total_points)
-        .bind(finish_task_dto.wallet)
-        .execute(db)
+pub async fn _get_users(db: &Database) -> Result<Vec<User>, sqlx::Error> {
+    let users: Vec<User> = sqlx::query_as(&format!("SELECT {USER_COLUMNS} FROM users"))
+        .fetch_all(db)
         .await?;
-
-        if user.referrer_id.is_some() {
-            let mut user_referral: User = sqlx::query_as("SELECT id, wallet_address, twitter_id, referral_code, total_points, finished_tasks, referral_points, referred_by, referrer_id, multiplier FROM users WHERE id = $1")
-            .bind(user.referrer_id)
-            .fetch_one(db)
-            .await?;
-
-            user_referral.total_points += points_for_referral;
-            user_referral.referral_points += points_for_referral;
-
-            sqlx::query("UPDATE users SET total_points = $1, referral_points = $2 WHERE id = $3")
-                .bind(user_referral.total_points)
-                .bind(user_referral.referral_points)
-                .bind(user_referral.id)
-                .execute(db)
-                .await?;
-
-        }
-    }
-    Ok(())
+    Ok(users)
 }
 
 pub async fn _get_user_by_referral_code(
     db: &Database,
     referral_code: String,
 ) -> Result<Option<User>, sqlx::Error> {
-    let user: Option<User> =
-        sqlx::query_as("SELECT id, wallet_address, twitter_id, referral_code, total_points, finished_tasks, referral_points, referred_by, referrer_id, multiplier FROM users WHERE referral_code = $1")
-            .bind(referral_code)
-            .fetch_optional(db)
-            .await?;
+    let user: Option<User> = sqlx::query_as(&format!(
+        "SELECT {USER_COLUMNS} FROM users WHERE referral_code = $1"
+    ))
+    .bind(referral_code)
+    .fetch_optional(db)
+    .await?;
     Ok(user)
 }
 
 pub async fn _get_user_by_id(db: &Database, id: i32) -> Result<Option<User>, sqlx::Error> {
     let user: Option<User> =
-        sqlx::query_as("SELECT id, wallet_address, twitter_id, referral_code, total_points, finished_tasks, referral_points, referred_by, referrer_id, multiplier FROM users WHERE id = $1")
+        sqlx::query_as(&format!("SELECT {USER_COLUMNS} FROM users WHERE id = $1"))
             .bind(id)
             .fetch_optional(db)
             .await?;
@@ -221,20 +211,91 @@ pub async fn _get_user_by_twitter_id(
     Ok(user)
 }
 
-
 pub async fn _get_user_by_wallet_address(
     db: &Database,
-    wallet_address: &str,
+    wallet_address: &SolanaAddress,
 ) -> Result<Option<UserWithEncryptedPassword>, sqlx::Error> {
-    let user =
-        sqlx::query_as::<_, UserWithEncryptedPassword>("SELECT * FROM users WHERE wallet_address = $1")
-            .bind(wallet_address)
-            .fetch_optional(db)
-            .await?;
+    let user = sqlx::query_as::<_, UserWithEncryptedPassword>(
+        "SELECT * FROM users WHERE wallet_address = $1",
+    )
+    .bind(wallet_address)
+    .fetch_optional(db)
+    .await?;
 
     Ok(user)
 }
 
+/// Trades points for a streak freeze. Returns the new freeze count, or `None` if the
+/// user can't afford one or already holds the maximum.
+pub async fn _buy_streak_freeze(db: &Database, user_id: i32) -> Result<Option<i32>, sqlx::Error> {
+    let result = sqlx::query_as::<_, (i32,)>(
+        "UPDATE users SET streak_freezes = streak_freezes + 1, total_points = total_points - $1 WHERE id = $2 AND total_points >= $1 AND streak_freezes < $3 RETURNING streak_freezes",
+    )
+    .bind(STREAK_FREEZE_PRICE)
+    .bind(user_id)
+    .bind(MAX_STREAK_FREEZES)
+    .fetch_optional(db)
+    .await?;
+    Ok(result.map(|row| row.0))
+}
+
+pub async fn _create_twitter_oauth_state(
+    db: &Database,
+    state: &str,
+    user_id: i32,
+    code_verifier: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO twitter_oauth_states (state, user_id, code_verifier) VALUES ($1, $2, $3)",
+    )
+    .bind(state)
+    .bind(user_id)
+    .bind(code_verifier)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Consumes an OAuth state started by the user and returns its PKCE verifier. Each
+/// state works once and expires after `TWITTER_OAUTH_STATE_TTL`, expired states are
+/// deleted along the way.
+pub async fn _take_twitter_oauth_state(
+    db: &Database,
+    state: &str,
+    user_id: i32,
+) -> Result<Option<String>, sqlx::Error> {
+    let row: Option<(String,)> = sqlx::query_as(
+        "WITH expired AS (DELETE FROM twitter_oauth_states WHERE created_at <= NOW() - make_interval(secs => $3)) DELETE FROM twitter_oauth_states WHERE state = $1 AND user_id = $2 AND created_at > NOW() - make_interval(secs => $3) RETURNING code_verifier",
+    )
+    .bind(state)
+    .bind(user_id)
+    .bind(TWITTER_OAUTH_STATE_TTL.as_secs_f64())
+    .fetch_optional(db)
+    .await?;
+    Ok(row.map(|row| row.0))
+}
+
+/// Links a verified X account. Returns `false` if it's already linked to someone else.
+pub async fn _link_twitter_account(
+    db: &Database,
+    user_id: i32,
+    account: &TwitterAccount,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE users SET twitter_user_id = $1, twitter_handle = $2, twitter_linked_at = NOW() WHERE id = $3",
+    )
+    .bind(&account.id)
+    .bind(&account.username)
+    .bind(user_id)
+    .execute(db)
+    .await;
+
+    match result {
+        Ok(_) => Ok(true),
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => Ok(false),
+        Err(err) => Err(err),
+    }
+}
 
 pub async fn _set_user_multiplier(
     db: &Database,
@@ -275,7 +336,7 @@ mod tests {
             CreateUserDTO {
                 twitter_id: "123".to_string() + chrono::Local::now().to_string().as_str(),
                 reffer_code: None,
-                solana_adr: "123".to_string() + chrono::Local::now().to_string().as_str(),
+                solana_adr: SolanaAddress::random(),
                 password: "123".to_string(),
             },
             PasswordEncryptor::new(vec![1, 2, 3], None),
@@ -320,4 +381,103 @@ mod tests {
 
         assert_eq!(rows_affected, 1);
     }
+
+    #[tokio::test]
+    async fn test_link_twitter_account() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let suffix = chrono::Local::now().to_string();
+        let mut users = vec![];
+        for name in ["linker", "squatter"] {
+            let user = _create_user(
+                &pool,
+                CreateUserDTO {
+                    twitter_id: format!("{name}{suffix}"),
+                    reffer_code: None,
+                    solana_adr: SolanaAddress::random(),
+                    password: "123".to_string(),
+                },
+                PasswordEncryptor::new(vec![1, 2, 3], None),
+                "salt",
+            )
+            .await
+            .unwrap();
+            users.push(user);
+        }
+
+        let state = format!("state{suffix}");
+        _create_twitter_oauth_state(&pool, &state, users[0].id, "verifier")
+            .await
+            .unwrap();
+        // The state belongs to the first user only and can be used once.
+        assert_eq!(
+            _take_twitter_oauth_state(&pool, &state, users[1].id)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            _take_twitter_oauth_state(&pool, &state, users[0].id)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("verifier")
+        );
+        assert_eq!(
+            _take_twitter_oauth_state(&pool, &state, users[0].id)
+                .await
+                .unwrap(),
+            None
+        );
+
+        // Taking a state clears out the ones that expired.
+        let expired = format!("expired{suffix}");
+        _create_twitter_oauth_state(&pool, &expired, users[1].id, "verifier")
+            .await
+            .unwrap();
+        sqlx::query("UPDATE twitter_oauth_states SET created_at = NOW() - INTERVAL '1 day' WHERE state = $1")
+            .bind(&expired)
+            .execute(&pool)
+            .await
+            .unwrap();
+        _take_twitter_oauth_state(&pool, &state, users[0].id)
+            .await
+            .unwrap();
+        let (left,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM twitter_oauth_states WHERE state = $1")
+                .bind(&expired)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(left, 0);
+
+        let account = TwitterAccount {
+            id: format!("{}", chrono::Local::now().timestamp_nanos_opt().unwrap()),
+            username: "Frog".to_string(),
+        };
+        assert!(_link_twitter_account(&pool, users[0].id, &account)
+            .await
+            .unwrap());
+        assert!(!_link_twitter_account(&pool, users[1].id, &account)
+            .await
+            .unwrap());
+
+        let user = _get_user_by_id(&pool, users[0].id).await.unwrap().unwrap();
+        assert_eq!(user.twitter_user_id, Some(account.id.clone()));
+        assert!(user.is_named(&account.id));
+        assert!(!user.is_named("frog"));
+        // Once linked, the registered twitter_id no longer counts, it may be someone else's.
+        assert!(!user.is_named(&users[0].twitter_id));
+        let unlinked = _get_user_by_id(&pool, users[1].id).await.unwrap().unwrap();
+        assert!(unlinked.is_named(&users[1].twitter_id));
+    }
 }
@@ -0,0 +1,31 @@
+use crate::{db::Database, models::TaskProof, verification::Verdict};
+
+/// Stores the outcome of a verification, passed or not, and returns its id.
+pub async fn _record_verification(
+    db: &Database,
+    user_id: i32,
+    task_id: i32,
+    verifier: &str,
+    verdict: &Verdict,
+    proof: &TaskProof,
+) -> Result<i32, sqlx::Error> {
+    let details = if verdict.details.is_null() {
+        serde_json::json!({})
+    } else {
+        verdict.details.clone()
+    };
+
+    let (id,): (i32,) = sqlx::query_as(
+        "INSERT INTO task_verifications (user_id, task_id, verifier, verified, reason, proof, details) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id",
+    )
+    .bind(user_id)
+    .bind(task_id)
+    .bind(verifier)
+    .bind(verdict.verified)
+    .bind(&verdict.reason)
+    .bind(sqlx::types::Json(proof))
+    .bind(details)
+    .fetch_one(db)
+    .await?;
+    Ok(id)
+}
@@ -0,0 +1,301 @@
+use crate::{
+    constants::WALLET_LINK_CHALLENGE_TTL,
+    db::Database,
+    models::{UserWallet, WalletChain},
+};
+
+const USER_WALLET_COLUMNS: &str = "id, user_id, chain, address, verified_at, created_at";
+
+pub async fn _get_user_wallets(
+    db: &Database,
+    user_id: i32,
+) -> Result<Vec<UserWallet>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {USER_WALLET_COLUMNS} FROM user_wallets WHERE user_id = $1 ORDER BY chain"
+    ))
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+}
+
+pub async fn _get_chain_wallets(
+    db: &Database,
+    chain: WalletChain,
+) -> Result<Vec<UserWallet>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {USER_WALLET_COLUMNS} FROM user_wallets WHERE chain = $1"
+    ))
+    .bind(chain)
+    .fetch_all(db)
+    .await
+}
+
+pub async fn _create_wallet_link_challenge(
+    db: &Database,
+    nonce: &str,
+    user_id: i32,
+    chain: WalletChain,
+    address: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO wallet_link_challenges (nonce, user_id, chain, address) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(nonce)
+    .bind(user_id)
+    .bind(chain)
+    .bind(address)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Consumes a challenge issued to the user for this wallet. Each challenge works once
+/// and expires after `WALLET_LINK_CHALLENGE_TTL`, expired challenges are deleted along
+/// the way.
+pub async fn _take_wallet_link_challenge(
+    db: &Database,
+    nonce: &str,
+    user_id: i32,
+    chain: WalletChain,
+    address: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "WITH expired AS (DELETE FROM wallet_link_challenges WHERE created_at <= NOW() - make_interval(secs => $5)) DELETE FROM wallet_link_challenges WHERE nonce = $1 AND user_id = $2 AND chain = $3 AND address = $4 AND created_at > NOW() - make_interval(secs => $5)",
+    )
+    .bind(nonce)
+    .bind(user_id)
+    .bind(chain)
+    .bind(address)
+    .bind(WALLET_LINK_CHALLENGE_TTL.as_secs_f64())
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Links a verified wallet, replacing the user's wallet for that chain. Returns `None`
+/// if the address is already linked to someone else.
+pub async fn _link_wallet(
+    db: &Database,
+    user_id: i32,
+    chain: WalletChain,
+    address: &str,
+) -> Result<Option<UserWallet>, sqlx::Error> {
+    let result = sqlx::query_as(&format!(
+        "INSERT INTO user_wallets (user_id, chain, address, verified_at) VALUES ($1, $2, $3, NOW()) ON CONFLICT (user_id, chain) DO UPDATE SET address = EXCLUDED.address, verified_at = EXCLUDED.verified_at RETURNING {USER_WALLET_COLUMNS}"
+    ))
+    .bind(user_id)
+    .bind(chain)
+    .bind(address)
+    .fetch_one(db)
+    .await;
+
+    match result {
+        Ok(wallet) => Ok(Some(wallet)),
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+pub async fn _unlink_wallet(
+    db: &Database,
+    user_id: i32,
+    chain: WalletChain,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM user_wallets WHERE user_id = $1 AND chain = $2")
+        .bind(user_id)
+        .bind(chain)
+        .execute(db)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::{
+        db::{_bind_wallet_address, _create_user, _get_user_by_id},
+        models::{BindWalletAddressDTO, CreateUserDTO, SolanaAddress, User},
+    };
+    use password_encryptor::PasswordEncryptor;
+    use sqlx::postgres::PgPoolOptions;
+
+    #[tokio::test]
+    async fn test_link_wallet() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let suffix = chrono::Local::now().to_string();
+        let mut users = vec![];
+        for name in ["bridger", "copycat"] {
+            let user = _create_user(
+                &pool,
+                CreateUserDTO {
+                    twitter_id: format!("{name}{suffix}"),
+                    reffer_code: None,
+                    solana_adr: SolanaAddress::random(),
+                    password: "123".to_string(),
+                },
+                PasswordEncryptor::new(vec![1, 2, 3], None),
+                "salt",
+            )
+            .await
+            .unwrap();
+            users.push(user);
+        }
+
+        // The sign up wallet is the unverified Solana entry.
+        let wallets = _get_user_wallets(&pool, users[0].id).await.unwrap();
+        assert_eq!(wallets.len(), 1);
+        assert_eq!(wallets[0].chain, WalletChain::Solana);
+        assert_eq!(wallets[0].address, users[0].wallet_address);
+        assert_eq!(wallets[0].verified_at, None);
+
+        let address = format!("0x{:040x}", users[0].id);
+        let nonce = format!("nonce{suffix}");
+        _create_wallet_link_challenge(&pool, &nonce, users[0].id, WalletChain::Base, &address)
+            .await
+            .unwrap();
+        assert!(!_take_wallet_link_challenge(
+            &pool,
+            &nonce,
+            users[0].id,
+            WalletChain::Ethereum,
+            &address
+        )
+        .await
+        .unwrap());
+        assert!(_take_wallet_link_challenge(
+            &pool,
+            &nonce,
+            users[0].id,
+            WalletChain::Base,
+            &address
+        )
+        .await
+        .unwrap());
+        assert!(!_take_wallet_link_challenge(
+            &pool,
+            &nonce,
+            users[0].id,
+            WalletChain::Base,
+            &address
+        )
+        .await
+        .unwrap());
+
+        let wallet = _link_wallet(&pool, users[0].id, WalletChain::Base, &address)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(wallet.verified_at.is_some());
+        assert!(
+            _link_wallet(&pool, users[1].id, WalletChain::Base, &address)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        // The same address may be used on another chain.
+        assert!(
+            _link_wallet(&pool, users[1].id, WalletChain::Ethereum, &address)
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        assert!(_unlink_wallet(&pool, users[0].id, WalletChain::Base)
+            .await
+            .unwrap());
+        assert!(
+            _link_wallet(&pool, users[1].id, WalletChain::Base, &address)
+                .await
+                .unwrap()
+                .is_some()
+        );
+        let base_wallets = _get_chain_wallets(&pool, WalletChain::Base).await.unwrap();
+        assert!(base_wallets
+            .iter()
+            .any(|wallet| wallet.user_id == users[1].id && wallet.address == address));
+    }
+
+    #[tokio::test]
+    async fn test_bind_wallet_address_replaces_solana_wallet() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let suffix = chrono::Local::now().to_string();
+        let mut users = vec![];
+        for name in ["binder", "claimer"] {
+            let user = _create_user(
+                &pool,
+                CreateUserDTO {
+                    twitter_id: format!("{name}{suffix}"),
+                    reffer_code: None,
+                    solana_adr: SolanaAddress::random(),
+                    password: "123".to_string(),
+                },
+                PasswordEncryptor::new(vec![1, 2, 3], None),
+                "salt",
+            )
+            .await
+            .unwrap();
+            users.push(user);
+        }
+        let bind = |user: &User, wallet_address: &SolanaAddress| BindWalletAddressDTO {
+            twitter_id: user.twitter_id.clone(),
+            wallet_address: wallet_address.clone(),
+        };
+
+        // Accounts with a wallet from before addresses were checked bind a proper one.
+        sqlx::query(
+            "UPDATE users SET wallet_address = $1, wallet_address_invalid = TRUE WHERE id = $2",
+        )
+        .bind(format!("binder-wallet{suffix}"))
+        .bind(users[0].id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let address = SolanaAddress::random();
+        assert_eq!(
+            _bind_wallet_address(&pool, bind(&users[0], &address))
+                .await
+                .unwrap(),
+            Some(users[0].id)
+        );
+        let user = _get_user_by_id(&pool, users[0].id).await.unwrap().unwrap();
+        assert_eq!(user.wallet_address, address.as_str());
+        let wallets = _get_user_wallets(&pool, users[0].id).await.unwrap();
+        assert_eq!(wallets.len(), 1);
+        assert_eq!(wallets[0].address, address.as_str());
+        assert_eq!(wallets[0].verified_at, None);
+
+        // Someone else's wallet is refused and the account keeps its own.
+        assert_eq!(
+            _bind_wallet_address(&pool, bind(&users[1], &address))
+                .await
+                .unwrap(),
+            None
+        );
+        let wallets = _get_user_wallets(&pool, users[1].id).await.unwrap();
+        assert_eq!(wallets[0].address, users[1].wallet_address);
+        let user = _get_user_by_id(&pool, users[1].id).await.unwrap().unwrap();
+        assert_eq!(user.wallet_address, users[1].wallet_address);
+    }
+}
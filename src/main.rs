@@ -1,23 +1,42 @@
+mod airdrop;
+mod cli;
 mod constants;
+mod cors;
 mod db;
 mod jwt;
 mod middlewares;
 mod models;
 mod password;
+mod prerequisites;
+mod quiz;
+mod recurrence;
 mod routes;
+mod snapshots;
 mod state;
+mod streaks;
+mod task_history;
+mod task_io;
+mod twitter;
+mod validation;
+mod verification;
+mod wallets;
 
 use axum::middleware;
-use axum::{http::Method, Extension, Router};
-use constants::{REQUESTS_AMOUNT_LIMIT, REQUESTS_AMOUNT_TIME_FRAME};
-use middlewares::{RateLimiterConfig, RedisRateLimiterDb};
+use axum::{Extension, Router};
+use constants::{REQUESTS_AMOUNT_LIMIT, REQUESTS_AMOUNT_TIME_FRAME, VERIFIER_TIMEOUT};
+use middlewares::{RateLimiterConfig, RedisNonceStore, RedisRateLimiterDb};
 use password_encryptor::PasswordEncryptor;
 use std::{env, net::SocketAddr, sync::Arc};
 use tokio::net::TcpListener;
-use tower_http::cors::{Any, CorsLayer};
 
 use crate::middlewares::*;
-use crate::{db::connect, state::AppState};
+use crate::{
+    cors::CorsSettings,
+    db::connect,
+    state::AppState,
+    twitter::{TwitterClient, TwitterOAuth},
+    verification::Verifiers,
+};
 
 #[tokio::main]
 async fn main() {
@@ -26,31 +45,32 @@ async fn main() {
     let database_url = env::var("DATABASE_URL")
         .unwrap_or_else(|_| panic!("Missing required environment variable: {}", "DATABASE_URL"));
 
+    // Commands like `import-tasks` only need the database.
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.is_empty() {
+        let db = connect(database_url.as_str()).await.unwrap();
+        sqlx::migrate!("./migrations").run(&db).await.unwrap();
+        std::process::exit(cli::run(&db, &args).await);
+    }
+
     let dev_secret = env::var("DEV_SECRET")
         .unwrap_or_else(|_| panic!("Missing required environment variable: {}", "DEV_SECRET"));
 
-    let security_hash = env::var("SECURITY_HASH")
-        .unwrap_or_else(|_| panic!("Missing required environment variable: {}", "SECURITY_HASH"));
-
     let encryption_key = env::var("JWT_SECRET")
         .unwrap_or_else(|_| panic!("Missing required environment variable: SECURITY_HASH"));
 
     let salt = env::var("SALT")
         .unwrap_or_else(|_| panic!("Missing required environment variable: SECURITY_HASH"));
 
+    let period_timezone: chrono_tz::Tz = env::var("TASK_PERIOD_TIMEZONE")
+        .unwrap_or_else(|_| "UTC".to_string())
+        .parse()
+        .unwrap_or_else(|_| panic!("Invalid timezone in {}", "TASK_PERIOD_TIMEZONE"));
+
     let redis_url = env::var("REDIS_URL")
         .unwrap_or_else(|_| panic!("Missing required environment variable: {}", "DATABSE_URL"));
 
-    let cors = CorsLayer::new()
-        // .allow_origin(["https://farm.frogofroggins.wtf".parse().unwrap()])
-        .allow_origin(Any)
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-        .allow_headers([
-            "Content-Type".parse().unwrap(),
-            "Authorization".parse().unwrap(),
-            "Access-Control-Allow-Origin".parse().unwrap(),
-            "X-Security-Hash".parse().unwrap(),
-        ]);
+    let cors = CorsSettings::from_env().layer();
 
     let encoding_key = jwt::init_encoding_key(&encryption_key).unwrap();
 
@@ -60,11 +80,23 @@ async fn main() {
 
     let redis_rate_limiter_db = RedisRateLimiterDb::new(redis_url).await.unwrap();
 
+    let nonce_store = RedisNonceStore::new(&redis_rate_limiter_db.client)
+        .await
+        .unwrap();
+
     let rate_limiter_config = RateLimiterConfig {
         requests_amount: REQUESTS_AMOUNT_LIMIT,
         time_frame: REQUESTS_AMOUNT_TIME_FRAME,
     };
 
+    let http_client = reqwest::Client::builder()
+        .timeout(VERIFIER_TIMEOUT)
+        .build()
+        .unwrap();
+    let twitter = TwitterClient::from_env(http_client.clone());
+    let twitter_oauth = TwitterOAuth::from_env(http_client.clone());
+    let verifiers = Verifiers::new(http_client, twitter);
+
     sqlx::migrate!("./migrations").run(&db).await.unwrap();
 
     let password_encryptor = PasswordEncryptor::new(encryption_key.as_bytes().to_vec(), None);
@@ -72,13 +104,16 @@ async fn main() {
     let state = AppState {
         db: db.clone(),
         dev_secret,
-        security_hash,
         password_encryptor,
         salt,
         encoding_key,
         decoding_key,
         redis_rate_limiter_db,
         rate_limiter_config,
+        nonce_store,
+        period_timezone,
+        verifiers,
+        twitter_oauth,
     };
 
     let shared_state = Arc::new(state);
@@ -1,9 +1,11 @@
 mod rate_limiter;
+mod request_signing;
+mod require_api_key;
 mod require_auth;
 mod require_auth_jwt;
-mod require_security_hash;
 
 pub use rate_limiter::*;
+pub use request_signing::*;
+pub use require_api_key::*;
 pub use require_auth::*;
 pub use require_auth_jwt::*;
-pub use require_security_hash::*;
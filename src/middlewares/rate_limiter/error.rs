@@ -15,7 +15,9 @@ impl From<redis::RedisError> for Error {
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{self:?}")
+        match self {
+            Self::Redis { error } => write!(f, "Redis error: {error}"),
+        }
     }
 }
 
@@ -3,6 +3,6 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, FromRedisValue, ToRedisArgs, PartialEq, Eq, Debug)]
 pub struct RateLimitInfo {
-    pub limit: u8,
+    pub limit: u32,
     pub next_reset: i64,
 }
@@ -20,7 +20,7 @@ pub async fn rate_limit(
 ) -> Response {
     let ip_data = state.redis_rate_limiter_db.get_data(ip_addr).await;
 
-    let requests_amount = state.rate_limiter_config.requests_amount;
+    let requests_amount: u32 = state.rate_limiter_config.requests_amount.into();
     let next_reset = Local::now() + REQUESTS_AMOUNT_TIME_FRAME;
 
     if ip_data.is_none() {
@@ -1,5 +1,3 @@
-use std::net::SocketAddr;
-
 use redis::{aio::MultiplexedConnection, AsyncCommands, Client};
 
 use super::{RateLimitInfo, Result};
@@ -8,8 +6,8 @@ pub trait RateLimiterRedisInteractor {
     async fn new(redis_url: String) -> Result<Self>
     where
         Self: Sized;
-    async fn get_data(&self, ip_addr: SocketAddr) -> Option<RateLimitInfo>;
-    async fn set_data(&self, ip_addr: SocketAddr, rate_limit_info: &RateLimitInfo);
+    async fn get_data(&self, key: impl ToString) -> Option<RateLimitInfo>;
+    async fn set_data(&self, key: impl ToString, rate_limit_info: &RateLimitInfo);
 }
 
 #[derive(Clone, Debug)]
@@ -25,8 +23,8 @@ impl RateLimiterRedisInteractor for RedisRateLimiterDb {
         Ok(Self { client, connection })
     }
 
-    async fn get_data(&self, ip_addr: SocketAddr) -> Option<RateLimitInfo> {
-        let key = ip_addr.to_string();
+    async fn get_data(&self, key: impl ToString) -> Option<RateLimitInfo> {
+        let key = key.to_string();
         let mut connection = self.connection.clone();
         connection
             .get::<String, Option<RateLimitInfo>>(key)
@@ -34,8 +32,8 @@ impl RateLimiterRedisInteractor for RedisRateLimiterDb {
             .unwrap()
     }
 
-    async fn set_data(&self, ip_addr: SocketAddr, rate_limit_info: &RateLimitInfo) {
-        let key = ip_addr.to_string();
+    async fn set_data(&self, key: impl ToString, rate_limit_info: &RateLimitInfo) {
+        let key = key.to_string();
         let mut connection = self.connection.clone();
 
         connection
@@ -50,7 +48,7 @@ mod tests {
     use crate::constants::REQUESTS_AMOUNT_LIMIT;
 
     use super::*;
-    use std::str::FromStr;
+    use std::{net::SocketAddr, str::FromStr};
 
     async fn setup_test_db() -> RedisRateLimiterDb {
         let redis_url = "redis://localhost:6379/15"; // using database 15 for testing
@@ -69,7 +67,7 @@ mod tests {
         let db = setup_test_db().await;
         let test_ip = SocketAddr::from_str("127.0.0.1:8080").unwrap();
         let rate_limit_info = RateLimitInfo {
-            limit: REQUESTS_AMOUNT_LIMIT,
+            limit: REQUESTS_AMOUNT_LIMIT.into(),
             next_reset: 0,
         };
 
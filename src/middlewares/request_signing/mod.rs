@@ -0,0 +1,7 @@
+mod nonce_store;
+mod require_signature;
+mod signature;
+
+pub use nonce_store::*;
+pub use require_signature::*;
+pub use signature::*;
@@ -0,0 +1,29 @@
+use redis::{aio::MultiplexedConnection, Client, RedisResult};
+
+#[derive(Clone, Debug)]
+pub struct RedisNonceStore {
+    pub connection: MultiplexedConnection,
+}
+
+impl RedisNonceStore {
+    pub async fn new(client: &Client) -> RedisResult<Self> {
+        let connection = client.get_multiplexed_async_connection().await?;
+        Ok(Self { connection })
+    }
+
+    /// Records the nonce for the given key. Returns `false` if it was already used
+    /// within `ttl_secs`, which means the request is a replay.
+    pub async fn claim(&self, key_id: &str, nonce: &str, ttl_secs: u64) -> RedisResult<bool> {
+        let key = format!("nonce:{key_id}:{nonce}");
+        let mut connection = self.connection.clone();
+        let result: Option<String> = redis::cmd("SET")
+            .arg(key)
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl_secs)
+            .query_async(&mut connection)
+            .await?;
+        Ok(result.is_some())
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{OriginalUri, Request},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use chrono::Utc;
+use serde_json::json;
+
+use crate::{
+    constants::{MAX_SIGNED_BODY_SIZE, SIGNATURE_REPLAY_WINDOW},
+    db::_get_active_api_client_secret,
+    state::AppState,
+};
+
+use super::{canonical_request, is_within_replay_window, verify_signature};
+
+pub const SIGNATURE_KEY_HEADER: &str = "X-Signature-Key";
+pub const SIGNATURE_TIMESTAMP_HEADER: &str = "X-Signature-Timestamp";
+pub const SIGNATURE_NONCE_HEADER: &str = "X-Signature-Nonce";
+pub const SIGNATURE_HEADER: &str = "X-Signature";
+
+fn unauthorized() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(json!({
+            "error":"Unauthorized!"
+        })),
+    )
+        .into_response()
+}
+
+fn header<'a>(headers_map: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers_map.get(name).and_then(|value| value.to_str().ok())
+}
+
+pub async fn require_signature(
+    headers_map: HeaderMap,
+    OriginalUri(original_uri): OriginalUri,
+    Extension(state): Extension<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let (key_id, timestamp, nonce, signature) = match (
+        header(&headers_map, SIGNATURE_KEY_HEADER),
+        header(&headers_map, SIGNATURE_TIMESTAMP_HEADER),
+        header(&headers_map, SIGNATURE_NONCE_HEADER),
+        header(&headers_map, SIGNATURE_HEADER),
+    ) {
+        (Some(key_id), Some(timestamp), Some(nonce), Some(signature)) => {
+            (key_id, timestamp, nonce, signature)
+        }
+        _ => return unauthorized(),
+    };
+
+    let timestamp = match timestamp.parse::<i64>() {
+        Ok(value) => value,
+        Err(_) => return unauthorized(),
+    };
+
+    let replay_window = SIGNATURE_REPLAY_WINDOW.as_secs() as i64;
+    if !is_within_replay_window(timestamp, Utc::now().timestamp(), replay_window) {
+        return unauthorized();
+    }
+
+    let secret = match _get_active_api_client_secret(&state.db, key_id).await {
+        Ok(Some(secret)) => secret,
+        Ok(None) => return unauthorized(),
+        Err(_) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error":"Internal Server Error"
+                })),
+            )
+                .into_response()
+        }
+    };
+
+    let (parts, body) = req.into_parts();
+    let body = match to_bytes(body, MAX_SIGNED_BODY_SIZE).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                Json(json!({
+                    "error":"Payload too large."
+                })),
+            )
+                .into_response()
+        }
+    };
+
+    let path_and_query = original_uri
+        .path_and_query()
+        .map(|path| path.as_str())
+        .unwrap_or_else(|| original_uri.path());
+    let canonical = canonical_request(
+        parts.method.as_str(),
+        path_and_query,
+        timestamp,
+        nonce,
+        &body,
+    );
+
+    if !verify_signature(&secret, &canonical, signature) {
+        return unauthorized();
+    }
+
+    // Nonces are remembered for twice the replay window so a request can't be
+    // replayed from either side of the clock skew allowance.
+    match state
+        .nonce_store
+        .claim(key_id, nonce, SIGNATURE_REPLAY_WINDOW.as_secs() * 2)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => return unauthorized(),
+        Err(_) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error":"Internal Server Error"
+                })),
+            )
+                .into_response()
+        }
+    }
+
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
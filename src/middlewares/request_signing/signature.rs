@@ -0,0 +1,92 @@
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// String the client signs: method, path with query, unix timestamp, nonce and
+/// the hex encoded SHA-256 of the raw body, separated by newlines.
+pub fn canonical_request(
+    method: &str,
+    path_and_query: &str,
+    timestamp: i64,
+    nonce: &str,
+    body: &[u8],
+) -> String {
+    let body_hash = hex::encode(Sha256::digest(body));
+    format!(
+        "{}\n{}\n{}\n{}\n{}",
+        method.to_uppercase(),
+        path_and_query,
+        timestamp,
+        nonce,
+        body_hash
+    )
+}
+
+/// Checks a hex encoded signature in constant time.
+pub fn verify_signature(secret: &str, canonical_request: &str, signature: &str) -> bool {
+    let signature = match hex::decode(signature) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(canonical_request.as_bytes());
+    mac.verify_slice(&signature).is_ok()
+}
+
+pub fn is_within_replay_window(timestamp: i64, now: i64, window_secs: i64) -> bool {
+    (now - timestamp).abs() <= window_secs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign_request(secret: &str, canonical_request: &str) -> String {
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(canonical_request.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let canonical = canonical_request("post", "/api/v1/users/login", 1700000000, "abc", b"{}");
+        let signature = sign_request("secret", &canonical);
+        assert!(verify_signature("secret", &canonical, &signature));
+        assert!(!verify_signature("other-secret", &canonical, &signature));
+    }
+
+    #[test]
+    fn test_tampered_body_is_rejected() {
+        let canonical = canonical_request(
+            "POST",
+            "/api/v1/users/finish",
+            1700000000,
+            "n",
+            b"{\"task_id\":1}",
+        );
+        let signature = sign_request("secret", &canonical);
+        let tampered = canonical_request(
+            "POST",
+            "/api/v1/users/finish",
+            1700000000,
+            "n",
+            b"{\"task_id\":2}",
+        );
+        assert!(!verify_signature("secret", &tampered, &signature));
+    }
+
+    #[test]
+    fn test_malformed_signature_is_rejected() {
+        let canonical = canonical_request("GET", "/api/v1/tasks", 1700000000, "n", b"");
+        assert!(!verify_signature("secret", &canonical, "not-hex"));
+    }
+
+    #[test]
+    fn test_replay_window() {
+        assert!(is_within_replay_window(1000, 1200, 300));
+        assert!(is_within_replay_window(1200, 1000, 300));
+        assert!(!is_within_replay_window(1000, 1301, 300));
+    }
+}
@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{OriginalUri, Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization, HeaderMapExt};
+use redis::RedisResult;
+use serde_json::json;
+use sha3_rust::sha3_256;
+
+use crate::{
+    constants::API_KEY_RATE_LIMIT_TIME_FRAME,
+    db::_use_api_key,
+    models::{ApiKey, ApiScope},
+    state::AppState,
+};
+
+use super::require_signature;
+
+pub const API_KEY_HEADER: &str = "X-Api-Key";
+
+pub fn hash_api_key(api_key: &str) -> String {
+    hex::encode(sha3_256(api_key.as_bytes()))
+}
+
+fn unauthorized() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(json!({
+            "error":"Unauthorized"
+        })),
+    )
+        .into_response()
+}
+
+fn is_dev_secret(headers_map: &HeaderMap, dev_secret: &str) -> bool {
+    headers_map
+        .typed_get::<Authorization<Bearer>>()
+        .is_some_and(|authorization| authorization.token() == dev_secret)
+}
+
+/// Returns `false` once the key used up its requests for the current time frame. The
+/// count is a single `INCR`, so concurrent requests never see the same value. The
+/// frame starts with the first request and ends when the counter expires.
+async fn consume_rate_limit(state: &AppState, api_key: &ApiKey) -> RedisResult<bool> {
+    let limit = match api_key.rate_limit {
+        Some(limit) => i64::from(limit.max(0)),
+        None => return Ok(true),
+    };
+
+    let key = format!("api_key_requests:{}", api_key.id);
+    let mut connection = state.redis_rate_limiter_db.connection.clone();
+    let count: i64 = redis::cmd("INCR")
+        .arg(&key)
+        .query_async(&mut connection)
+        .await?;
+    if count == 1 {
+        redis::cmd("EXPIRE")
+            .arg(&key)
+            .arg(API_KEY_RATE_LIMIT_TIME_FRAME.as_secs())
+            .query_async::<_, ()>(&mut connection)
+            .await?;
+    }
+    Ok(count <= limit)
+}
+
+/// Accepts either the dev secret as a bearer token or an `X-Api-Key` carrying `scope`.
+/// Use with `middleware::from_fn_with_state(scope, require_api_key)`.
+pub async fn require_api_key(
+    State(scope): State<ApiScope>,
+    headers_map: HeaderMap,
+    Extension(state): Extension<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    if is_dev_secret(&headers_map, &state.dev_secret) {
+        return next.run(req).await;
+    }
+
+    let raw_key = match headers_map
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(value) => value,
+        None => return unauthorized(),
+    };
+
+    let api_key = match _use_api_key(&state.db, &hash_api_key(raw_key)).await {
+        Ok(Some(api_key)) => api_key,
+        Ok(None) => return unauthorized(),
+        Err(_) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error":"Internal Server Error"
+                })),
+            )
+                .into_response()
+        }
+    };
+
+    if !api_key.has_scope(scope) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error":"Missing scope.",
+                "scope": scope.as_str()
+            })),
+        )
+            .into_response();
+    }
+
+    match consume_rate_limit(&state, &api_key).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({"error":"Too many requests!"})),
+            )
+                .into_response()
+        }
+        Err(_) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error":"Internal Server Error"
+                })),
+            )
+                .into_response()
+        }
+    }
+
+    req.extensions_mut().insert::<ApiKey>(api_key);
+    next.run(req).await
+}
+
+/// For read routes shared by the frontend and partners: requests carrying an
+/// `X-Api-Key` are checked against `scope`, everything else must be signed.
+pub async fn require_signature_or_api_key(
+    State(scope): State<ApiScope>,
+    headers_map: HeaderMap,
+    original_uri: OriginalUri,
+    extension: Extension<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if headers_map.contains_key(API_KEY_HEADER) {
+        require_api_key(State(scope), headers_map, extension, req, next).await
+    } else {
+        require_signature(headers_map, original_uri, extension, req, next).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use futures_util::future::join_all;
+    use sqlx::postgres::PgPoolOptions;
+
+    use super::*;
+    use crate::{db::_create_api_key, models::CreateApiKeyDTO};
+
+    #[tokio::test]
+    async fn test_concurrent_requests_stay_within_rate_limit() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let suffix = chrono::Local::now().to_string();
+        let api_key = _create_api_key(
+            &pool,
+            CreateApiKeyDTO {
+                name: "limited".to_string(),
+                scopes: vec![ApiScope::TasksRead],
+                rate_limit: Some(3),
+                expires_at: None,
+            },
+            "fk_test",
+            &format!("limited{suffix}"),
+        )
+        .await
+        .unwrap();
+
+        let state = AppState::for_tests(pool).await;
+        let allowed = join_all((0..10).map(|_| consume_rate_limit(&state, &api_key)))
+            .await
+            .into_iter()
+            .filter(|allowed| *allowed.as_ref().unwrap())
+            .count();
+        assert_eq!(allowed, 3);
+    }
+}
@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::prelude::FromRow;
+
+/// A stored airdrop, `merkle_root` is hex encoded.
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct AirdropDistribution {
+    pub id: i32,
+    pub merkle_root: String,
+    pub total_amount: i64,
+    pub allocated_amount: i64,
+    pub num_nodes: i32,
+    /// Allocation formula the amounts were computed with.
+    pub formula: Value,
+    /// Frozen snapshot the allocations were computed from.
+    pub snapshot_id: Option<i32>,
+    /// Api key that generated it.
+    pub created_by: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct AirdropAllocation {
+    pub airdrop_id: i32,
+    pub leaf_index: i64,
+    pub user_id: Option<i32>,
+    pub wallet: String,
+    pub points: i64,
+    pub amount: i64,
+    /// Hex encoded sibling hashes.
+    pub proof: Value,
+}
+
+/// A claimed allocation.
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct AirdropClaim {
+    pub airdrop_id: i32,
+    pub leaf_index: i64,
+    pub signature: Option<String>,
+    /// Block time of the claim transaction.
+    pub claimed_at: Option<DateTime<Utc>>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// A user's allocation in one airdrop and whether it has been claimed.
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct AirdropClaimStatus {
+    pub airdrop_id: i32,
+    pub merkle_root: String,
+    pub leaf_index: i64,
+    pub wallet: String,
+    pub amount: i64,
+    pub claimed: bool,
+    pub signature: Option<String>,
+    pub claimed_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,22 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct ApiClient {
+    pub id: i32,
+    pub name: String,
+    pub key_id: String,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct ApiClientWithSecret {
+    pub id: i32,
+    pub name: String,
+    pub key_id: String,
+    pub secret: String,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
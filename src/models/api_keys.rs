@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ApiScope {
+    #[serde(rename = "tasks:read")]
+    TasksRead,
+    #[serde(rename = "tasks:write")]
+    TasksWrite,
+    #[serde(rename = "users:read")]
+    UsersRead,
+    #[serde(rename = "snapshot:read")]
+    SnapshotRead,
+    #[serde(rename = "snapshot:write")]
+    SnapshotWrite,
+    #[serde(rename = "submissions:review")]
+    SubmissionsReview,
+    #[serde(rename = "airdrop:write")]
+    AirdropWrite,
+}
+
+impl ApiScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TasksRead => "tasks:read",
+            Self::TasksWrite => "tasks:write",
+            Self::UsersRead => "users:read",
+            Self::SnapshotRead => "snapshot:read",
+            Self::SnapshotWrite => "snapshot:write",
+            Self::SubmissionsReview => "submissions:review",
+            Self::AirdropWrite => "airdrop:write",
+        }
+    }
+}
+
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct ApiKey {
+    pub id: i32,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub rate_limit: Option<i32>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    pub fn has_scope(&self, scope: ApiScope) -> bool {
+        self.scopes.iter().any(|s| s == scope.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_serde_matches_as_str() {
+        for scope in [
+            ApiScope::TasksRead,
+            ApiScope::TasksWrite,
+            ApiScope::UsersRead,
+            ApiScope::SnapshotRead,
+            ApiScope::SnapshotWrite,
+            ApiScope::SubmissionsReview,
+            ApiScope::AirdropWrite,
+        ] {
+            let serialized = serde_json::to_string(&scope).unwrap();
+            assert_eq!(serialized, format!("\"{}\"", scope.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_unknown_scope_is_rejected() {
+        assert!(serde_json::from_str::<ApiScope>("\"users:write\"").is_err());
+    }
+}
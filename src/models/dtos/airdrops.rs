@@ -0,0 +1,36 @@
+use serde::Deserialize;
+
+use crate::{
+    airdrop::AllocationFormula,
+    validation::{Validate, ValidationErrors},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateAirdropDTO {
+    /// Token pool in base units, e.g. lamports of the mint.
+    pub total_amount: u64,
+    /// Frozen snapshot to allocate from instead of live points.
+    pub snapshot_id: Option<i32>,
+    /// Plain pro rata over points when left out.
+    #[serde(default)]
+    pub formula: AllocationFormula,
+}
+
+impl Validate for CreateAirdropDTO {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.check(
+            "total_amount",
+            self.total_amount > 0 && self.total_amount <= i64::MAX as u64,
+            format!("Must be between 1 and {}.", i64::MAX),
+        );
+        self.formula.validate("formula", &mut errors);
+        errors.into_result()
+    }
+}
+
+/// Without an id the proof is for the latest airdrop.
+#[derive(Debug, Deserialize)]
+pub struct AirdropProofQueryDTO {
+    pub airdrop_id: Option<i32>,
+}
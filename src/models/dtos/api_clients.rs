@@ -0,0 +1,11 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiClientDTO {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevokeApiClientDTO {
+    pub key_id: String,
+}
@@ -0,0 +1,17 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::models::ApiScope;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyDTO {
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    pub rate_limit: Option<i32>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevokeApiKeyDTO {
+    pub id: i32,
+}
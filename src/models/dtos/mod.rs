@@ -1,5 +1,21 @@
+mod airdrops;
+mod api_clients;
+mod api_keys;
+mod nullable;
+mod quests;
+mod quizzes;
+mod snapshots;
+mod submissions;
 mod tasks;
 mod users;
 
+pub use airdrops::*;
+pub use api_clients::*;
+pub use api_keys::*;
+pub use nullable::*;
+pub use quests::*;
+pub use quizzes::*;
+pub use snapshots::*;
+pub use submissions::*;
 pub use tasks::*;
 pub use users::*;
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Deserializer};
+
+/// Tells an absent field (`None`) apart from an explicit `null` (`Some(None)`), use
+/// together with `#[serde(default)]`.
+pub fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
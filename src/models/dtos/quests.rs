@@ -0,0 +1,22 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateQuestDTO {
+    pub name: String,
+    pub description: Option<String>,
+    pub bonus_points: i32,
+    pub task_ids: Vec<i32>,
+    /// Makes every task a prerequisite of the one after it.
+    pub sequential: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteQuestDTO {
+    pub quest_id: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetPrerequisitesDTO {
+    pub task_id: i32,
+    pub prerequisite_task_ids: Vec<i32>,
+}
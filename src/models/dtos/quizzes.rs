@@ -0,0 +1,29 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateQuizQuestionDTO {
+    pub task_id: i32,
+    pub question: String,
+    pub options: Vec<String>,
+    pub correct_option: i32,
+    pub position: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PutQuizQuestionDTO {
+    pub question_id: i32,
+    pub question: Option<String>,
+    pub options: Option<Vec<String>>,
+    pub correct_option: Option<i32>,
+    pub position: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteQuizQuestionDTO {
+    pub question_id: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QuizQuestionsQueryDTO {
+    pub task_id: i32,
+}
@@ -0,0 +1,28 @@
+use serde::Deserialize;
+
+use crate::{models::WalletChain, snapshots::SnapshotFormat};
+
+/// Without a chain the snapshot lists account wallets, with one it lists the wallets
+/// linked for that chain and leaves out users without one.
+#[derive(Debug, Deserialize)]
+pub struct SnapshotQueryDTO {
+    pub chain: Option<WalletChain>,
+    #[serde(default)]
+    pub format: SnapshotFormat,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FreezeSnapshotDTO {
+    pub chain: Option<WalletChain>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DownloadSnapshotQueryDTO {
+    pub snapshot_id: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnapshotDiffQueryDTO {
+    pub from: i32,
+    pub to: i32,
+}
@@ -0,0 +1,20 @@
+use serde::Deserialize;
+
+use crate::models::SubmissionStatus;
+
+#[derive(Debug, Deserialize)]
+pub struct SubmissionFilterDTO {
+    pub status: Option<SubmissionStatus>,
+    pub task_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApproveSubmissionDTO {
+    pub submission_id: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RejectSubmissionDTO {
+    pub submission_id: i32,
+    pub reason: String,
+}
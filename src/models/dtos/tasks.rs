@@ -1,17 +1,215 @@
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-#[derive(Debug, Deserialize)]
+use crate::{
+    constants::MAX_USER_MULTIPLIER,
+    models::{nullable, QuizAnswer, SolanaAddress, Task, TaskRecurrence, TaskStatus, TaskType},
+    recurrence::validate_recurrence,
+    task_io::TaskFormat,
+    validation::{Validate, ValidationErrors},
+};
+
+/// Sizes of the `VARCHAR` task columns.
+const DESCRIPTION_MAX_LENGTH: usize = 255;
+const LINK_MAX_LENGTH: usize = 120;
+const TASK_BUTTON_TEXT_MAX_LENGTH: usize = 120;
+const CATEGORY_MAX_LENGTH: usize = 64;
+const ICON_URL_MAX_LENGTH: usize = 255;
+const RECURRENCE_SCHEDULE_MAX_LENGTH: usize = 120;
+/// Sizes of the `task_submissions` columns proofs end up in.
+const PROOF_URL_MAX_LENGTH: usize = 2048;
+const PROOF_TEXT_MAX_LENGTH: usize = 4096;
+const PROOF_ANSWER_MAX_LENGTH: usize = 255;
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct CreateTaskDTO {
     pub description: String,
     pub points: i32,
     pub task_button_text: String,
     pub link: Option<String>,
+    pub task_type: Option<TaskType>,
+    pub category: Option<String>,
+    pub icon_url: Option<String>,
+    pub display_order: Option<i32>,
+    pub metadata: Option<Value>,
+    pub status: Option<TaskStatus>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub recurrence: Option<TaskRecurrence>,
+    pub recurrence_schedule: Option<String>,
+    pub max_completions: Option<i32>,
+    pub points_budget: Option<i32>,
+}
+
+/// Field values set by a create or an update, checked the same way for both.
+#[derive(Default)]
+struct TaskFields<'a> {
+    description: Option<&'a str>,
+    points: Option<i32>,
+    task_button_text: Option<&'a str>,
+    link: Option<&'a str>,
+    category: Option<&'a str>,
+    icon_url: Option<&'a str>,
+    metadata: Option<&'a Value>,
+    recurrence_schedule: Option<&'a str>,
+    max_completions: Option<i32>,
+    points_budget: Option<i32>,
+}
+
+impl TaskFields<'_> {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if let Some(description) = self.description {
+            errors.check(
+                "description",
+                !description.trim().is_empty(),
+                "Can't be empty.",
+            );
+            errors.max_length("description", description, DESCRIPTION_MAX_LENGTH);
+        }
+        if let Some(points) = self.points {
+            errors.check("points", points > 0, "Must be positive.");
+        }
+        if let Some(task_button_text) = self.task_button_text {
+            errors.max_length(
+                "task_button_text",
+                task_button_text,
+                TASK_BUTTON_TEXT_MAX_LENGTH,
+            );
+        }
+        if let Some(link) = self.link {
+            errors.url("link", link, LINK_MAX_LENGTH);
+        }
+        if let Some(category) = self.category {
+            errors.max_length("category", category, CATEGORY_MAX_LENGTH);
+        }
+        if let Some(icon_url) = self.icon_url {
+            errors.url("icon_url", icon_url, ICON_URL_MAX_LENGTH);
+        }
+        if let Some(max_completions) = self.max_completions {
+            errors.check(
+                "max_completions",
+                max_completions >= 0,
+                "Can't be negative.",
+            );
+        }
+        if let Some(points_budget) = self.points_budget {
+            errors.check("points_budget", points_budget >= 0, "Can't be negative.");
+        }
+        if let Some(metadata) = self.metadata {
+            errors.check("metadata", metadata.is_object(), "Must be a JSON object.");
+        }
+        if let Some(schedule) = self.recurrence_schedule {
+            errors.max_length(
+                "recurrence_schedule",
+                schedule,
+                RECURRENCE_SCHEDULE_MAX_LENGTH,
+            );
+        }
+    }
+}
+
+/// Checks a whole task, as created or as it would be after an update.
+impl Validate for CreateTaskDTO {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        TaskFields {
+            description: Some(&self.description),
+            points: Some(self.points),
+            task_button_text: Some(&self.task_button_text),
+            link: self.link.as_deref(),
+            category: self.category.as_deref(),
+            icon_url: self.icon_url.as_deref(),
+            metadata: self.metadata.as_ref(),
+            recurrence_schedule: self.recurrence_schedule.as_deref(),
+            max_completions: self.max_completions,
+            points_budget: self.points_budget,
+        }
+        .validate(&mut errors);
+
+        if let (Some(starts_at), Some(ends_at)) = (self.starts_at, self.ends_at) {
+            errors.check("ends_at", ends_at > starts_at, "Must be after starts_at.");
+        }
+        if let Err(err) = validate_recurrence(
+            self.recurrence.unwrap_or(TaskRecurrence::None),
+            self.recurrence_schedule.as_deref(),
+        ) {
+            errors.add("recurrence_schedule", err.to_string());
+        }
+
+        errors.into_result()
+    }
+}
+
+/// Only the fields sent, rules spanning several fields depend on the stored task and
+/// are checked once the update is applied.
+impl Validate for PutTaskDTO {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        TaskFields {
+            description: self.description.as_deref(),
+            points: self.points,
+            task_button_text: self.task_button_text.as_ref().and_then(Option::as_deref),
+            link: self.link.as_ref().and_then(Option::as_deref),
+            category: self.category.as_ref().and_then(Option::as_deref),
+            icon_url: self.icon_url.as_ref().and_then(Option::as_deref),
+            metadata: self.metadata.as_ref(),
+            recurrence_schedule: self.recurrence_schedule.as_ref().and_then(Option::as_deref),
+            max_completions: self.max_completions.flatten(),
+            points_budget: self.points_budget.flatten(),
+        }
+        .validate(&mut errors);
+        errors.into_result()
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct FinishTaskDTO {
     pub task_id: i32,
-    pub wallet:String,
+    pub wallet: SolanaAddress,
+    #[serde(default)]
+    pub proof: TaskProof,
+}
+
+/// What the user submits to prove a task was done, which fields are needed depends
+/// on the task's verifier.
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct TaskProof {
+    /// Link to the retweet, reply or quote tweet, or to whatever was posted.
+    pub url: Option<String>,
+    pub answer: Option<String>,
+    pub code: Option<String>,
+    /// For tasks reviewed by hand, e.g. the caption of a posted meme.
+    pub text: Option<String>,
+    pub screenshot_url: Option<String>,
+    /// Answers to a multiple-choice quiz.
+    pub quiz_answers: Option<Vec<QuizAnswer>>,
+}
+
+impl Validate for FinishTaskDTO {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        for (field, url) in [
+            ("proof.url", &self.proof.url),
+            ("proof.screenshot_url", &self.proof.screenshot_url),
+        ] {
+            if let Some(url) = url {
+                errors.url(field, url, PROOF_URL_MAX_LENGTH);
+            }
+        }
+        for (field, value) in [
+            ("proof.answer", &self.proof.answer),
+            ("proof.code", &self.proof.code),
+        ] {
+            if let Some(value) = value {
+                errors.max_length(field, value, PROOF_ANSWER_MAX_LENGTH);
+            }
+        }
+        if let Some(text) = &self.proof.text {
+            errors.max_length("proof.text", text, PROOF_TEXT_MAX_LENGTH);
+        }
+        errors.into_result()
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -19,13 +217,95 @@ pub struct DeleteTaskDTO {
     pub task_id: i32,
 }
 
+/// Absent fields stay as they are, an explicit `null` clears a nullable field.
 #[derive(Debug, Deserialize)]
 pub struct PutTaskDTO {
     pub task_id: i32,
     pub description: Option<String>,
     pub points: Option<i32>,
-    pub link: Option<String>,
-    pub task_button_text: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub link: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub task_button_text: Option<Option<String>>,
+    pub task_type: Option<TaskType>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub category: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub icon_url: Option<Option<String>>,
+    pub display_order: Option<i32>,
+    pub metadata: Option<Value>,
+    pub status: Option<TaskStatus>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub starts_at: Option<Option<DateTime<Utc>>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub ends_at: Option<Option<DateTime<Utc>>>,
+    pub recurrence: Option<TaskRecurrence>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub recurrence_schedule: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub max_completions: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub points_budget: Option<Option<i32>>,
+}
+
+impl PutTaskDTO {
+    /// The task as it would be after the update.
+    pub fn apply(self, mut task: Task) -> Task {
+        task.description = self.description.unwrap_or(task.description);
+        task.points = self.points.unwrap_or(task.points);
+        task.link = self.link.unwrap_or(task.link);
+        task.task_button_text = self.task_button_text.unwrap_or(task.task_button_text);
+        task.task_type = self.task_type.unwrap_or(task.task_type);
+        task.category = self.category.unwrap_or(task.category);
+        task.icon_url = self.icon_url.unwrap_or(task.icon_url);
+        task.display_order = self.display_order.unwrap_or(task.display_order);
+        task.metadata = self.metadata.unwrap_or(task.metadata);
+        task.status = self.status.unwrap_or(task.status);
+        task.starts_at = self.starts_at.unwrap_or(task.starts_at);
+        task.ends_at = self.ends_at.unwrap_or(task.ends_at);
+        task.recurrence = self.recurrence.unwrap_or(task.recurrence);
+        task.recurrence_schedule = self.recurrence_schedule.unwrap_or(task.recurrence_schedule);
+        task.max_completions = self.max_completions.unwrap_or(task.max_completions);
+        task.points_budget = self.points_budget.unwrap_or(task.points_budget);
+        task
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TaskFilterDTO {
+    pub task_type: Option<TaskType>,
+    pub category: Option<String>,
+    /// Only honoured for admins, users always get available tasks.
+    pub status: Option<TaskStatus>,
+    /// Only honoured for admins, lists deleted tasks instead of the others.
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TaskRevisionsQueryDTO {
+    pub task_id: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RestoreTaskRevisionDTO {
+    pub task_id: i32,
+    pub revision: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportTasksQueryDTO {
+    #[serde(default)]
+    pub format: TaskFormat,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportTasksQueryDTO {
+    #[serde(default)]
+    pub format: TaskFormat,
+    pub status: Option<TaskStatus>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -33,3 +313,99 @@ pub struct SetMultiplierDTO {
     pub twitter_id: String,
     pub multiplier: i32,
 }
+
+impl Validate for SetMultiplierDTO {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.check("twitter_id", !self.twitter_id.is_empty(), "Can't be empty.");
+        errors.check(
+            "multiplier",
+            (1..=MAX_USER_MULTIPLIER).contains(&self.multiplier),
+            format!("Must be between 1 and {MAX_USER_MULTIPLIER}."),
+        );
+        errors.into_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verification::tests::task;
+
+    fn fields(result: Result<(), ValidationErrors>) -> Vec<String> {
+        match result {
+            Ok(()) => vec![],
+            Err(errors) => errors.0.into_iter().map(|error| error.field).collect(),
+        }
+    }
+
+    #[test]
+    fn test_valid_task() {
+        let mut task: CreateTaskDTO = task(TaskType::Follow, serde_json::json!({})).into();
+        task.link = Some("https://x.com/frog".to_string());
+        assert!(fields(task.validate()).is_empty());
+    }
+
+    #[test]
+    fn test_every_bad_field_is_reported() {
+        let mut task: CreateTaskDTO = task(TaskType::Follow, serde_json::json!({})).into();
+        task.description = "a".repeat(256);
+        task.points = -5;
+        task.link = Some("javascript:alert(1)".to_string());
+        task.category = Some("c".repeat(65));
+        task.metadata = Some(serde_json::json!([1]));
+        task.recurrence = Some(TaskRecurrence::Cron);
+        assert_eq!(
+            fields(task.validate()),
+            vec![
+                "description",
+                "points",
+                "link",
+                "category",
+                "metadata",
+                "recurrence_schedule"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_put_checks_only_sent_fields() {
+        let put: PutTaskDTO = serde_json::from_value(serde_json::json!({
+            "task_id": 1,
+            "link": null,
+            "icon_url": "not a url",
+            "points": 0
+        }))
+        .unwrap();
+        assert_eq!(put.link, Some(None));
+        assert_eq!(put.category, None);
+        assert_eq!(fields(put.validate()), vec!["points", "icon_url"]);
+    }
+
+    #[test]
+    fn test_finish_task_proof() {
+        let finish: FinishTaskDTO = serde_json::from_value(serde_json::json!({
+            "task_id": 1,
+            "wallet": "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU",
+            "proof": { "url": "ftp://x.com/frog", "answer": "a".repeat(256) }
+        }))
+        .unwrap();
+        assert_eq!(fields(finish.validate()), vec!["proof.url", "proof.answer"]);
+        assert!(serde_json::from_value::<FinishTaskDTO>(serde_json::json!({
+            "task_id": 1,
+            "wallet": "memer-wallet"
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_multiplier_range() {
+        let set = |multiplier| SetMultiplierDTO {
+            twitter_id: "frog".to_string(),
+            multiplier,
+        };
+        assert!(set(2).validate().is_ok());
+        assert!(set(0).validate().is_err());
+        assert!(set(MAX_USER_MULTIPLIER + 1).validate().is_err());
+    }
+}
@@ -1,28 +1,133 @@
 use serde::Deserialize;
 
+use crate::{
+    constants::PASSWORD_MIN_LENGTH,
+    models::{SolanaAddress, WalletChain},
+    validation::{Validate, ValidationErrors},
+    wallets::normalize_address,
+};
+
+/// Size of the `VARCHAR` user columns.
+const USER_FIELD_MAX_LENGTH: usize = 255;
+/// Longer passwords only slow down hashing.
+const PASSWORD_MAX_LENGTH: usize = 128;
+/// Size of `wallet_link_challenges.nonce`.
+const NONCE_MAX_LENGTH: usize = 64;
+/// Generous for a base58 ed25519 or hex secp256k1 signature.
+const SIGNATURE_MAX_LENGTH: usize = 200;
+
 #[derive(Debug, Deserialize)]
 pub struct CreateUserDTO {
     pub twitter_id: String,
-    pub solana_adr: String,
+    pub solana_adr: SolanaAddress,
     pub password: String,
     pub reffer_code: Option<String>,
 }
 
+impl Validate for CreateUserDTO {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.check(
+            "twitter_id",
+            !self.twitter_id.trim().is_empty(),
+            "Can't be empty.",
+        );
+        errors.max_length("twitter_id", &self.twitter_id, USER_FIELD_MAX_LENGTH);
+        errors.check(
+            "solana_adr",
+            self.solana_adr.is_on_curve(),
+            "Must be a wallet address, not a program derived one.",
+        );
+        errors.check(
+            "password",
+            self.password.chars().count() >= PASSWORD_MIN_LENGTH,
+            format!("Must be at least {PASSWORD_MIN_LENGTH} characters."),
+        );
+        errors.max_length("password", &self.password, PASSWORD_MAX_LENGTH);
+        if let Some(reffer_code) = &self.reffer_code {
+            errors.max_length("reffer_code", reffer_code, USER_FIELD_MAX_LENGTH);
+        }
+        errors.into_result()
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct BindWalletAddressDTO {
     pub twitter_id: String,
-    pub wallet_address: String,
+    pub wallet_address: SolanaAddress,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct LoginUserDTO {
     pub twitter_id: String,
-    pub solana_adr: String,
+    pub solana_adr: SolanaAddress,
     pub password: String,
 }
 
+/// Existing passwords may be shorter than what sign up asks for now, so only empty
+/// ones are turned away.
+impl Validate for LoginUserDTO {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.check("twitter_id", !self.twitter_id.is_empty(), "Can't be empty.");
+        errors.check("password", !self.password.is_empty(), "Can't be empty.");
+        errors.max_length("password", &self.password, PASSWORD_MAX_LENGTH);
+        errors.into_result()
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ValidateJwtDTO {
     pub username: String,
-    pub solana_adr: String,
+    pub solana_adr: SolanaAddress,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwitterCallbackDTO {
+    pub code: String,
+    pub state: String,
+}
+
+/// Asks for the message a wallet has to sign before it can be linked.
+#[derive(Debug, Deserialize)]
+pub struct WalletChallengeDTO {
+    pub chain: WalletChain,
+    pub address: String,
+}
+
+impl Validate for WalletChallengeDTO {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        if let Err(err) = normalize_address(self.chain, &self.address) {
+            errors.add("address", err.to_string());
+        }
+        errors.into_result()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LinkWalletDTO {
+    pub chain: WalletChain,
+    pub address: String,
+    pub nonce: String,
+    pub signature: String,
+}
+
+impl Validate for LinkWalletDTO {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        if let Err(err) = normalize_address(self.chain, &self.address) {
+            errors.add("address", err.to_string());
+        }
+        errors.check("nonce", !self.nonce.is_empty(), "Can't be empty.");
+        errors.max_length("nonce", &self.nonce, NONCE_MAX_LENGTH);
+        errors.check("signature", !self.signature.is_empty(), "Can't be empty.");
+        errors.max_length("signature", &self.signature, SIGNATURE_MAX_LENGTH);
+        errors.into_result()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnlinkWalletDTO {
+    pub chain: WalletChain,
 }
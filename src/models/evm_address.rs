@@ -0,0 +1,150 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+/// A 20 byte EVM account address, kept in its EIP-55 checksummed form so equal
+/// addresses compare equal as strings.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(try_from = "String", into = "String")]
+#[sqlx(transparent)]
+pub struct EvmAddress(String);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvmAddressError {
+    InvalidHex,
+    /// Mixed case that doesn't match the EIP-55 checksum, usually a typo.
+    InvalidChecksum,
+}
+
+impl fmt::Display for EvmAddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvmAddressError::InvalidHex => {
+                write!(f, "EVM address must be 0x followed by 40 hex digits")
+            }
+            EvmAddressError::InvalidChecksum => write!(f, "EVM address has a wrong checksum"),
+        }
+    }
+}
+
+impl std::error::Error for EvmAddressError {}
+
+impl EvmAddress {
+    /// All lower or all upper case addresses carry no checksum and are accepted as
+    /// they are, mixed case ones have to match it.
+    pub fn parse(value: &str) -> Result<EvmAddress, EvmAddressError> {
+        let value = value.trim();
+        let digits = value
+            .strip_prefix("0x")
+            .or_else(|| value.strip_prefix("0X"))
+            .ok_or(EvmAddressError::InvalidHex)?;
+        if digits.len() != 40 {
+            return Err(EvmAddressError::InvalidHex);
+        }
+        let bytes: [u8; 20] = hex::decode(digits)
+            .map_err(|_| EvmAddressError::InvalidHex)?
+            .try_into()
+            .map_err(|_| EvmAddressError::InvalidHex)?;
+
+        let address = EvmAddress::from_bytes(bytes);
+        let has_lower = digits.chars().any(|c| c.is_ascii_lowercase());
+        let has_upper = digits.chars().any(|c| c.is_ascii_uppercase());
+        if has_lower && has_upper && address.0[2..] != *digits {
+            return Err(EvmAddressError::InvalidChecksum);
+        }
+        Ok(address)
+    }
+
+    pub fn from_bytes(bytes: [u8; 20]) -> EvmAddress {
+        let lower = hex::encode(bytes);
+        let hash = Keccak256::digest(lower.as_bytes());
+        let checksummed = lower
+            .chars()
+            .enumerate()
+            .map(|(index, c)| {
+                let nibble = (hash[index / 2] >> (4 * (1 - index % 2))) & 0x0f;
+                if nibble >= 8 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect::<String>();
+        EvmAddress(format!("0x{checksummed}"))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for EvmAddress {
+    type Err = EvmAddressError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        EvmAddress::parse(value)
+    }
+}
+
+impl TryFrom<String> for EvmAddress {
+    type Error = EvmAddressError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        EvmAddress::parse(&value)
+    }
+}
+
+impl From<EvmAddress> for String {
+    fn from(address: EvmAddress) -> Self {
+        address.0
+    }
+}
+
+impl fmt::Display for EvmAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum() {
+        // Examples from EIP-55.
+        for address in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            assert_eq!(EvmAddress::parse(address).unwrap().as_str(), address);
+            assert_eq!(
+                EvmAddress::parse(&address.to_lowercase()).unwrap().as_str(),
+                address
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects() {
+        assert_eq!(
+            EvmAddress::parse("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD"),
+            Err(EvmAddressError::InvalidChecksum)
+        );
+        assert_eq!(
+            EvmAddress::parse("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"),
+            Err(EvmAddressError::InvalidHex)
+        );
+        assert_eq!(
+            EvmAddress::parse("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA"),
+            Err(EvmAddressError::InvalidHex)
+        );
+        assert_eq!(
+            EvmAddress::parse("0xzaAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"),
+            Err(EvmAddressError::InvalidHex)
+        );
+    }
+}
@@ -1,7 +1,29 @@
+mod airdrops;
+mod api_clients;
+mod api_keys;
 mod dtos;
+mod evm_address;
+mod quests;
+mod quizzes;
+mod snapshots;
+mod solana_address;
+mod submissions;
+mod task_revisions;
 mod tasks;
 mod users;
+mod wallets;
 
+pub use airdrops::*;
+pub use api_clients::*;
+pub use api_keys::*;
 pub use dtos::*;
+pub use evm_address::*;
+pub use quests::*;
+pub use quizzes::*;
+pub use snapshots::*;
+pub use solana_address::*;
+pub use submissions::*;
+pub use task_revisions::*;
 pub use tasks::*;
 pub use users::*;
+pub use wallets::*;
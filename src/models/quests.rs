@@ -0,0 +1,12 @@
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct Quest {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub bonus_points: i32,
+    /// Tasks in quest order.
+    pub task_ids: Vec<i32>,
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct QuizQuestion {
+    pub id: i32,
+    pub task_id: i32,
+    pub question: String,
+    pub options: Vec<String>,
+    /// Index into `options`, only ever sent to admins.
+    pub correct_option: i32,
+    pub position: i32,
+}
+
+/// A question as users see it, without the answer.
+#[derive(Debug, Serialize, Clone)]
+pub struct QuizQuestionView {
+    pub id: i32,
+    pub question: String,
+    pub options: Vec<String>,
+}
+
+impl From<QuizQuestion> for QuizQuestionView {
+    fn from(question: QuizQuestion) -> Self {
+        Self {
+            id: question.id,
+            question: question.question,
+            options: question.options,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct QuizAnswer {
+    pub question_id: i32,
+    pub option: i32,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct QuizScore {
+    pub correct: i32,
+    pub total: i32,
+}
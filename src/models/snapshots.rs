@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::prelude::FromRow;
+
+use super::WalletChain;
+
+/// A frozen snapshot, its entries are stored separately.
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct Snapshot {
+    pub id: i32,
+    pub chain: Option<WalletChain>,
+    /// `SnapshotParameters` it was computed with.
+    pub parameters: Value,
+    pub content_hash: String,
+    pub num_entries: i32,
+    pub total_points: i64,
+    /// Api key that froze it.
+    pub created_by: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SnapshotEntry {
+    pub user_id: i32,
+    pub twitter_id: String,
+    pub wallet_address: String,
+    pub points: i64,
+}
@@ -0,0 +1,170 @@
+use std::{fmt, str::FromStr};
+
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use serde::{Deserialize, Serialize};
+
+/// A Solana public key in its base58 form. Parsing checks that it decodes to exactly
+/// 32 bytes and keeps the canonical encoding, so equal keys compare equal as strings.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(try_from = "String", into = "String")]
+#[sqlx(transparent)]
+pub struct SolanaAddress(String);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolanaAddressError {
+    InvalidBase58,
+    InvalidLength { bytes: usize },
+}
+
+impl fmt::Display for SolanaAddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolanaAddressError::InvalidBase58 => write!(f, "Solana address is not valid base58"),
+            SolanaAddressError::InvalidLength { bytes } => {
+                write!(f, "Solana address decodes to {bytes} bytes instead of 32")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SolanaAddressError {}
+
+impl SolanaAddress {
+    pub fn parse(value: &str) -> Result<SolanaAddress, SolanaAddressError> {
+        let bytes = bs58::decode(value.trim())
+            .into_vec()
+            .map_err(|_| SolanaAddressError::InvalidBase58)?;
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|bytes: Vec<u8>| SolanaAddressError::InvalidLength { bytes: bytes.len() })?;
+        Ok(SolanaAddress::from_bytes(bytes))
+    }
+
+    pub fn from_bytes(bytes: [u8; 32]) -> SolanaAddress {
+        SolanaAddress(bs58::encode(bytes).into_string())
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        bs58::decode(&self.0)
+            .onto(&mut bytes)
+            .expect("parsed addresses are 32 bytes");
+        bytes
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the key is a point on ed25519, i.e. something a wallet can sign for.
+    /// Program derived addresses are deliberately off the curve.
+    pub fn is_on_curve(&self) -> bool {
+        CompressedEdwardsY(self.to_bytes()).decompress().is_some()
+    }
+
+    #[cfg(test)]
+    pub fn random() -> SolanaAddress {
+        SolanaAddress::from_bytes(rand::random())
+    }
+}
+
+impl FromStr for SolanaAddress {
+    type Err = SolanaAddressError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        SolanaAddress::parse(value)
+    }
+}
+
+impl TryFrom<String> for SolanaAddress {
+    type Error = SolanaAddressError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        SolanaAddress::parse(&value)
+    }
+}
+
+impl From<SolanaAddress> for String {
+    fn from(address: SolanaAddress) -> Self {
+        address.0
+    }
+}
+
+impl fmt::Display for SolanaAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl PartialEq<str> for SolanaAddress {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<String> for SolanaAddress {
+    fn eq(&self, other: &String) -> bool {
+        self.0 == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The system program, all zero bytes.
+    const SYSTEM_PROGRAM: &str = "11111111111111111111111111111111";
+
+    #[test]
+    fn test_parse() {
+        let address = SolanaAddress::parse("7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU").unwrap();
+        assert_eq!(
+            address.as_str(),
+            "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU"
+        );
+        assert_eq!(
+            SolanaAddress::parse(SYSTEM_PROGRAM).unwrap().to_bytes(),
+            [0; 32]
+        );
+        assert_eq!(
+            SolanaAddress::parse(" 11111111111111111111111111111111\n")
+                .unwrap()
+                .as_str(),
+            SYSTEM_PROGRAM
+        );
+
+        assert_eq!(
+            SolanaAddress::parse("7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAs0"),
+            Err(SolanaAddressError::InvalidBase58)
+        );
+        assert_eq!(
+            SolanaAddress::parse("7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsUU"),
+            Err(SolanaAddressError::InvalidLength { bytes: 33 })
+        );
+        assert_eq!(
+            SolanaAddress::parse("frog"),
+            Err(SolanaAddressError::InvalidLength { bytes: 3 })
+        );
+    }
+
+    #[test]
+    fn test_is_on_curve() {
+        let wallet = SolanaAddress::parse("7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU").unwrap();
+        assert!(wallet.is_on_curve());
+        // y = 7 has no matching x on ed25519.
+        let mut bytes = [0; 32];
+        bytes[0] = 7;
+        assert!(!SolanaAddress::from_bytes(bytes).is_on_curve());
+    }
+
+    #[test]
+    fn test_serde() {
+        let address: SolanaAddress =
+            serde_json::from_str("\"7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU\"").unwrap();
+        assert_eq!(
+            serde_json::to_string(&address).unwrap(),
+            "\"7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU\""
+        );
+        assert!(serde_json::from_str::<SolanaAddress>("\"memer-wallet\"").is_err());
+    }
+}
@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "submission_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// Proof of a task waiting for, or past, manual review.
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct TaskSubmission {
+    pub id: i32,
+    pub user_id: i32,
+    pub task_id: i32,
+    pub period_key: String,
+    pub status: SubmissionStatus,
+    pub url: Option<String>,
+    pub text: Option<String>,
+    pub screenshot_url: Option<String>,
+    /// Why the submission was rejected.
+    pub reason: Option<String>,
+    /// Api key of the reviewer.
+    pub reviewed_by: Option<i32>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
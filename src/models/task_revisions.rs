@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::prelude::FromRow;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "task_revision_action", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TaskRevisionAction {
+    Created,
+    Updated,
+    Deleted,
+    Restored,
+}
+
+/// One change to a task. Revisions count up from 1 per task.
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct TaskRevision {
+    pub id: i32,
+    pub task_id: i32,
+    pub revision: i32,
+    pub action: TaskRevisionAction,
+    /// Editable fields after the change, restoring the revision puts them back.
+    pub snapshot: Value,
+    /// Changed fields as `{"field": {"from": ..., "to": ...}}`.
+    pub diff: Value,
+    /// Api key that made the change, `None` for the dev secret and the CLI.
+    pub changed_by: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
@@ -1,6 +1,53 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use sqlx::prelude::FromRow;
 
+/// Metadata keys only admins and verifiers get to see.
+const SECRET_METADATA_KEYS: [&str; 4] = ["answer", "answers", "code_word", "verifier_url"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "task_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TaskType {
+    Follow,
+    Retweet,
+    Like,
+    Reply,
+    QuoteTweet,
+    JoinDiscord,
+    VisitLink,
+    Quiz,
+    Custom,
+    /// Daily check-in that builds up the user's streak.
+    CheckIn,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "task_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Draft,
+    /// Waiting for `starts_at`, becomes available on its own once it passes.
+    Scheduled,
+    Active,
+    Paused,
+    Archived,
+    /// Closed automatically once `max_completions` or `points_budget` ran out.
+    Exhausted,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "task_recurrence", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TaskRecurrence {
+    None,
+    Daily,
+    Weekly,
+    /// Uses `recurrence_schedule` as a cron expression.
+    Cron,
+}
+
 #[derive(Debug, FromRow, Serialize, Deserialize, Clone)]
 pub struct Task {
     pub id: i32,
@@ -8,9 +55,180 @@ pub struct Task {
     pub points: i32,
     pub link: Option<String>,
     pub task_button_text: Option<String>,
+    pub task_type: TaskType,
+    pub category: Option<String>,
+    pub icon_url: Option<String>,
+    pub display_order: i32,
+    pub metadata: Value,
+    pub status: TaskStatus,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub recurrence: TaskRecurrence,
+    pub recurrence_schedule: Option<String>,
+    pub max_completions: Option<i32>,
+    pub points_budget: Option<i32>,
+    pub completions_count: i32,
+    pub points_spent: i32,
+    /// Set once the task is deleted, the row stays for the completions pointing to it.
+    pub archived_at: Option<DateTime<Utc>>,
+    /// Bumped with every revision, sent as the ETag of the task.
+    pub version: i32,
 }
 
-#[derive(Debug, FromRow, Serialize)]
-pub struct TaskPoints {
-    pub points: i32,
+/// What is left of a capped task, `None` meaning unlimited.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct TaskCapacity {
+    pub remaining_completions: Option<i32>,
+    pub remaining_budget: Option<i32>,
+}
+
+/// A task as a specific user sees it.
+#[derive(Debug, Serialize, Clone)]
+pub struct TaskView {
+    #[serde(flatten)]
+    pub task: Task,
+    pub prerequisite_task_ids: Vec<i32>,
+    /// Some prerequisite isn't completed yet. Always set for anonymous requests
+    /// when the task has prerequisites.
+    pub locked: bool,
+}
+
+impl Task {
+    /// Whether users can see and complete the task at `now`.
+    pub fn is_available(&self, now: DateTime<Utc>) -> bool {
+        self.archived_at.is_none()
+            && matches!(self.status, TaskStatus::Active | TaskStatus::Scheduled)
+            && self.starts_at.is_none_or(|starts_at| starts_at <= now)
+            && self.ends_at.is_none_or(|ends_at| ends_at > now)
+    }
+
+    /// Tasks with `"manual_review": true` in their metadata are credited once an admin
+    /// approves the submission.
+    pub fn requires_review(&self) -> bool {
+        self.metadata
+            .get("manual_review")
+            .and_then(Value::as_bool)
+            .unwrap_or_default()
+    }
+
+    /// Drops metadata that would give away answers or verifier endpoints, for tasks
+    /// sent to users.
+    pub fn redact_secrets(&mut self) {
+        if let Some(metadata) = self.metadata.as_object_mut() {
+            for key in SECRET_METADATA_KEYS {
+                metadata.remove(key);
+            }
+        }
+    }
+
+    pub fn capacity(&self) -> TaskCapacity {
+        TaskCapacity {
+            remaining_completions: self
+                .max_completions
+                .map(|max_completions| (max_completions - self.completions_count).max(0)),
+            remaining_budget: self
+                .points_budget
+                .map(|points_budget| (points_budget - self.points_spent).max(0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn task(
+        status: TaskStatus,
+        starts_at: Option<DateTime<Utc>>,
+        ends_at: Option<DateTime<Utc>>,
+    ) -> Task {
+        Task {
+            id: 1,
+            description: "task".to_string(),
+            points: 10,
+            link: None,
+            task_button_text: None,
+            task_type: TaskType::Custom,
+            category: None,
+            icon_url: None,
+            display_order: 0,
+            metadata: Value::Null,
+            status,
+            starts_at,
+            ends_at,
+            recurrence: TaskRecurrence::None,
+            recurrence_schedule: None,
+            max_completions: None,
+            points_budget: None,
+            completions_count: 0,
+            points_spent: 0,
+            archived_at: None,
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn test_active_task_without_window_is_available() {
+        assert!(task(TaskStatus::Active, None, None).is_available(Utc::now()));
+    }
+
+    #[test]
+    fn test_inactive_statuses_are_unavailable() {
+        let now = Utc::now();
+        for status in [
+            TaskStatus::Draft,
+            TaskStatus::Paused,
+            TaskStatus::Archived,
+            TaskStatus::Exhausted,
+        ] {
+            assert!(!task(status, None, None).is_available(now));
+        }
+    }
+
+    #[test]
+    fn test_deleted_task_is_unavailable() {
+        let mut task = task(TaskStatus::Active, None, None);
+        task.archived_at = Some(Utc::now());
+        assert!(!task.is_available(Utc::now()));
+    }
+
+    #[test]
+    fn test_window_is_respected() {
+        let now = Utc::now();
+        let hour = Duration::hours(1);
+        assert!(!task(TaskStatus::Scheduled, Some(now + hour), None).is_available(now));
+        assert!(task(TaskStatus::Scheduled, Some(now - hour), None).is_available(now));
+        assert!(!task(TaskStatus::Active, None, Some(now - hour)).is_available(now));
+        assert!(task(TaskStatus::Active, Some(now - hour), Some(now + hour)).is_available(now));
+    }
+
+    #[test]
+    fn test_capacity_is_unlimited_without_caps() {
+        let capacity = task(TaskStatus::Active, None, None).capacity();
+        assert_eq!(capacity.remaining_completions, None);
+        assert_eq!(capacity.remaining_budget, None);
+    }
+
+    #[test]
+    fn test_capacity_counts_down() {
+        let mut task = task(TaskStatus::Active, None, None);
+        task.max_completions = Some(10);
+        task.points_budget = Some(100);
+        task.completions_count = 4;
+        task.points_spent = 120;
+        let capacity = task.capacity();
+        assert_eq!(capacity.remaining_completions, Some(6));
+        assert_eq!(capacity.remaining_budget, Some(0));
+    }
+
+    #[test]
+    fn test_redact_secrets() {
+        let mut task = task(TaskStatus::Active, None, None);
+        task.metadata =
+            serde_json::json!({ "answer": "sol", "code_word": "ribbit", "target": "frog" });
+        task.redact_secrets();
+        assert_eq!(task.metadata, serde_json::json!({ "target": "frog" }));
+    }
 }
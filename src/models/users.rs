@@ -1,10 +1,15 @@
+use chrono::NaiveDate;
 use serde::Serialize;
 use sqlx::prelude::FromRow;
 
+use super::SnapshotEntry;
+
 #[derive(Debug, FromRow, Serialize, Clone)]
 pub struct User {
     pub id: i32,
     pub wallet_address: String,
+    /// Set for wallets stored before addresses were checked, cleared by binding a new one.
+    pub wallet_address_invalid: bool,
     pub twitter_id: String,
     pub referral_code: String,
     pub total_points: i32,
@@ -13,19 +18,37 @@ pub struct User {
     pub referred_by: Vec<i32>,
     pub referrer_id: Option<i32>,
     pub multiplier: i32,
+    pub current_streak: i32,
+    pub longest_streak: i32,
+    pub last_check_in: Option<NaiveDate>,
+    pub streak_freezes: i32,
+    /// Numeric X user id, set once the account was linked through OAuth.
+    pub twitter_user_id: Option<String>,
+    pub twitter_handle: Option<String>,
 }
 
 #[derive(Serialize)]
 pub struct UserSnapshot {
     pub twitter_id: String,
     pub wallet_address: String,
-    pub points: i32,
+    pub points: i64,
+}
+
+impl From<SnapshotEntry> for UserSnapshot {
+    fn from(entry: SnapshotEntry) -> Self {
+        UserSnapshot {
+            twitter_id: entry.twitter_id,
+            wallet_address: entry.wallet_address,
+            points: entry.points,
+        }
+    }
 }
 
 #[derive(Debug, FromRow, Serialize, Clone)]
 pub struct UserWithEncryptedPassword {
     pub id: i32,
     pub wallet_address: String,
+    pub wallet_address_invalid: bool,
     pub twitter_id: String,
     pub referral_code: String,
     pub total_points: i32,
@@ -35,6 +58,24 @@ pub struct UserWithEncryptedPassword {
     pub referrer_id: Option<i32>,
     pub encrypted_password: String,
     pub multiplier: i32,
+    pub current_streak: i32,
+    pub longest_streak: i32,
+    pub last_check_in: Option<NaiveDate>,
+    pub streak_freezes: i32,
+    pub twitter_user_id: Option<String>,
+    pub twitter_handle: Option<String>,
+}
+
+impl User {
+    /// Whether `username` names this user, by their verified X id once the account is
+    /// linked and by the `twitter_id` they registered until then, which nobody checked.
+    /// Handles can be changed and taken over, so they don't count.
+    pub fn is_named(&self, username: &str) -> bool {
+        match &self.twitter_user_id {
+            Some(twitter_user_id) => twitter_user_id == username,
+            None => username == self.twitter_id,
+        }
+    }
 }
 
 impl From<UserWithEncryptedPassword> for User {
@@ -48,8 +89,15 @@ impl From<UserWithEncryptedPassword> for User {
             total_points: user.total_points,
             twitter_id: user.twitter_id,
             wallet_address: user.wallet_address,
+            wallet_address_invalid: user.wallet_address_invalid,
             finished_tasks: user.finished_tasks,
             multiplier: user.multiplier,
+            current_streak: user.current_streak,
+            longest_streak: user.longest_streak,
+            last_check_in: user.last_check_in,
+            streak_freezes: user.streak_freezes,
+            twitter_user_id: user.twitter_user_id,
+            twitter_handle: user.twitter_handle,
         }
     }
 }
@@ -0,0 +1,51 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, sqlx::Type)]
+#[sqlx(type_name = "wallet_chain", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum WalletChain {
+    Solana,
+    Ethereum,
+    Base,
+    Arbitrum,
+    Polygon,
+}
+
+impl WalletChain {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WalletChain::Solana => "solana",
+            WalletChain::Ethereum => "ethereum",
+            WalletChain::Base => "base",
+            WalletChain::Arbitrum => "arbitrum",
+            WalletChain::Polygon => "polygon",
+        }
+    }
+
+    /// EVM chains share address and signature formats.
+    pub fn is_evm(self) -> bool {
+        !matches!(self, WalletChain::Solana)
+    }
+}
+
+impl fmt::Display for WalletChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A wallet the user linked for airdrops on one chain.
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct UserWallet {
+    pub id: i32,
+    pub user_id: i32,
+    pub chain: WalletChain,
+    pub address: String,
+    /// When ownership was proven with a signature, `None` for the sign up wallet.
+    pub verified_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Visiting,
+    Done,
+}
+
+/// Checks whether `(task_id, prerequisite_task_id)` edges contain a cycle, in which
+/// case some tasks could never be unlocked.
+pub fn has_cycle(edges: &[(i32, i32)]) -> bool {
+    let mut graph: HashMap<i32, Vec<i32>> = HashMap::new();
+    for &(task_id, prerequisite_task_id) in edges {
+        graph.entry(task_id).or_default().push(prerequisite_task_id);
+    }
+
+    let mut marks: HashMap<i32, Mark> = HashMap::new();
+    for &start in graph.keys() {
+        if marks.contains_key(&start) {
+            continue;
+        }
+        marks.insert(start, Mark::Visiting);
+        // Iterative DFS, each entry is a node and the index of its next child.
+        let mut stack: Vec<(i32, usize)> = vec![(start, 0)];
+        while let Some((node, next_child)) = stack.last().copied() {
+            let child = graph
+                .get(&node)
+                .and_then(|children| children.get(next_child))
+                .copied();
+            match child {
+                Some(child) => {
+                    if let Some(top) = stack.last_mut() {
+                        top.1 += 1;
+                    }
+                    match marks.get(&child) {
+                        Some(Mark::Visiting) => return true,
+                        Some(Mark::Done) => {}
+                        None => {
+                            marks.insert(child, Mark::Visiting);
+                            stack.push((child, 0));
+                        }
+                    }
+                }
+                None => {
+                    marks.insert(node, Mark::Done);
+                    stack.pop();
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Prerequisite edges that turn `task_ids` into a chain, each task unlocking the next.
+pub fn chain_edges(task_ids: &[i32]) -> Vec<(i32, i32)> {
+    task_ids.windows(2).map(|pair| (pair[1], pair[0])).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_has_no_cycle() {
+        let edges = chain_edges(&[1, 2, 3, 4]);
+        assert_eq!(edges, vec![(2, 1), (3, 2), (4, 3)]);
+        assert!(!has_cycle(&edges));
+    }
+
+    #[test]
+    fn test_diamond_has_no_cycle() {
+        assert!(!has_cycle(&[(2, 1), (3, 1), (4, 2), (4, 3)]));
+    }
+
+    #[test]
+    fn test_cycle_is_detected() {
+        assert!(has_cycle(&[(2, 1), (3, 2), (1, 3)]));
+        assert!(has_cycle(&[(1, 2), (2, 1)]));
+    }
+}
@@ -0,0 +1,133 @@
+use serde_json::Value;
+
+use crate::{
+    constants::{QUIZ_MAX_ATTEMPTS, QUIZ_MIN_SCORE_PERCENT},
+    models::{QuizAnswer, QuizQuestion, QuizScore, Task},
+};
+
+/// Counts correct answers. Unanswered questions and answers to questions that aren't
+/// part of the quiz count as wrong.
+pub fn grade(questions: &[QuizQuestion], answers: &[QuizAnswer]) -> QuizScore {
+    let correct = questions
+        .iter()
+        .filter(|question| {
+            answers
+                .iter()
+                .find(|answer| answer.question_id == question.id)
+                .is_some_and(|answer| answer.option == question.correct_option)
+        })
+        .count();
+
+    QuizScore {
+        correct: correct as i32,
+        total: questions.len() as i32,
+    }
+}
+
+/// Task points scaled by the share of correct answers, rounded down.
+pub fn scaled_points(points: i32, score: QuizScore) -> i32 {
+    if score.total == 0 {
+        return 0;
+    }
+    (points as i64 * score.correct as i64 / score.total as i64) as i32
+}
+
+/// `max_attempts` from the task metadata, per period.
+pub fn max_attempts(task: &Task) -> i64 {
+    task.metadata
+        .get("max_attempts")
+        .and_then(Value::as_i64)
+        .unwrap_or(QUIZ_MAX_ATTEMPTS)
+}
+
+/// Whether the score pays out, `min_score_percent` from the task metadata sets the bar.
+/// A score without a single correct answer never passes.
+pub fn passed(task: &Task, score: QuizScore) -> bool {
+    let min_score_percent = task
+        .metadata
+        .get("min_score_percent")
+        .and_then(Value::as_i64)
+        .unwrap_or(QUIZ_MIN_SCORE_PERCENT);
+    score.correct > 0 && score.correct as i64 * 100 >= min_score_percent * score.total as i64
+}
+
+/// Checks a question before it's saved.
+pub fn validate_question(options: &[String], correct_option: i32) -> Result<(), String> {
+    if options.len() < 2 {
+        return Err("A question needs at least two options.".to_string());
+    }
+    if correct_option < 0 || correct_option as usize >= options.len() {
+        return Err("The correct option must be one of the options.".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::TaskType;
+    use crate::verification::tests::task;
+
+    fn question(id: i32, correct_option: i32) -> QuizQuestion {
+        QuizQuestion {
+            id,
+            task_id: 1,
+            question: format!("question {id}"),
+            options: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            correct_option,
+            position: id,
+        }
+    }
+
+    fn answer(question_id: i32, option: i32) -> QuizAnswer {
+        QuizAnswer {
+            question_id,
+            option,
+        }
+    }
+
+    #[test]
+    fn test_grade() {
+        let questions = [question(1, 0), question(2, 2), question(3, 1)];
+        let answers = [answer(1, 0), answer(2, 1), answer(9, 1)];
+        assert_eq!(
+            grade(&questions, &answers),
+            QuizScore {
+                correct: 1,
+                total: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_scaled_points() {
+        let score = |correct, total| QuizScore { correct, total };
+        assert_eq!(scaled_points(100, score(3, 3)), 100);
+        assert_eq!(scaled_points(100, score(2, 3)), 66);
+        assert_eq!(scaled_points(100, score(0, 3)), 0);
+        assert_eq!(scaled_points(100, score(0, 0)), 0);
+    }
+
+    #[test]
+    fn test_passed_respects_min_score() {
+        let score = |correct| QuizScore { correct, total: 4 };
+        let default = task(TaskType::Quiz, json!({}));
+        assert!(!passed(&default, score(1)));
+        assert!(passed(&default, score(2)));
+
+        let lenient = task(TaskType::Quiz, json!({ "min_score_percent": 0 }));
+        assert!(!passed(&lenient, score(0)));
+        assert!(passed(&lenient, score(1)));
+    }
+
+    #[test]
+    fn test_validate_question() {
+        let options = vec!["yes".to_string(), "no".to_string()];
+        assert!(validate_question(&options, 1).is_ok());
+        assert!(validate_question(&options, 2).is_err());
+        assert!(validate_question(&options, -1).is_err());
+        assert!(validate_question(&options[..1], 0).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Utc};
+use chrono_tz::Tz;
+use cron::Schedule;
+
+use crate::models::TaskRecurrence;
+
+/// Period key of tasks that can only be completed once.
+pub const ONCE_PERIOD_KEY: &str = "once";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RecurrenceError {
+    MissingSchedule,
+    InvalidSchedule { error: String },
+}
+
+impl std::fmt::Display for RecurrenceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingSchedule => write!(f, "Cron recurrence requires a schedule."),
+            Self::InvalidSchedule { error } => write!(f, "Invalid cron schedule: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for RecurrenceError {}
+
+/// Checks that a recurrence can produce period keys, done before a task is saved.
+pub fn validate_recurrence(
+    recurrence: TaskRecurrence,
+    schedule: Option<&str>,
+) -> Result<(), RecurrenceError> {
+    if recurrence == TaskRecurrence::Cron {
+        parse_schedule(schedule)?;
+    }
+    Ok(())
+}
+
+fn parse_schedule(schedule: Option<&str>) -> Result<Schedule, RecurrenceError> {
+    let schedule = schedule.ok_or(RecurrenceError::MissingSchedule)?;
+    Schedule::from_str(schedule).map_err(|err| RecurrenceError::InvalidSchedule {
+        error: err.to_string(),
+    })
+}
+
+/// Identifies the period `now` falls into, a task pays out once per key.
+///
+/// Daily periods are calendar days and weekly periods ISO weeks, both in `timezone`.
+/// Cron schedules (`sec min hour day month weekday`) start a new period on every
+/// occurrence, keyed by the occurrence that opened it.
+pub fn period_key(
+    recurrence: TaskRecurrence,
+    schedule: Option<&str>,
+    now: DateTime<Utc>,
+    timezone: Tz,
+) -> Result<String, RecurrenceError> {
+    let local = now.with_timezone(&timezone);
+    match recurrence {
+        TaskRecurrence::None => Ok(ONCE_PERIOD_KEY.to_string()),
+        TaskRecurrence::Daily => Ok(local.format("%Y-%m-%d").to_string()),
+        TaskRecurrence::Weekly => {
+            let week = local.iso_week();
+            Ok(format!("{}-W{:02}", week.year(), week.week()))
+        }
+        TaskRecurrence::Cron => {
+            let schedule = parse_schedule(schedule)?;
+            // Nothing fired yet, so everything up to the first occurrence is one period.
+            Ok(schedule
+                .after(&local)
+                .next_back()
+                .map(|opened_at| opened_at.format("%Y-%m-%dT%H:%M:%S").to_string())
+                .unwrap_or_else(|| "initial".to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_once() {
+        let key = period_key(TaskRecurrence::None, None, Utc::now(), Tz::UTC).unwrap();
+        assert_eq!(key, ONCE_PERIOD_KEY);
+    }
+
+    #[test]
+    fn test_daily_uses_timezone() {
+        let now = at(2026, 10, 19, 23, 30);
+        assert_eq!(
+            period_key(TaskRecurrence::Daily, None, now, Tz::UTC).unwrap(),
+            "2026-10-19"
+        );
+        assert_eq!(
+            period_key(TaskRecurrence::Daily, None, now, Tz::Europe__Belgrade).unwrap(),
+            "2026-10-20"
+        );
+    }
+
+    #[test]
+    fn test_weekly_is_iso_week() {
+        let monday = at(2026, 10, 19, 0, 0);
+        let sunday = at(2026, 10, 25, 23, 0);
+        let next_monday = at(2026, 10, 26, 0, 0);
+        let key = |now| period_key(TaskRecurrence::Weekly, None, now, Tz::UTC).unwrap();
+        assert_eq!(key(monday), "2026-W43");
+        assert_eq!(key(monday), key(sunday));
+        assert_ne!(key(sunday), key(next_monday));
+    }
+
+    #[test]
+    fn test_cron_period_starts_at_last_occurrence() {
+        let schedule = Some("0 0 9 * * *");
+        let key = |now| period_key(TaskRecurrence::Cron, schedule, now, Tz::UTC).unwrap();
+        assert_eq!(key(at(2026, 10, 19, 8, 0)), "2026-10-18T09:00:00");
+        assert_eq!(key(at(2026, 10, 19, 10, 0)), "2026-10-19T09:00:00");
+    }
+
+    #[test]
+    fn test_cron_requires_valid_schedule() {
+        assert_eq!(
+            validate_recurrence(TaskRecurrence::Cron, None),
+            Err(RecurrenceError::MissingSchedule)
+        );
+        assert!(validate_recurrence(TaskRecurrence::Cron, Some("not a cron")).is_err());
+        assert!(validate_recurrence(TaskRecurrence::Daily, None).is_ok());
+    }
+}
@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::Query,
+    http::StatusCode,
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use serde_json::json;
+
+use crate::{
+    airdrop::{build_airdrop, AirdropEntry},
+    db::{
+        _compute_snapshot, _create_airdrop, _get_airdrop, _get_airdrop_allocation,
+        _get_airdrop_claim, _get_latest_airdrop, _get_snapshot, _get_snapshot_entries,
+        _get_user_claim_statuses,
+    },
+    jwt::Claims,
+    middlewares::{require_api_key, require_auth_jwt},
+    models::{AirdropProofQueryDTO, ApiKey, ApiScope, CreateAirdropDTO, WalletChain},
+    state::AppState,
+    validation::ValidatedJson,
+};
+
+pub fn routes() -> Router {
+    Router::new().nest("/airdrop", _routes())
+}
+
+fn _routes() -> Router {
+    Router::new()
+        .route("/", post(create_airdrop))
+        .layer(middleware::from_fn_with_state(
+            ApiScope::AirdropWrite,
+            require_api_key,
+        ))
+        .merge(
+            Router::new()
+                .route("/proof", get(get_own_proof))
+                .route("/claims", get(get_own_claims))
+                .layer(middleware::from_fn(require_auth_jwt)),
+        )
+}
+
+fn internal_error() -> Response {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({
+            "error": "Internal Server Error"
+        })),
+    )
+        .into_response()
+}
+
+/// Allocates the pool with the requested formula over every user with a Solana wallet,
+/// or over a frozen snapshot, stores the tree and answers with the root to set on chain.
+async fn create_airdrop(
+    Extension(state): Extension<Arc<AppState>>,
+    api_key: Option<Extension<ApiKey>>,
+    ValidatedJson(create_airdrop_dto): ValidatedJson<CreateAirdropDTO>,
+) -> impl IntoResponse {
+    let entries = match create_airdrop_dto.snapshot_id {
+        Some(snapshot_id) => match _get_snapshot(&state.db, snapshot_id).await {
+            Ok(Some(_)) => _get_snapshot_entries(&state.db, snapshot_id).await,
+            Ok(None) => {
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({
+                        "error": format!("Snapshot {snapshot_id} not found.")
+                    })),
+                )
+                    .into_response()
+            }
+            Err(err) => Err(err),
+        },
+        None => _compute_snapshot(&state.db, Some(WalletChain::Solana)).await,
+    };
+    let Ok(entries) = entries else {
+        return internal_error();
+    };
+    // Snapshots of other chains hold addresses that aren't Solana keys, those are skipped.
+    let entries: Vec<AirdropEntry> = entries
+        .into_iter()
+        .filter_map(|entry| {
+            Some(AirdropEntry {
+                user_id: entry.user_id,
+                wallet: entry.wallet_address.parse().ok()?,
+                points: entry.points,
+            })
+        })
+        .collect();
+
+    let Some(airdrop) = build_airdrop(
+        entries,
+        create_airdrop_dto.total_amount,
+        &create_airdrop_dto.formula,
+    ) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "No user would receive tokens."
+            })),
+        )
+            .into_response();
+    };
+
+    let created_by = api_key.map(|Extension(api_key)| api_key.id);
+    match _create_airdrop(
+        &state.db,
+        &airdrop,
+        create_airdrop_dto.snapshot_id,
+        created_by,
+    )
+    .await
+    {
+        Ok(distribution) => (
+            StatusCode::CREATED,
+            Json(json!({ "airdrop": distribution })),
+        )
+            .into_response(),
+        Err(_) => internal_error(),
+    }
+}
+
+async fn get_own_proof(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+    Query(proof_query): Query<AirdropProofQueryDTO>,
+) -> impl IntoResponse {
+    let distribution = match proof_query.airdrop_id {
+        Some(airdrop_id) => _get_airdrop(&state.db, airdrop_id).await,
+        None => _get_latest_airdrop(&state.db).await,
+    };
+    let distribution = match distribution {
+        Ok(Some(distribution)) => distribution,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "error": "Airdrop not found."
+                })),
+            )
+                .into_response()
+        }
+        Err(_) => return internal_error(),
+    };
+
+    let allocation = match _get_airdrop_allocation(&state.db, distribution.id, claims.id).await {
+        Ok(Some(allocation)) => allocation,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "error": "No allocation in this airdrop."
+                })),
+            )
+                .into_response()
+        }
+        Err(_) => return internal_error(),
+    };
+
+    match _get_airdrop_claim(&state.db, distribution.id, allocation.leaf_index).await {
+        Ok(claim) => (
+            StatusCode::OK,
+            Json(json!({
+                "airdrop_id": distribution.id,
+                "merkle_root": distribution.merkle_root,
+                "index": allocation.leaf_index,
+                "wallet": allocation.wallet,
+                "amount": allocation.amount,
+                "proof": allocation.proof,
+                "claim": claim
+            })),
+        )
+            .into_response(),
+        Err(_) => internal_error(),
+    }
+}
+
+/// The user's allocation in every airdrop and whether it was claimed.
+async fn get_own_claims(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    match _get_user_claim_statuses(&state.db, claims.id).await {
+        Ok(statuses) => (StatusCode::OK, Json(json!({ "claims": statuses }))).into_response(),
+        Err(_) => internal_error(),
+    }
+}
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use axum::{
+    http::StatusCode,
+    middleware,
+    response::IntoResponse,
+    routing::{delete, get, post},
+    Extension, Json, Router,
+};
+use rand::{distributions::Alphanumeric, Rng, RngCore};
+use serde_json::json;
+
+use crate::{
+    db::{_create_api_client, _get_api_clients, _revoke_api_client},
+    middlewares::require_auth,
+    models::{CreateApiClientDTO, RevokeApiClientDTO},
+    state::AppState,
+};
+
+pub fn routes() -> Router {
+    Router::new().nest("/api-clients", _routes())
+}
+
+fn _routes() -> Router {
+    Router::new()
+        .route("/", post(create_api_client))
+        .route("/", get(get_api_clients))
+        .route("/", delete(revoke_api_client))
+        .layer(middleware::from_fn(require_auth))
+}
+
+fn generate_key_id() -> String {
+    let suffix: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(24)
+        .map(char::from)
+        .collect();
+    format!("pk_{suffix}")
+}
+
+fn generate_secret() -> String {
+    let mut secret = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    hex::encode(secret)
+}
+
+async fn create_api_client(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(create_api_client_dto): Json<CreateApiClientDTO>,
+) -> impl IntoResponse {
+    let key_id = generate_key_id();
+    let secret = generate_secret();
+
+    match _create_api_client(&state.db, create_api_client_dto, &key_id, &secret).await {
+        // The secret is only ever returned here, clients have to store it themselves.
+        Ok(client) => (StatusCode::OK, Json(json!({ "client": client }))).into_response(),
+        Err(err) => (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    }
+}
+
+async fn get_api_clients(Extension(state): Extension<Arc<AppState>>) -> impl IntoResponse {
+    match _get_api_clients(&state.db).await {
+        Ok(clients) => (StatusCode::OK, Json(json!({ "clients": clients }))).into_response(),
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Failed to retrieve api clients."
+            })),
+        )
+            .into_response(),
+    }
+}
+
+async fn revoke_api_client(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(revoke_api_client_dto): Json<RevokeApiClientDTO>,
+) -> impl IntoResponse {
+    if _revoke_api_client(&state.db, &revoke_api_client_dto.key_id)
+        .await
+        .is_err()
+    {
+        return (StatusCode::NOT_FOUND, "Api client not found!").into_response();
+    }
+    (StatusCode::OK, "Api client revoked!").into_response()
+}
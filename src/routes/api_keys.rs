@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use axum::{
+    http::StatusCode,
+    middleware,
+    response::IntoResponse,
+    routing::{delete, get, post},
+    Extension, Json, Router,
+};
+use rand::RngCore;
+use serde_json::json;
+
+use crate::{
+    db::{_create_api_key, _get_api_keys, _revoke_api_key},
+    middlewares::{hash_api_key, require_auth},
+    models::{CreateApiKeyDTO, RevokeApiKeyDTO},
+    state::AppState,
+};
+
+pub fn routes() -> Router {
+    Router::new().nest("/api-keys", _routes())
+}
+
+fn _routes() -> Router {
+    Router::new()
+        .route("/", post(create_api_key))
+        .route("/", get(get_api_keys))
+        .route("/", delete(revoke_api_key))
+        .layer(middleware::from_fn(require_auth))
+}
+
+fn generate_api_key() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("tpf_{}", hex::encode(bytes))
+}
+
+async fn create_api_key(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(create_api_key_dto): Json<CreateApiKeyDTO>,
+) -> impl IntoResponse {
+    let raw_key = generate_api_key();
+    let key_prefix = &raw_key[..12];
+
+    match _create_api_key(
+        &state.db,
+        create_api_key_dto,
+        key_prefix,
+        &hash_api_key(&raw_key),
+    )
+    .await
+    {
+        // Only the hash is stored, so this is the one chance to read the key.
+        Ok(api_key) => (
+            StatusCode::OK,
+            Json(json!({ "api_key": api_key, "key": raw_key })),
+        )
+            .into_response(),
+        Err(err) => (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    }
+}
+
+async fn get_api_keys(Extension(state): Extension<Arc<AppState>>) -> impl IntoResponse {
+    match _get_api_keys(&state.db).await {
+        Ok(api_keys) => (StatusCode::OK, Json(json!({ "api_keys": api_keys }))).into_response(),
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Failed to retrieve api keys."
+            })),
+        )
+            .into_response(),
+    }
+}
+
+async fn revoke_api_key(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(revoke_api_key_dto): Json<RevokeApiKeyDTO>,
+) -> impl IntoResponse {
+    if _revoke_api_key(&state.db, revoke_api_key_dto.id)
+        .await
+        .is_err()
+    {
+        return (StatusCode::NOT_FOUND, "Api key not found!").into_response();
+    }
+    (StatusCode::OK, "Api key revoked!").into_response()
+}
@@ -1,4 +1,10 @@
+mod airdrop;
+mod api_clients;
+mod api_keys;
 mod dbg;
+mod quests;
+mod snapshots;
+mod submissions;
 mod tasks;
 mod users;
 
@@ -12,7 +18,13 @@ fn _routes() -> Router {
     let mut router = Router::new();
     router = router
         .merge(dbg::routes())
+        .merge(api_clients::routes())
+        .merge(api_keys::routes())
         .merge(users::routes())
-        .merge(tasks::routes());
+        .merge(tasks::routes())
+        .merge(quests::routes())
+        .merge(submissions::routes())
+        .merge(airdrop::routes())
+        .merge(snapshots::routes());
     router
 }
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use axum::{
+    http::StatusCode,
+    middleware,
+    response::IntoResponse,
+    routing::{delete, get, post},
+    Extension, Json, Router,
+};
+use serde_json::json;
+
+use crate::{
+    db::{_create_quest, _delete_quest, _get_quests},
+    middlewares::{require_api_key, require_signature_or_api_key},
+    models::{ApiScope, CreateQuestDTO, DeleteQuestDTO},
+    state::AppState,
+};
+
+pub fn routes() -> Router {
+    Router::new().nest("/quests", _routes())
+}
+
+fn _routes() -> Router {
+    Router::new()
+        .route("/", post(create_quest))
+        .route("/", delete(delete_quest))
+        .layer(middleware::from_fn_with_state(
+            ApiScope::TasksWrite,
+            require_api_key,
+        ))
+        .merge(
+            Router::new()
+                .route("/", get(get_quests))
+                .layer(middleware::from_fn_with_state(
+                    ApiScope::TasksRead,
+                    require_signature_or_api_key,
+                )),
+        )
+}
+
+async fn get_quests(Extension(state): Extension<Arc<AppState>>) -> impl IntoResponse {
+    match _get_quests(&state.db).await {
+        Ok(quests) => (StatusCode::OK, Json(json!({ "quests": quests }))).into_response(),
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Failed to retrieve quests."
+            })),
+        )
+            .into_response(),
+    }
+}
+
+async fn create_quest(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(create_quest_dto): Json<CreateQuestDTO>,
+) -> impl IntoResponse {
+    match _create_quest(&state.db, create_quest_dto).await {
+        Ok(Some(id)) => (StatusCode::OK, Json(json!({ "id": id }))).into_response(),
+        Ok(None) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Quest order would form a prerequisite cycle."
+            })),
+        )
+            .into_response(),
+        Err(err) => (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    }
+}
+
+async fn delete_quest(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(delete_quest_dto): Json<DeleteQuestDTO>,
+) -> impl IntoResponse {
+    if _delete_quest(&state.db, delete_quest_dto.quest_id)
+        .await
+        .is_err()
+    {
+        return (StatusCode::NOT_FOUND, "Quest not found!").into_response();
+    }
+    (StatusCode::OK, "Quest deleted!").into_response()
+}
@@ -0,0 +1,155 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::Query,
+    http::StatusCode,
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use serde_json::json;
+
+use crate::{
+    db::{
+        _compute_snapshot, _freeze_snapshot, _get_snapshot, _get_snapshot_entries, _get_snapshots,
+        Database,
+    },
+    middlewares::{require_api_key, require_signature_or_api_key},
+    models::{
+        ApiKey, ApiScope, DownloadSnapshotQueryDTO, FreezeSnapshotDTO, Snapshot,
+        SnapshotDiffQueryDTO, SnapshotEntry,
+    },
+    snapshots::{diff, SnapshotParameters, POINTS_FORMULA},
+    state::AppState,
+};
+
+pub fn routes() -> Router {
+    Router::new().nest("/snapshots", _routes())
+}
+
+fn _routes() -> Router {
+    Router::new()
+        .route("/", post(freeze_snapshot))
+        .layer(middleware::from_fn_with_state(
+            ApiScope::SnapshotWrite,
+            require_api_key,
+        ))
+        .merge(
+            Router::new()
+                .route("/", get(get_snapshots))
+                .route("/download", get(download_snapshot))
+                .route("/diff", get(diff_snapshots))
+                .layer(middleware::from_fn_with_state(
+                    ApiScope::SnapshotRead,
+                    require_signature_or_api_key,
+                )),
+        )
+}
+
+fn internal_error() -> Response {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({
+            "error": "Internal Server Error"
+        })),
+    )
+        .into_response()
+}
+
+fn snapshot_not_found(snapshot_id: i32) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({
+            "error": format!("Snapshot {snapshot_id} not found.")
+        })),
+    )
+        .into_response()
+}
+
+async fn load_snapshot(
+    db: &Database,
+    snapshot_id: i32,
+) -> Result<(Snapshot, Vec<SnapshotEntry>), Response> {
+    let snapshot = match _get_snapshot(db, snapshot_id).await {
+        Ok(Some(snapshot)) => snapshot,
+        Ok(None) => return Err(snapshot_not_found(snapshot_id)),
+        Err(_) => return Err(internal_error()),
+    };
+    let entries = _get_snapshot_entries(db, snapshot_id)
+        .await
+        .map_err(|_| internal_error())?;
+    Ok((snapshot, entries))
+}
+
+/// Stores the live snapshot so a distribution can point at exactly what it used.
+async fn freeze_snapshot(
+    Extension(state): Extension<Arc<AppState>>,
+    api_key: Option<Extension<ApiKey>>,
+    Json(freeze_snapshot_dto): Json<FreezeSnapshotDTO>,
+) -> impl IntoResponse {
+    let parameters = SnapshotParameters {
+        chain: freeze_snapshot_dto.chain,
+        formula: POINTS_FORMULA.to_string(),
+    };
+    let entries = match _compute_snapshot(&state.db, parameters.chain).await {
+        Ok(entries) => entries,
+        Err(_) => return internal_error(),
+    };
+
+    let created_by = api_key.map(|Extension(api_key)| api_key.id);
+    match _freeze_snapshot(&state.db, &parameters, &entries, created_by).await {
+        Ok(snapshot) => {
+            (StatusCode::CREATED, Json(json!({ "snapshot": snapshot }))).into_response()
+        }
+        Err(_) => internal_error(),
+    }
+}
+
+async fn get_snapshots(Extension(state): Extension<Arc<AppState>>) -> impl IntoResponse {
+    match _get_snapshots(&state.db).await {
+        Ok(snapshots) => (StatusCode::OK, Json(json!({ "snapshots": snapshots }))).into_response(),
+        Err(_) => internal_error(),
+    }
+}
+
+async fn download_snapshot(
+    Extension(state): Extension<Arc<AppState>>,
+    Query(download_query): Query<DownloadSnapshotQueryDTO>,
+) -> impl IntoResponse {
+    match load_snapshot(&state.db, download_query.snapshot_id).await {
+        Ok((snapshot, entries)) => (
+            StatusCode::OK,
+            Json(json!({
+                "snapshot": snapshot,
+                "entries": entries
+            })),
+        )
+            .into_response(),
+        Err(response) => response,
+    }
+}
+
+async fn diff_snapshots(
+    Extension(state): Extension<Arc<AppState>>,
+    Query(diff_query): Query<SnapshotDiffQueryDTO>,
+) -> impl IntoResponse {
+    let (from, from_entries) = match load_snapshot(&state.db, diff_query.from).await {
+        Ok(loaded) => loaded,
+        Err(response) => return response,
+    };
+    let (to, to_entries) = match load_snapshot(&state.db, diff_query.to).await {
+        Ok(loaded) => loaded,
+        Err(response) => return response,
+    };
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "from": from,
+            "to": to,
+            "diff": diff(&from_entries, &to_entries)
+        })),
+    )
+        .into_response()
+}
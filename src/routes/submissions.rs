@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::Query,
+    http::StatusCode,
+    middleware,
+    response::IntoResponse,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use serde_json::json;
+
+use crate::{
+    db::{_approve_submission, _get_submissions, _reject_submission},
+    middlewares::require_api_key,
+    models::{ApiKey, ApiScope, ApproveSubmissionDTO, RejectSubmissionDTO, SubmissionFilterDTO},
+    state::AppState,
+};
+
+use super::{tasks::changed_by, users::completion_error_response};
+
+pub fn routes() -> Router {
+    Router::new().nest("/submissions", _routes())
+}
+
+fn _routes() -> Router {
+    Router::new()
+        .route("/", get(get_submissions))
+        .route("/approve", post(approve_submission))
+        .route("/reject", post(reject_submission))
+        .layer(middleware::from_fn_with_state(
+            ApiScope::SubmissionsReview,
+            require_api_key,
+        ))
+}
+
+async fn get_submissions(
+    Extension(state): Extension<Arc<AppState>>,
+    Query(filter): Query<SubmissionFilterDTO>,
+) -> impl IntoResponse {
+    match _get_submissions(&state.db, filter).await {
+        Ok(submissions) => {
+            (StatusCode::OK, Json(json!({ "submissions": submissions }))).into_response()
+        }
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Failed to retrieve submissions."
+            })),
+        )
+            .into_response(),
+    }
+}
+
+async fn approve_submission(
+    Extension(state): Extension<Arc<AppState>>,
+    api_key: Option<Extension<ApiKey>>,
+    Json(approve_submission_dto): Json<ApproveSubmissionDTO>,
+) -> impl IntoResponse {
+    match _approve_submission(
+        &state.db,
+        approve_submission_dto.submission_id,
+        changed_by(api_key),
+        state.period_timezone,
+    )
+    .await
+    {
+        Ok(()) => (StatusCode::OK, "Submission approved!").into_response(),
+        Err(err) => completion_error_response(err),
+    }
+}
+
+async fn reject_submission(
+    Extension(state): Extension<Arc<AppState>>,
+    api_key: Option<Extension<ApiKey>>,
+    Json(reject_submission_dto): Json<RejectSubmissionDTO>,
+) -> impl IntoResponse {
+    match _reject_submission(
+        &state.db,
+        reject_submission_dto.submission_id,
+        changed_by(api_key),
+        &reject_submission_dto.reason,
+    )
+    .await
+    {
+        Ok(true) => (StatusCode::OK, "Submission rejected!").into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "No pending submission found."
+            })),
+        )
+            .into_response(),
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal Server Error"
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use chrono_tz::Tz;
+    use password_encryptor::PasswordEncryptor;
+    use sqlx::postgres::PgPoolOptions;
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::{
+        db::{_create_task, _create_user, _finish_task, _get_user_submissions, FinishOutcome},
+        models::{
+            CreateTaskDTO, CreateUserDTO, FinishTaskDTO, SolanaAddress, SubmissionStatus, TaskProof,
+        },
+    };
+
+    #[tokio::test]
+    async fn test_review_with_dev_secret() {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            panic!("Missing required environment variable: {}", "DATABASE_URL")
+        });
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url.as_str())
+            .await
+            .expect("Failed to create pool");
+
+        let suffix = chrono::Local::now().to_string();
+        let task_id = _create_task(
+            &pool,
+            CreateTaskDTO {
+                description: "Draw a frog".to_string(),
+                points: 25,
+                task_button_text: "Submit".to_string(),
+                link: None,
+                task_type: None,
+                category: None,
+                icon_url: None,
+                display_order: None,
+                metadata: Some(json!({ "manual_review": true })),
+                status: None,
+                starts_at: None,
+                ends_at: None,
+                recurrence: None,
+                recurrence_schedule: None,
+                max_completions: None,
+                points_budget: None,
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        let state = AppState::for_tests(pool.clone()).await;
+        let mut user_ids = vec![];
+        let mut submission_ids = vec![];
+        for name in ["approved", "rejected"] {
+            let user = _create_user(
+                &pool,
+                CreateUserDTO {
+                    twitter_id: format!("{name}{suffix}"),
+                    solana_adr: SolanaAddress::random(),
+                    password: "123".to_string(),
+                    reffer_code: None,
+                },
+                PasswordEncryptor::new(vec![1, 2, 3], None),
+                "salt",
+            )
+            .await
+            .unwrap();
+            let outcome = _finish_task(
+                &pool,
+                &state.verifiers,
+                FinishTaskDTO {
+                    task_id,
+                    wallet: user.wallet_address.parse().unwrap(),
+                    proof: TaskProof {
+                        url: Some("https://x.com/frog/status/1".to_string()),
+                        ..Default::default()
+                    },
+                },
+                Tz::UTC,
+            )
+            .await
+            .unwrap();
+            match outcome {
+                FinishOutcome::PendingReview { submission_id } => {
+                    submission_ids.push(submission_id)
+                }
+                _ => panic!("submission wasn't queued"),
+            }
+            user_ids.push(user.id);
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/submissions", listener.local_addr().unwrap());
+        let app = routes().layer(Extension(Arc::new(state)));
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let client = reqwest::Client::new();
+        let response = client
+            .post(format!("{url}/approve"))
+            .bearer_auth("dev_secret")
+            .json(&json!({ "submission_id": submission_ids[0] }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+        let response = client
+            .post(format!("{url}/reject"))
+            .bearer_auth("dev_secret")
+            .json(&json!({ "submission_id": submission_ids[1], "reason": "blurry" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+
+        for (user_id, status) in user_ids
+            .into_iter()
+            .zip([SubmissionStatus::Approved, SubmissionStatus::Rejected])
+        {
+            let submissions = _get_user_submissions(&pool, user_id).await.unwrap();
+            assert_eq!(submissions[0].status, status);
+            assert_eq!(submissions[0].reviewed_by, None);
+        }
+    }
+}
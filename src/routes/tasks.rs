@@ -1,19 +1,38 @@
 use std::sync::Arc;
 
 use axum::{
-    http::StatusCode,
+    extract::Query,
+    http::{header, HeaderMap, StatusCode},
     middleware,
-    response::IntoResponse,
-    routing::{delete, get, post, put},
+    response::{IntoResponse, Response},
+    routing::{delete, get, patch, post, put},
     Extension, Json, Router,
 };
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
 use serde_json::json;
 
 use crate::{
-    db::{_create_task, _delete_task, _get_tasks, _put_task},
-    middlewares::{require_auth, require_security_hash},
-    models::{CreateTaskDTO, DeleteTaskDTO, PutTaskDTO},
+    db::{
+        _create_quiz_question, _create_task, _delete_quiz_question, _delete_task,
+        _get_locked_task_ids, _get_prerequisite_edges, _get_quiz_question_by_id,
+        _get_quiz_questions, _get_task_revisions, _get_tasks, _import_tasks, _put_quiz_question,
+        _put_task, _restore_task_revision, _set_task_prerequisites, TaskUpdateError,
+    },
+    jwt::{validate_jwt, Claims},
+    middlewares::{require_api_key, require_signature_or_api_key},
+    models::{
+        ApiKey, ApiScope, CreateQuizQuestionDTO, CreateTaskDTO, DeleteQuizQuestionDTO,
+        DeleteTaskDTO, ExportTasksQueryDTO, ImportTasksQueryDTO, PutQuizQuestionDTO, PutTaskDTO,
+        QuizQuestionView, QuizQuestionsQueryDTO, RestoreTaskRevisionDTO, SetPrerequisitesDTO, Task,
+        TaskFilterDTO, TaskRevisionsQueryDTO, TaskView,
+    },
+    quiz::validate_question,
     state::AppState,
+    task_io::{export_tasks as export_tasks_as, parse_tasks, ImportError},
+    validation::ValidatedJson,
 };
 
 pub fn routes() -> Router {
@@ -24,22 +43,237 @@ fn _routes() -> Router {
     Router::new()
         .route("/", delete(delete_task))
         .route("/", put(put_task))
+        .route("/", patch(put_task))
         .route("/", post(create_task))
-        .layer(middleware::from_fn(require_auth))
-        .route("/", get(get_tasks))
-        .layer(middleware::from_fn(require_security_hash))
+        .route("/prerequisites", put(set_prerequisites))
+        .route("/import", post(import_tasks))
+        .route("/export", get(export_tasks))
+        .route("/revisions", get(get_revisions))
+        .route("/restore", post(restore_revision))
+        .route("/questions", post(create_question))
+        .route("/questions", put(put_question))
+        .route("/questions", delete(delete_question))
+        .layer(middleware::from_fn_with_state(
+            ApiScope::TasksWrite,
+            require_api_key,
+        ))
+        .merge(
+            Router::new()
+                .route("/", get(get_tasks))
+                .route("/questions", get(get_questions))
+                .layer(middleware::from_fn_with_state(
+                    ApiScope::TasksRead,
+                    require_signature_or_api_key,
+                )),
+        )
 }
 
-async fn get_tasks(Extension(state): Extension<Arc<AppState>>) -> impl IntoResponse {
-    let tasks = _get_tasks(&state.db).await.unwrap();
-    (StatusCode::OK, Json(json!({ "tasks": tasks })))
+async fn get_tasks(
+    Extension(state): Extension<Arc<AppState>>,
+    authorization_token: Option<TypedHeader<Authorization<Bearer>>>,
+    api_key: Option<Extension<ApiKey>>,
+    Query(filter): Query<TaskFilterDTO>,
+) -> impl IntoResponse {
+    let token = authorization_token.as_ref().map(|token| token.token());
+    // Admins also get drafts, paused, archived and out of window tasks.
+    let is_admin = is_admin(&state, token, api_key);
+    // Users sending their JWT get their own lock state.
+    let claims = token.and_then(|token| validate_jwt::<Claims>(token, &state.decoding_key).ok());
+
+    let tasks = match _get_tasks(&state.db, filter, is_admin).await {
+        Ok(tasks) => tasks,
+        Err(_) => return internal_server_error(),
+    };
+    let edges = match _get_prerequisite_edges(&state.db).await {
+        Ok(edges) => edges,
+        Err(_) => return internal_server_error(),
+    };
+    let locked_task_ids = match &claims {
+        Some(claims) => match _get_locked_task_ids(&state.db, claims.id).await {
+            Ok(locked_task_ids) => Some(locked_task_ids),
+            Err(_) => return internal_server_error(),
+        },
+        None => None,
+    };
+
+    let tasks: Vec<TaskView> = tasks
+        .into_iter()
+        .map(|mut task| {
+            if !is_admin {
+                task.redact_secrets();
+            }
+            let prerequisite_task_ids: Vec<i32> = edges
+                .iter()
+                .filter(|edge| edge.0 == task.id)
+                .map(|edge| edge.1)
+                .collect();
+            let locked = match &locked_task_ids {
+                Some(locked_task_ids) => locked_task_ids.contains(&task.id),
+                None => !prerequisite_task_ids.is_empty(),
+            };
+            TaskView {
+                task,
+                prerequisite_task_ids,
+                locked,
+            }
+        })
+        .collect();
+
+    (StatusCode::OK, Json(json!({ "tasks": tasks }))).into_response()
+}
+
+async fn set_prerequisites(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(set_prerequisites_dto): Json<SetPrerequisitesDTO>,
+) -> impl IntoResponse {
+    match _set_task_prerequisites(&state.db, set_prerequisites_dto).await {
+        Ok(true) => (StatusCode::OK, "Prerequisites updated!").into_response(),
+        Ok(false) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Prerequisites would form a cycle."
+            })),
+        )
+            .into_response(),
+        Err(err) => (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    }
+}
+
+/// Dev secret holders and api keys allowed to edit tasks.
+fn is_admin(state: &AppState, token: Option<&str>, api_key: Option<Extension<ApiKey>>) -> bool {
+    token.is_some_and(|token| token == state.dev_secret)
+        || api_key.is_some_and(|Extension(api_key)| api_key.has_scope(ApiScope::TasksWrite))
+}
+
+/// The api key a change is recorded under, requests with the dev secret have none.
+pub(super) fn changed_by(api_key: Option<Extension<ApiKey>>) -> Option<i32> {
+    api_key.map(|Extension(api_key)| api_key.id)
+}
+
+async fn get_revisions(
+    Extension(state): Extension<Arc<AppState>>,
+    Query(query): Query<TaskRevisionsQueryDTO>,
+) -> impl IntoResponse {
+    match _get_task_revisions(&state.db, query.task_id).await {
+        Ok(revisions) => (StatusCode::OK, Json(json!({ "revisions": revisions }))).into_response(),
+        Err(_) => internal_server_error(),
+    }
+}
+
+async fn restore_revision(
+    Extension(state): Extension<Arc<AppState>>,
+    api_key: Option<Extension<ApiKey>>,
+    Json(restore_task_revision_dto): Json<RestoreTaskRevisionDTO>,
+) -> impl IntoResponse {
+    match _restore_task_revision(
+        &state.db,
+        restore_task_revision_dto.task_id,
+        restore_task_revision_dto.revision,
+        changed_by(api_key),
+    )
+    .await
+    {
+        Ok(Some(task)) => (StatusCode::OK, Json(json!({ "task": task }))).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Revision not found!").into_response(),
+        Err(err) => (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    }
+}
+
+/// Questions of a quiz task, with the correct options for admins only.
+async fn get_questions(
+    Extension(state): Extension<Arc<AppState>>,
+    authorization_token: Option<TypedHeader<Authorization<Bearer>>>,
+    api_key: Option<Extension<ApiKey>>,
+    Query(query): Query<QuizQuestionsQueryDTO>,
+) -> impl IntoResponse {
+    let token = authorization_token.as_ref().map(|token| token.token());
+    let questions = match _get_quiz_questions(&state.db, query.task_id).await {
+        Ok(questions) => questions,
+        Err(_) => return internal_server_error(),
+    };
+
+    if is_admin(&state, token, api_key) {
+        return (StatusCode::OK, Json(json!({ "questions": questions }))).into_response();
+    }
+    let questions: Vec<QuizQuestionView> = questions.into_iter().map(Into::into).collect();
+    (StatusCode::OK, Json(json!({ "questions": questions }))).into_response()
+}
+
+async fn create_question(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(create_quiz_question_dto): Json<CreateQuizQuestionDTO>,
+) -> impl IntoResponse {
+    if let Err(error) = validate_question(
+        &create_quiz_question_dto.options,
+        create_quiz_question_dto.correct_option,
+    ) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": error }))).into_response();
+    }
+
+    match _create_quiz_question(&state.db, create_quiz_question_dto).await {
+        Ok(id) => (StatusCode::OK, Json(json!({ "id": id }))).into_response(),
+        Err(err) => (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    }
+}
+
+async fn put_question(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(put_quiz_question_dto): Json<PutQuizQuestionDTO>,
+) -> impl IntoResponse {
+    let question =
+        match _get_quiz_question_by_id(&state.db, put_quiz_question_dto.question_id).await {
+            Ok(Some(question)) => question,
+            Ok(None) => return (StatusCode::NOT_FOUND, "Question not found!").into_response(),
+            Err(_) => return internal_server_error(),
+        };
+
+    if let Err(error) = validate_question(
+        put_quiz_question_dto
+            .options
+            .as_deref()
+            .unwrap_or(&question.options),
+        put_quiz_question_dto
+            .correct_option
+            .unwrap_or(question.correct_option),
+    ) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": error }))).into_response();
+    }
+
+    match _put_quiz_question(&state.db, &question, put_quiz_question_dto).await {
+        Ok(()) => (StatusCode::OK, "Question updated!").into_response(),
+        Err(err) => (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    }
+}
+
+async fn delete_question(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(delete_quiz_question_dto): Json<DeleteQuizQuestionDTO>,
+) -> impl IntoResponse {
+    if _delete_quiz_question(&state.db, delete_quiz_question_dto.question_id)
+        .await
+        .is_err()
+    {
+        return (StatusCode::NOT_FOUND, "Question not found!").into_response();
+    }
+    (StatusCode::OK, "Question deleted!").into_response()
+}
+
+fn internal_server_error() -> Response {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({
+            "error": "Internal Server Error"
+        })),
+    )
+        .into_response()
 }
 
 async fn create_task(
     Extension(state): Extension<Arc<AppState>>,
-    Json(create_task_dto): Json<CreateTaskDTO>,
+    api_key: Option<Extension<ApiKey>>,
+    ValidatedJson(create_task_dto): ValidatedJson<CreateTaskDTO>,
 ) -> impl IntoResponse {
-    let create_result = _create_task(&state.db, create_task_dto).await;
+    let create_result = _create_task(&state.db, create_task_dto, changed_by(api_key)).await;
 
     if let Err(err) = create_result {
         return (StatusCode::BAD_REQUEST, err.to_string()).into_response();
@@ -48,26 +282,148 @@ async fn create_task(
     (StatusCode::OK, Json(json!({"id":create_result.unwrap()}))).into_response()
 }
 
+/// Imports a JSON array or a CSV file of tasks, all or nothing. Bad rows are
+/// reported together so the sheet can be fixed in one go.
+async fn import_tasks(
+    Extension(state): Extension<Arc<AppState>>,
+    api_key: Option<Extension<ApiKey>>,
+    Query(query): Query<ImportTasksQueryDTO>,
+    body: String,
+) -> impl IntoResponse {
+    let tasks = match parse_tasks(query.format, &body) {
+        Ok(tasks) => tasks,
+        Err(err) => return import_error_response(err),
+    };
+
+    match _import_tasks(&state.db, tasks, query.dry_run, changed_by(api_key)).await {
+        Ok(task_ids) if query.dry_run => (
+            StatusCode::OK,
+            Json(json!({ "dry_run": true, "valid": task_ids.len() })),
+        )
+            .into_response(),
+        Ok(task_ids) => (
+            StatusCode::OK,
+            Json(json!({ "imported": task_ids.len(), "ids": task_ids })),
+        )
+            .into_response(),
+        Err(err) => import_error_response(err),
+    }
+}
+
+fn import_error_response(err: ImportError) -> Response {
+    match err {
+        ImportError::Invalid(errors) => {
+            (StatusCode::BAD_REQUEST, Json(json!({ "errors": errors }))).into_response()
+        }
+        ImportError::Database { .. } => internal_server_error(),
+    }
+}
+
+async fn export_tasks(
+    Extension(state): Extension<Arc<AppState>>,
+    Query(query): Query<ExportTasksQueryDTO>,
+) -> impl IntoResponse {
+    let filter = TaskFilterDTO {
+        task_type: None,
+        category: None,
+        status: query.status,
+        deleted: false,
+    };
+    let tasks = match _get_tasks(&state.db, filter, true).await {
+        Ok(tasks) => tasks,
+        Err(_) => return internal_server_error(),
+    };
+
+    match export_tasks_as(query.format, tasks) {
+        Ok(body) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, query.format.content_type())],
+            body,
+        )
+            .into_response(),
+        Err(_) => internal_server_error(),
+    }
+}
+
 async fn delete_task(
     Extension(state): Extension<Arc<AppState>>,
+    api_key: Option<Extension<ApiKey>>,
     Json(delete_task_dto): Json<DeleteTaskDTO>,
 ) -> impl IntoResponse {
-    let delete_result = _delete_task(&state.db, delete_task_dto).await;
+    let delete_result = _delete_task(&state.db, delete_task_dto, changed_by(api_key)).await;
     if delete_result.is_err() {
         return (StatusCode::NOT_FOUND, "Task not found!").into_response();
     }
     (StatusCode::OK, "Task deleted!").into_response()
 }
 
+/// Partial update, also served as PATCH. Sending back the task's ETag in `If-Match`
+/// makes the update fail with 412 if someone else changed the task meanwhile.
 async fn put_task(
     Extension(state): Extension<Arc<AppState>>,
-    Json(update_task_dto): Json<PutTaskDTO>,
+    api_key: Option<Extension<ApiKey>>,
+    headers: HeaderMap,
+    ValidatedJson(update_task_dto): ValidatedJson<PutTaskDTO>,
 ) -> impl IntoResponse {
-    let update_result = _put_task(&state.db, update_task_dto).await;
+    let expected_version = match headers.get(header::IF_MATCH) {
+        None => None,
+        Some(value) => match value.to_str().ok().and_then(parse_etag) {
+            Some(version) => version,
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "error": "Invalid If-Match header." })),
+                )
+                    .into_response()
+            }
+        },
+    };
 
-    if let Err(err) = update_result {
-        return (StatusCode::BAD_REQUEST, err.to_string()).into_response();
+    match _put_task(
+        &state.db,
+        update_task_dto,
+        expected_version,
+        changed_by(api_key),
+    )
+    .await
+    {
+        Ok(task) => (
+            StatusCode::OK,
+            [(header::ETAG, etag(&task))],
+            Json(json!({ "task": task })),
+        )
+            .into_response(),
+        Err(TaskUpdateError::NotFound) => {
+            (StatusCode::NOT_FOUND, "Task not found!").into_response()
+        }
+        Err(TaskUpdateError::VersionMismatch { current }) => (
+            StatusCode::PRECONDITION_FAILED,
+            Json(json!({
+                "error": "Task was changed meanwhile.",
+                "version": current
+            })),
+        )
+            .into_response(),
+        Err(TaskUpdateError::Invalid(errors)) => errors.into_response(),
+        Err(TaskUpdateError::Database { .. }) => internal_server_error(),
     }
+}
+
+fn etag(task: &Task) -> String {
+    format!("\"{}\"", task.version)
+}
 
-    (StatusCode::OK, "Task updated!").into_response()
+/// Reads the version out of an `If-Match` value, `Some(None)` for `*`. `If-Match`
+/// compares strongly (RFC 7232), so weak tags are turned away.
+fn parse_etag(value: &str) -> Option<Option<i32>> {
+    let value = value.trim();
+    if value == "*" {
+        return Some(None);
+    }
+    value
+        .strip_prefix('"')?
+        .strip_suffix('"')?
+        .parse()
+        .ok()
+        .map(Some)
 }
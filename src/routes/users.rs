@@ -1,9 +1,11 @@
 use std::sync::Arc;
 
 use axum::{
-    http::StatusCode,
+    body::Body,
+    extract::Query,
+    http::{header, StatusCode},
     middleware,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     routing::{get, post},
     Extension, Json, Router,
 };
@@ -11,24 +13,34 @@ use axum_extra::{
     headers::{authorization::Bearer, Authorization},
     TypedHeader,
 };
+use futures_util::{stream, StreamExt, TryStreamExt};
+use rand::{distributions::Alphanumeric, Rng};
 use serde_json::json;
 
 use crate::{
     db::{
-        _bind_wallet_address, _create_user, _finish_task, _get_user_by_twitter_id, _get_user_by_wallet_address, _get_users, _set_user_multiplier
+        _bind_wallet_address, _buy_streak_freeze, _create_twitter_oauth_state, _create_user,
+        _create_wallet_link_challenge, _finish_task, _get_user_by_id, _get_user_by_twitter_id,
+        _get_user_by_wallet_address, _get_user_submissions, _get_user_wallets, _get_users,
+        _link_twitter_account, _link_wallet, _set_user_multiplier, _stream_snapshot,
+        _take_twitter_oauth_state, _take_wallet_link_challenge, _unlink_wallet, CompletionError,
+        FinishOutcome,
     },
     jwt::{generate_jwt, validate_jwt, Claims},
-    middlewares::{require_auth_jwt, require_security_hash},
+    middlewares::{require_auth_jwt, require_signature, require_signature_or_api_key},
     models::{
-        BindWalletAddressDTO, CreateUserDTO, FinishTaskDTO, LoginUserDTO, SetMultiplierDTO, User,
-        UserSnapshot, ValidateJwtDTO,
+        ApiScope, BindWalletAddressDTO, CreateUserDTO, FinishTaskDTO, LinkWalletDTO, LoginUserDTO,
+        SetMultiplierDTO, SnapshotQueryDTO, SolanaAddress, SubmissionStatus, TwitterCallbackDTO,
+        UnlinkWalletDTO, User, ValidateJwtDTO, WalletChallengeDTO,
     },
     password::validate_password,
+    snapshots::SnapshotWriter,
     state::AppState,
+    twitter::Pkce,
+    validation::ValidatedJson,
+    wallets::{link_message, normalize_address, verify_signature},
 };
 
-use super::dbg;
-
 pub fn routes() -> Router {
     Router::new().nest("/users", _routes())
 }
@@ -37,14 +49,32 @@ fn _routes() -> Router {
     Router::new()
         .route("/bind", post(bind_wallet_address))
         .route("/finish", post(finish_task))
+        .route("/streak/freeze", post(buy_streak_freeze))
+        .route("/submissions", get(get_own_submissions))
+        .route("/twitter/link", post(start_twitter_link))
+        .route("/twitter/callback", post(finish_twitter_link))
+        .route(
+            "/wallets",
+            get(get_own_wallets).post(link_wallet).delete(unlink_wallet),
+        )
+        .route("/wallets/challenge", post(start_wallet_link))
         .layer(middleware::from_fn(require_auth_jwt))
         .route("/login", post(login_user))
         .route("/", post(create_user))
         .route("/validate", post(validate_jwt_route))
         .route("/multiplier", post(set_multiplier))
-        .route("/snapshot", get(get_snapshot))
-        .route("/", get(get_users))
-        .layer(middleware::from_fn(require_security_hash))
+        .layer(middleware::from_fn(require_signature))
+        .merge(Router::new().route("/snapshot", get(get_snapshot)).layer(
+            middleware::from_fn_with_state(ApiScope::SnapshotRead, require_signature_or_api_key),
+        ))
+        .merge(
+            Router::new()
+                .route("/", get(get_users))
+                .layer(middleware::from_fn_with_state(
+                    ApiScope::UsersRead,
+                    require_signature_or_api_key,
+                )),
+        )
 }
 
 async fn validate_jwt_route(
@@ -55,13 +85,21 @@ async fn validate_jwt_route(
     let token = authorization_token.token();
     match validate_jwt::<Claims>(token, &state.decoding_key) {
         Ok(claims) => {
-            let user = _get_user_by_wallet_address(&state.db, &claims.wallet)
+            let Ok(wallet) = claims.wallet.parse::<SolanaAddress>() else {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({"error":"Inaccessible"})),
+                )
+                    .into_response();
+            };
+            let user = _get_user_by_wallet_address(&state.db, &wallet)
                 .await
                 .unwrap()
                 .unwrap();
 
+            let user: User = user.into();
             if validate_jwt_dto.solana_adr != user.wallet_address
-                || validate_jwt_dto.username != user.twitter_id
+                || !user.is_named(&validate_jwt_dto.username)
             {
                 return (
                     StatusCode::BAD_REQUEST,
@@ -90,7 +128,7 @@ async fn validate_jwt_route(
 
 async fn create_user(
     Extension(state): Extension<Arc<AppState>>,
-    Json(create_user_dto): Json<CreateUserDTO>,
+    ValidatedJson(create_user_dto): ValidatedJson<CreateUserDTO>,
 ) -> impl IntoResponse {
     let result = _create_user(
         &state.db,
@@ -143,14 +181,14 @@ async fn create_user(
 
 async fn login_user(
     Extension(state): Extension<Arc<AppState>>,
-    Json(login_user_dto): Json<LoginUserDTO>,
+    ValidatedJson(login_user_dto): ValidatedJson<LoginUserDTO>,
 ) -> impl IntoResponse {
     let user = _get_user_by_twitter_id(&state.db, login_user_dto.twitter_id.as_str()).await;
     match user {
         Ok(user) => match user {
             Some(user) => {
                 let cloned_user = user.clone();
-                if user.wallet_address != login_user_dto.solana_adr {
+                if login_user_dto.solana_adr != user.wallet_address {
                     (
                         StatusCode::BAD_REQUEST,
                         Json(json!({"error":"Wrong wallet connected."})),
@@ -216,10 +254,23 @@ async fn bind_wallet_address(
     Extension(state): Extension<Arc<AppState>>,
     Json(bind_wallet_address_dto): Json<BindWalletAddressDTO>,
 ) -> impl IntoResponse {
-    _bind_wallet_address(&state.db, bind_wallet_address_dto)
-        .await
-        .unwrap();
-    (StatusCode::OK).into_response()
+    match _bind_wallet_address(&state.db, bind_wallet_address_dto).await {
+        Ok(Some(_)) => (StatusCode::OK).into_response(),
+        Ok(None) => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Wallet is already linked to another user."
+            })),
+        )
+            .into_response(),
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal Server Error"
+            })),
+        )
+            .into_response(),
+    }
 }
 
 async fn get_users(Extension(state): Extension<Arc<AppState>>) -> impl IntoResponse {
@@ -227,33 +278,46 @@ async fn get_users(Extension(state): Extension<Arc<AppState>>) -> impl IntoRespo
     (StatusCode::OK, Json(json!({"users": users })))
 }
 
-async fn get_snapshot(Extension(state): Extension<Arc<AppState>>) -> impl IntoResponse {
-    match _get_users(&state.db).await {
-        Ok(users) => {
-            let snapshots: Vec<UserSnapshot> = users
-                .into_iter()
-                .map(|user| UserSnapshot {
-                    twitter_id: user.twitter_id,
-                    wallet_address: user.wallet_address,
-                    points: (user.total_points + user.referral_points) * user.multiplier,
-                })
-                .collect();
-
-            Json(snapshots).into_response()
+/// Streams the live snapshot in the requested format, page by page.
+async fn get_snapshot(
+    Extension(state): Extension<Arc<AppState>>,
+    Query(snapshot_query): Query<SnapshotQueryDTO>,
+) -> impl IntoResponse {
+    let format = snapshot_query.format;
+    let mut batches = Box::pin(_stream_snapshot(state.db.clone(), snapshot_query.chain));
+    // Waiting for the first batch lets a failing query still answer with an error status.
+    let first = match batches.try_next().await {
+        Ok(first) => first,
+        Err(_) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "Failed to retrieve users."
+                })),
+            )
+                .into_response()
         }
-        Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": "Failed to retrieve users."
-            })),
-        )
-            .into_response(),
-    }
+    };
+
+    let mut writer = SnapshotWriter::new(format);
+    let rows = stream::iter(first.map(Ok))
+        .chain(batches)
+        .map_ok(move |entries| writer.write(entries));
+    let body = stream::once(async move { Ok(format.header().to_vec()) })
+        .chain(rows)
+        .chain(stream::once(async move { Ok(format.footer().to_vec()) }));
+
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, format.content_type())],
+        Body::from_stream(body),
+    )
+        .into_response()
 }
 
 async fn set_multiplier(
     Extension(state): Extension<Arc<AppState>>,
-    Json(set_multiplier_dto): Json<SetMultiplierDTO>,
+    ValidatedJson(set_multiplier_dto): ValidatedJson<SetMultiplierDTO>,
 ) -> impl IntoResponse {
     let user_result = _get_user_by_twitter_id(&state.db, &set_multiplier_dto.twitter_id).await;
     match user_result {
@@ -286,7 +350,7 @@ async fn set_multiplier(
 async fn finish_task(
     claims: Claims,
     Extension(state): Extension<Arc<AppState>>,
-    Json(finish_task_dto): Json<FinishTaskDTO>,
+    ValidatedJson(finish_task_dto): ValidatedJson<FinishTaskDTO>,
 ) -> impl IntoResponse {
     let wallet = finish_task_dto.wallet.clone();
     if wallet != claims.wallet {
@@ -298,63 +362,462 @@ async fn finish_task(
         )
             .into_response();
     }
-    match _finish_task(&state.db, finish_task_dto).await {
-        Ok(_) => {
-            let user = match _get_user_by_wallet_address(&state.db, wallet.as_str()).await {
-                Ok(Some(user)) => user,
-                Ok(None) => {
-                    return (
-                        StatusCode::NOT_FOUND,
-                        Json(json!({
-                            "error": "User not found"
-                        })),
-                    )
-                        .into_response();
-                }
-                Err(e) => {
-                    return (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(json!({
-                            "error": "Internal Server Error"
-                        })),
-                    )
-                        .into_response();
-                }
-            };
-            let cloned_user = user.clone();
-            let claims = Claims::new(
-                user.id,
-                user.twitter_id.clone(),
-                user.wallet_address.clone(),
-                user.total_points,
-                user.referred_by.len() as u32,
-                user.referral_points,
-                user.referral_code,
-                user.finished_tasks.clone(),
-                user.multiplier,
-            );
+    let outcome = _finish_task(
+        &state.db,
+        &state.verifiers,
+        finish_task_dto,
+        state.period_timezone,
+    )
+    .await;
+    let (capacity, quiz) = match outcome {
+        Ok(FinishOutcome::PendingReview { submission_id }) => {
+            return (
+                StatusCode::ACCEPTED,
+                Json(json!({
+                    "submission_id": submission_id,
+                    "status": SubmissionStatus::Pending
+                })),
+            )
+                .into_response()
+        }
+        Ok(FinishOutcome::Completed(capacity)) => (capacity, None),
+        Ok(FinishOutcome::QuizPassed {
+            score,
+            points,
+            capacity,
+        }) => (capacity, Some(json!({ "score": score, "points": points }))),
+        Err(err) => return completion_error_response(err),
+    };
 
-            match generate_jwt(claims, &state.encoding_key) {
-                Ok(jwt) => {
-                    let public_user: User = cloned_user.into();
-                    (
-                        StatusCode::OK,
-                        Json(json!({
-                            "user": public_user,
-                            "jwt": jwt
-                        })),
-                    )
-                        .into_response()
-                }
-                Err(_err) => (
+    let user = match _get_user_by_wallet_address(&state.db, &wallet).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "error": "User not found"
+                })),
+            )
+                .into_response();
+        }
+        Err(_e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "Internal Server Error"
+                })),
+            )
+                .into_response();
+        }
+    };
+    let cloned_user = user.clone();
+    let claims = Claims::new(
+        user.id,
+        user.twitter_id.clone(),
+        user.wallet_address.clone(),
+        user.total_points,
+        user.referred_by.len() as u32,
+        user.referral_points,
+        user.referral_code,
+        user.finished_tasks.clone(),
+        user.multiplier,
+    );
+
+    match generate_jwt(claims, &state.encoding_key) {
+        Ok(jwt) => {
+            let public_user: User = cloned_user.into();
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "user": public_user,
+                    "jwt": jwt,
+                    "remaining_completions": capacity.remaining_completions,
+                    "remaining_budget": capacity.remaining_budget,
+                    "quiz": quiz
+                })),
+            )
+                .into_response()
+        }
+        Err(_err) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Bad Request"
+            })),
+        )
+            .into_response(),
+    }
+}
+
+async fn buy_streak_freeze(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    match _buy_streak_freeze(&state.db, claims.id).await {
+        Ok(Some(streak_freezes)) => (
+            StatusCode::OK,
+            Json(json!({
+                "streak_freezes": streak_freezes
+            })),
+        )
+            .into_response(),
+        Ok(None) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Not enough points or freeze limit reached."
+            })),
+        )
+            .into_response(),
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal Server Error"
+            })),
+        )
+            .into_response(),
+    }
+}
+
+pub(super) fn completion_error_response(err: CompletionError) -> Response {
+    let status = match err {
+        CompletionError::Database { .. } => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "Internal Server Error"
+                })),
+            )
+                .into_response()
+        }
+        CompletionError::UserNotFound
+        | CompletionError::TaskNotFound
+        | CompletionError::SubmissionNotFound => StatusCode::NOT_FOUND,
+        CompletionError::TaskNotAvailable
+        | CompletionError::TaskLocked
+        | CompletionError::TaskExhausted
+        | CompletionError::NoAttemptsLeft
+        | CompletionError::ProofRejected { .. }
+        | CompletionError::Recurrence { .. } => StatusCode::BAD_REQUEST,
+        CompletionError::VerifierFailed { .. } => StatusCode::BAD_GATEWAY,
+    };
+    (
+        status,
+        Json(json!({
+            "error": err.to_string()
+        })),
+    )
+        .into_response()
+}
+
+async fn get_own_submissions(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    match _get_user_submissions(&state.db, claims.id).await {
+        Ok(submissions) => {
+            (StatusCode::OK, Json(json!({ "submissions": submissions }))).into_response()
+        }
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal Server Error"
+            })),
+        )
+            .into_response(),
+    }
+}
+
+fn twitter_linking_unavailable() -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({
+            "error": "Twitter account linking is not configured."
+        })),
+    )
+        .into_response()
+}
+
+/// Starts linking an X account, the client sends the user to `authorize_url`.
+async fn start_twitter_link(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    let twitter_oauth = match &state.twitter_oauth {
+        Some(twitter_oauth) => twitter_oauth,
+        None => return twitter_linking_unavailable(),
+    };
+
+    let oauth_state: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+    let pkce = Pkce::generate();
+
+    let authorize_url = match twitter_oauth.authorize_url(&oauth_state, &pkce.code_challenge) {
+        Ok(authorize_url) => authorize_url,
+        Err(_) => return twitter_linking_unavailable(),
+    };
+    if _create_twitter_oauth_state(&state.db, &oauth_state, claims.id, &pkce.code_verifier)
+        .await
+        .is_err()
+    {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal Server Error"
+            })),
+        )
+            .into_response();
+    }
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "authorize_url": authorize_url.to_string(),
+            "state": oauth_state
+        })),
+    )
+        .into_response()
+}
+
+/// Completes linking with the `code` and `state` X redirected back with.
+async fn finish_twitter_link(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+    Json(twitter_callback_dto): Json<TwitterCallbackDTO>,
+) -> impl IntoResponse {
+    let twitter_oauth = match &state.twitter_oauth {
+        Some(twitter_oauth) => twitter_oauth,
+        None => return twitter_linking_unavailable(),
+    };
+
+    let code_verifier =
+        match _take_twitter_oauth_state(&state.db, &twitter_callback_dto.state, claims.id).await {
+            Ok(Some(code_verifier)) => code_verifier,
+            Ok(None) => {
+                return (
                     StatusCode::BAD_REQUEST,
                     Json(json!({
-                        "error": "Bad Request"
+                        "error": "Unknown or expired state."
                     })),
                 )
-                    .into_response(),
+                    .into_response()
             }
+            Err(_) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({
+                        "error": "Internal Server Error"
+                    })),
+                )
+                    .into_response()
+            }
+        };
+
+    let account = match twitter_oauth
+        .fetch_account(&twitter_callback_dto.code, &code_verifier)
+        .await
+    {
+        Ok(account) => account,
+        Err(err) => {
+            return (
+                StatusCode::BAD_GATEWAY,
+                Json(json!({
+                    "error": err.to_string()
+                })),
+            )
+                .into_response()
         }
+    };
+
+    match _link_twitter_account(&state.db, claims.id, &account).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": "Twitter account is already linked to another user."
+                })),
+            )
+                .into_response()
+        }
+        Err(_) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "Internal Server Error"
+                })),
+            )
+                .into_response()
+        }
+    }
+
+    match _get_user_by_id(&state.db, claims.id).await {
+        Ok(Some(user)) => (StatusCode::OK, Json(json!({ "user": user }))).into_response(),
+        _ => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "User not found"
+            })),
+        )
+            .into_response(),
+    }
+}
+
+async fn get_own_wallets(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    match _get_user_wallets(&state.db, claims.id).await {
+        Ok(wallets) => (StatusCode::OK, Json(json!({ "wallets": wallets }))).into_response(),
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal Server Error"
+            })),
+        )
+            .into_response(),
+    }
+}
+
+/// Starts linking a wallet, answers with the message it has to sign.
+async fn start_wallet_link(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+    ValidatedJson(wallet_challenge_dto): ValidatedJson<WalletChallengeDTO>,
+) -> impl IntoResponse {
+    let chain = wallet_challenge_dto.chain;
+    let Ok(address) = normalize_address(chain, &wallet_challenge_dto.address) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Invalid address."
+            })),
+        )
+            .into_response();
+    };
+    let nonce: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+
+    if _create_wallet_link_challenge(&state.db, &nonce, claims.id, chain, &address)
+        .await
+        .is_err()
+    {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal Server Error"
+            })),
+        )
+            .into_response();
+    }
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "address": address,
+            "nonce": nonce,
+            "message": link_message(claims.id, chain, &address, &nonce)
+        })),
+    )
+        .into_response()
+}
+
+/// Links the wallet once it signed the challenge message, with ed25519 on Solana and
+/// `personal_sign` on EVM chains.
+async fn link_wallet(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+    ValidatedJson(link_wallet_dto): ValidatedJson<LinkWalletDTO>,
+) -> impl IntoResponse {
+    let chain = link_wallet_dto.chain;
+    let Ok(address) = normalize_address(chain, &link_wallet_dto.address) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Invalid address."
+            })),
+        )
+            .into_response();
+    };
+
+    match _take_wallet_link_challenge(
+        &state.db,
+        &link_wallet_dto.nonce,
+        claims.id,
+        chain,
+        &address,
+    )
+    .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": "Unknown or expired nonce."
+                })),
+            )
+                .into_response()
+        }
+        Err(_) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "Internal Server Error"
+                })),
+            )
+                .into_response()
+        }
+    }
+
+    let message = link_message(claims.id, chain, &address, &link_wallet_dto.nonce);
+    if let Err(err) = verify_signature(chain, &address, &message, &link_wallet_dto.signature) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": err.to_string()
+            })),
+        )
+            .into_response();
+    }
+
+    match _link_wallet(&state.db, claims.id, chain, &address).await {
+        Ok(Some(wallet)) => (StatusCode::OK, Json(json!({ "wallet": wallet }))).into_response(),
+        Ok(None) => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Wallet is already linked to another user."
+            })),
+        )
+            .into_response(),
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal Server Error"
+            })),
+        )
+            .into_response(),
+    }
+}
+
+async fn unlink_wallet(
+    claims: Claims,
+    Extension(state): Extension<Arc<AppState>>,
+    Json(unlink_wallet_dto): Json<UnlinkWalletDTO>,
+) -> impl IntoResponse {
+    match _unlink_wallet(&state.db, claims.id, unlink_wallet_dto.chain).await {
+        Ok(true) => (StatusCode::OK).into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "No wallet linked for this chain."
+            })),
+        )
+            .into_response(),
         Err(_) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({
@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::models::{SnapshotEntry, UserSnapshot, WalletChain};
+
+/// How a user's points are computed, recorded with every frozen snapshot.
+pub const POINTS_FORMULA: &str = "total_points * multiplier";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SnapshotParameters {
+    pub chain: Option<WalletChain>,
+    pub formula: String,
+}
+
+/// How `/users/snapshot` is written out.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotFormat {
+    /// One array.
+    #[default]
+    Json,
+    /// One object per line.
+    Ndjson,
+    Csv,
+}
+
+impl SnapshotFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            SnapshotFormat::Json => "application/json",
+            SnapshotFormat::Ndjson => "application/x-ndjson",
+            SnapshotFormat::Csv => "text/csv",
+        }
+    }
+
+    pub fn header(self) -> &'static [u8] {
+        match self {
+            SnapshotFormat::Json => b"[",
+            SnapshotFormat::Ndjson => b"",
+            SnapshotFormat::Csv => b"twitter_id,wallet_address,points\n",
+        }
+    }
+
+    pub fn footer(self) -> &'static [u8] {
+        match self {
+            SnapshotFormat::Json => b"]",
+            SnapshotFormat::Ndjson | SnapshotFormat::Csv => b"",
+        }
+    }
+}
+
+/// Encodes entries a batch at a time, between the format's header and footer.
+#[derive(Debug)]
+pub struct SnapshotWriter {
+    format: SnapshotFormat,
+    written: bool,
+}
+
+impl SnapshotWriter {
+    pub fn new(format: SnapshotFormat) -> Self {
+        SnapshotWriter {
+            format,
+            written: false,
+        }
+    }
+
+    pub fn write(&mut self, entries: Vec<SnapshotEntry>) -> Vec<u8> {
+        let mut out = vec![];
+        match self.format {
+            SnapshotFormat::Json | SnapshotFormat::Ndjson => {
+                for entry in entries {
+                    if self.format == SnapshotFormat::Json && self.written {
+                        out.push(b',');
+                    }
+                    serde_json::to_writer(&mut out, &UserSnapshot::from(entry))
+                        .expect("a snapshot row always serializes");
+                    if self.format == SnapshotFormat::Ndjson {
+                        out.push(b'\n');
+                    }
+                    self.written = true;
+                }
+            }
+            SnapshotFormat::Csv => {
+                let mut writer = csv::WriterBuilder::new()
+                    .has_headers(false)
+                    .from_writer(out);
+                for entry in entries {
+                    let mut row = UserSnapshot::from(entry);
+                    row.twitter_id = defuse_formula(row.twitter_id);
+                    row.wallet_address = defuse_formula(row.wallet_address);
+                    writer
+                        .serialize(row)
+                        .expect("a snapshot row always serializes");
+                }
+                out = writer.into_inner().expect("writing to memory can't fail");
+            }
+        }
+        out
+    }
+}
+
+/// Prefixes a CSV text cell that a spreadsheet would run as a formula with `'`, that is
+/// one starting with `=`, `+`, `-`, `@`, a tab or a carriage return. Points are left
+/// alone, a negative number is still a number.
+fn defuse_formula(cell: String) -> String {
+    if cell.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{cell}")
+    } else {
+        cell
+    }
+}
+
+/// sha256 over the entries ordered by user, one `user_id,twitter_id,wallet,points` CSV
+/// row each, hex encoded. Quoting keeps a comma or line break in a twitter id from
+/// passing for another row. Anyone holding a download can recompute it.
+pub fn content_hash(entries: &[SnapshotEntry]) -> String {
+    let mut entries: Vec<&SnapshotEntry> = entries.iter().collect();
+    entries.sort_by_key(|entry| entry.user_id);
+
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(vec![]);
+    for entry in entries {
+        writer
+            .write_record([
+                entry.user_id.to_string(),
+                entry.twitter_id.clone(),
+                entry.wallet_address.clone(),
+                entry.points.to_string(),
+            ])
+            .expect("writing to memory can't fail");
+    }
+    let rows = writer.into_inner().expect("writing to memory can't fail");
+    hex::encode(Sha256::digest(rows))
+}
+
+/// A user in both snapshots whose points or wallet changed.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct SnapshotChange {
+    pub user_id: i32,
+    pub twitter_id: String,
+    pub points_from: i64,
+    pub points_to: i64,
+    pub delta: i64,
+    pub wallet_from: String,
+    pub wallet_to: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq, Default)]
+pub struct SnapshotDiff {
+    pub added: Vec<SnapshotEntry>,
+    pub removed: Vec<SnapshotEntry>,
+    pub changed: Vec<SnapshotChange>,
+}
+
+/// What changed going from `from` to `to`, every list ordered by user.
+pub fn diff(from: &[SnapshotEntry], to: &[SnapshotEntry]) -> SnapshotDiff {
+    let before: HashMap<i32, &SnapshotEntry> =
+        from.iter().map(|entry| (entry.user_id, entry)).collect();
+    let after: HashMap<i32, &SnapshotEntry> =
+        to.iter().map(|entry| (entry.user_id, entry)).collect();
+
+    let mut diff = SnapshotDiff::default();
+    for entry in to {
+        match before.get(&entry.user_id) {
+            None => diff.added.push(entry.clone()),
+            Some(old)
+                if old.points != entry.points || old.wallet_address != entry.wallet_address =>
+            {
+                diff.changed.push(SnapshotChange {
+                    user_id: entry.user_id,
+                    twitter_id: entry.twitter_id.clone(),
+                    points_from: old.points,
+                    points_to: entry.points,
+                    delta: entry.points - old.points,
+                    wallet_from: old.wallet_address.clone(),
+                    wallet_to: entry.wallet_address.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    diff.removed = from
+        .iter()
+        .filter(|entry| !after.contains_key(&entry.user_id))
+        .cloned()
+        .collect();
+
+    diff.added.sort_by_key(|entry| entry.user_id);
+    diff.removed.sort_by_key(|entry| entry.user_id);
+    diff.changed.sort_by_key(|change| change.user_id);
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(user_id: i32, wallet: &str, points: i64) -> SnapshotEntry {
+        SnapshotEntry {
+            user_id,
+            twitter_id: format!("user{user_id}"),
+            wallet_address: wallet.to_string(),
+            points,
+        }
+    }
+
+    #[test]
+    fn test_content_hash_ignores_order() {
+        let entries = vec![entry(2, "b", 20), entry(1, "a", 10)];
+        let reversed: Vec<SnapshotEntry> = entries.iter().rev().cloned().collect();
+        assert_eq!(content_hash(&entries), content_hash(&reversed));
+        assert_ne!(
+            content_hash(&entries),
+            content_hash(&[entry(2, "b", 21), entry(1, "a", 10)])
+        );
+        // Without quoting both would hash `1,user1,a,b,1`.
+        let mut comma_in_id = entry(1, "b", 1);
+        comma_in_id.twitter_id = "user1,a".to_string();
+        assert_ne!(
+            content_hash(&[entry(1, "a,b", 1)]),
+            content_hash(&[comma_in_id])
+        );
+        // sha256 of nothing.
+        assert_eq!(
+            content_hash(&[]),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    fn export(format: SnapshotFormat, batches: Vec<Vec<SnapshotEntry>>) -> String {
+        let mut writer = SnapshotWriter::new(format);
+        let mut out = format.header().to_vec();
+        for batch in batches {
+            out.extend(writer.write(batch));
+        }
+        out.extend(format.footer());
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_export_formats() {
+        let batches = || {
+            vec![
+                vec![entry(1, "a", 10), entry(2, "b", 20)],
+                vec![],
+                vec![entry(3, "c", -3)],
+            ]
+        };
+
+        let json: Vec<serde_json::Value> =
+            serde_json::from_str(&export(SnapshotFormat::Json, batches())).unwrap();
+        assert_eq!(json.len(), 3);
+        assert_eq!(
+            json[2],
+            serde_json::json!({"twitter_id": "user3", "wallet_address": "c", "points": -3})
+        );
+        assert_eq!(export(SnapshotFormat::Json, vec![]), "[]");
+
+        let ndjson = export(SnapshotFormat::Ndjson, batches());
+        assert_eq!(ndjson.lines().count(), 3);
+        assert_eq!(
+            ndjson.lines().next().unwrap(),
+            r#"{"twitter_id":"user1","wallet_address":"a","points":10}"#
+        );
+
+        let mut quoted = entry(4, "d", 1);
+        quoted.twitter_id = "comma, \"quote\"".to_string();
+        assert_eq!(
+            export(
+                SnapshotFormat::Csv,
+                vec![vec![entry(1, "a", 10)], vec![quoted]]
+            ),
+            "twitter_id,wallet_address,points\nuser1,a,10\n\"comma, \"\"quote\"\"\",d,1\n"
+        );
+
+        let mut formula = entry(5, "@SUM(A1)", -5);
+        formula.twitter_id = "=HYPERLINK(\"x\")".to_string();
+        assert_eq!(
+            export(SnapshotFormat::Csv, vec![vec![formula, entry(6, "+1", 6)]]),
+            "twitter_id,wallet_address,points\n\"'=HYPERLINK(\"\"x\"\")\",'@SUM(A1),-5\nuser6,'+1,6\n"
+        );
+        let mut tab = entry(7, "\r=1+1", 7);
+        tab.twitter_id = "\t=1+1".to_string();
+        assert_eq!(
+            export(SnapshotFormat::Csv, vec![vec![tab]]),
+            "twitter_id,wallet_address,points\n'\t=1+1,\"'\r=1+1\",7\n"
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let from = vec![entry(1, "a", 10), entry(2, "b", 20), entry(3, "c", 30)];
+        let to = vec![
+            entry(4, "d", 5),
+            entry(2, "b", 25),
+            entry(3, "z", 30),
+            entry(1, "a", 10),
+        ];
+        let diff = diff(&from, &to);
+
+        assert_eq!(diff.added, vec![entry(4, "d", 5)]);
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.changed.len(), 2);
+        assert_eq!(diff.changed[0].user_id, 2);
+        assert_eq!(diff.changed[0].delta, 5);
+        assert_eq!(diff.changed[1].wallet_to, "z");
+        assert_eq!(diff.changed[1].delta, 0);
+
+        let back = super::diff(&to, &from);
+        assert_eq!(back.removed, vec![entry(4, "d", 5)]);
+        assert_eq!(back.changed[0].delta, -5);
+    }
+}
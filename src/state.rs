@@ -1,20 +1,66 @@
+use chrono_tz::Tz;
 use jsonwebtoken::{DecodingKey, EncodingKey};
 use password_encryptor::PasswordEncryptor;
 
 use crate::{
     db::Database,
-    middlewares::{RateLimiterConfig, RedisRateLimiterDb},
+    middlewares::{RateLimiterConfig, RedisNonceStore, RedisRateLimiterDb},
+    twitter::TwitterOAuth,
+    verification::Verifiers,
 };
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: Database,
     pub dev_secret: String,
-    pub security_hash: String,
     pub password_encryptor: PasswordEncryptor,
     pub salt: String,
     pub encoding_key: EncodingKey,
     pub decoding_key: DecodingKey,
     pub redis_rate_limiter_db: RedisRateLimiterDb,
     pub rate_limiter_config: RateLimiterConfig,
+    pub nonce_store: RedisNonceStore,
+    /// Timezone that daily and weekly task periods roll over in.
+    pub period_timezone: Tz,
+    pub verifiers: Verifiers,
+    /// `None` when no OAuth client is configured, linking is unavailable then.
+    pub twitter_oauth: Option<TwitterOAuth>,
+}
+
+#[cfg(test)]
+impl AppState {
+    /// State over `db` for route tests, with `dev_secret` as the dev secret. Expects Redis
+    /// on localhost like the rate limiter tests.
+    pub async fn for_tests(db: Database) -> Self {
+        use crate::{
+            constants::{REQUESTS_AMOUNT_LIMIT, REQUESTS_AMOUNT_TIME_FRAME},
+            jwt,
+            middlewares::RateLimiterRedisInteractor,
+        };
+
+        let redis_rate_limiter_db =
+            RedisRateLimiterDb::new("redis://localhost:6379/15".to_string())
+                .await
+                .expect("Failed to create test Redis client");
+        let nonce_store = RedisNonceStore::new(&redis_rate_limiter_db.client)
+            .await
+            .unwrap();
+        AppState {
+            db,
+            dev_secret: "dev_secret".to_string(),
+            password_encryptor: PasswordEncryptor::new(vec![1, 2, 3], None),
+            salt: "salt".to_string(),
+            encoding_key: jwt::init_encoding_key("secret").unwrap(),
+            decoding_key: jwt::init_decoding_key("secret").unwrap(),
+            redis_rate_limiter_db,
+            rate_limiter_config: RateLimiterConfig {
+                requests_amount: REQUESTS_AMOUNT_LIMIT,
+                time_frame: REQUESTS_AMOUNT_TIME_FRAME,
+            },
+            nonce_store,
+            period_timezone: Tz::UTC,
+            verifiers: Verifiers::new(reqwest::Client::new(), None),
+            twitter_oauth: None,
+        }
+    }
 }
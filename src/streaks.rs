@@ -0,0 +1,121 @@
+use chrono::NaiveDate;
+
+use crate::constants::{STREAK_BONUS_CAP, STREAK_BONUS_STEP};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreakState {
+    pub current_streak: i32,
+    pub longest_streak: i32,
+    pub last_check_in: Option<NaiveDate>,
+    pub streak_freezes: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckIn {
+    pub state: StreakState,
+    pub bonus: i32,
+    pub freezes_used: i32,
+}
+
+/// Bonus for reaching `streak` consecutive days: 10, 20, 30, ... capped at 100.
+pub fn streak_bonus(streak: i32) -> i32 {
+    (streak * STREAK_BONUS_STEP).min(STREAK_BONUS_CAP)
+}
+
+/// Applies a check-in on `today`. Missed days are covered by freezes if there are
+/// enough of them, otherwise the streak starts over. Returns `None` when the user
+/// already checked in today.
+pub fn check_in(state: StreakState, today: NaiveDate) -> Option<CheckIn> {
+    let missed_days = match state.last_check_in {
+        Some(last) if last >= today => return None,
+        Some(last) => (today - last).num_days() as i32 - 1,
+        None => i32::MAX,
+    };
+
+    let (current_streak, freezes_used) = if missed_days == 0 {
+        (state.current_streak + 1, 0)
+    } else if missed_days <= state.streak_freezes {
+        (state.current_streak + 1, missed_days)
+    } else {
+        (1, 0)
+    };
+
+    Some(CheckIn {
+        state: StreakState {
+            current_streak,
+            longest_streak: state.longest_streak.max(current_streak),
+            last_check_in: Some(today),
+            streak_freezes: state.streak_freezes - freezes_used,
+        },
+        bonus: streak_bonus(current_streak),
+        freezes_used,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+    }
+
+    fn state(current_streak: i32, last: Option<NaiveDate>, streak_freezes: i32) -> StreakState {
+        StreakState {
+            current_streak,
+            longest_streak: current_streak,
+            last_check_in: last,
+            streak_freezes,
+        }
+    }
+
+    #[test]
+    fn test_bonus_escalates_and_caps() {
+        assert_eq!(streak_bonus(1), 10);
+        assert_eq!(streak_bonus(3), 30);
+        assert_eq!(streak_bonus(10), 100);
+        assert_eq!(streak_bonus(25), 100);
+    }
+
+    #[test]
+    fn test_first_check_in_starts_streak() {
+        let check_in = check_in(state(0, None, 0), day(1)).unwrap();
+        assert_eq!(check_in.state.current_streak, 1);
+        assert_eq!(check_in.bonus, 10);
+    }
+
+    #[test]
+    fn test_consecutive_days_extend_streak() {
+        let check_in = check_in(state(2, Some(day(1)), 0), day(2)).unwrap();
+        assert_eq!(check_in.state.current_streak, 3);
+        assert_eq!(check_in.state.longest_streak, 3);
+        assert_eq!(check_in.bonus, 30);
+    }
+
+    #[test]
+    fn test_same_day_is_rejected() {
+        assert!(check_in(state(2, Some(day(2)), 0), day(2)).is_none());
+    }
+
+    #[test]
+    fn test_missed_day_resets_streak() {
+        let check_in = check_in(state(5, Some(day(1)), 0), day(3)).unwrap();
+        assert_eq!(check_in.state.current_streak, 1);
+        assert_eq!(check_in.state.longest_streak, 5);
+    }
+
+    #[test]
+    fn test_freezes_cover_missed_days() {
+        let check_in = check_in(state(5, Some(day(1)), 2), day(4)).unwrap();
+        assert_eq!(check_in.state.current_streak, 6);
+        assert_eq!(check_in.freezes_used, 2);
+        assert_eq!(check_in.state.streak_freezes, 0);
+    }
+
+    #[test]
+    fn test_not_enough_freezes_keeps_them() {
+        let check_in = check_in(state(5, Some(day(1)), 1), day(4)).unwrap();
+        assert_eq!(check_in.state.current_streak, 1);
+        assert_eq!(check_in.state.streak_freezes, 1);
+    }
+}
@@ -0,0 +1,67 @@
+use serde_json::{json, Map, Value};
+
+use crate::models::{CreateTaskDTO, Task};
+
+/// The editable fields of a task, in the shape `POST /tasks` takes so a revision can
+/// be put back as is.
+pub fn snapshot(task: &Task) -> Value {
+    serde_json::to_value(CreateTaskDTO::from(task.clone())).unwrap_or_default()
+}
+
+/// Fields that differ between two snapshots as `{"field": {"from": ..., "to": ...}}`,
+/// every set field counts as changed for a new task.
+pub fn diff(before: Option<&Value>, after: &Value) -> Value {
+    let empty = Map::new();
+    let before = before.and_then(Value::as_object).unwrap_or(&empty);
+    let Some(after) = after.as_object() else {
+        return json!({});
+    };
+
+    let changes: Map<String, Value> = after
+        .iter()
+        .filter_map(|(field, to)| {
+            let from = before.get(field).unwrap_or(&Value::Null);
+            (from != to).then(|| (field.clone(), json!({ "from": from, "to": to })))
+        })
+        .collect();
+    Value::Object(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{models::TaskType, verification::tests::task};
+
+    #[test]
+    fn test_diff_lists_changed_fields_only() {
+        let before = task(TaskType::Follow, json!({}));
+        let mut after = before.clone();
+        after.points = 25;
+        after.metadata = json!({ "target": "frog" });
+
+        let diff = diff(Some(&snapshot(&before)), &snapshot(&after));
+        assert_eq!(
+            diff,
+            json!({
+                "points": { "from": 10, "to": 25 },
+                "metadata": { "from": null, "to": { "target": "frog" } },
+            })
+        );
+    }
+
+    #[test]
+    fn test_diff_of_new_task_has_every_set_field() {
+        let diff = diff(None, &snapshot(&task(TaskType::Follow, json!({}))));
+        assert_eq!(diff["points"], json!({ "from": null, "to": 10 }));
+        assert_eq!(diff["task_type"], json!({ "from": null, "to": "follow" }));
+        assert!(diff.get("link").is_none());
+    }
+
+    #[test]
+    fn test_snapshot_round_trips_into_a_task() {
+        let task = task(TaskType::Quiz, json!({ "answer": "frog" }));
+        let restored: CreateTaskDTO = serde_json::from_value(snapshot(&task)).unwrap();
+        assert_eq!(restored.points, task.points);
+        assert_eq!(restored.metadata, Some(task.metadata));
+    }
+}
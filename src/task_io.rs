@@ -0,0 +1,357 @@
+use std::{fmt, path::Path};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    models::{CreateTaskDTO, Task, TaskRecurrence, TaskStatus, TaskType},
+    validation::Validate,
+};
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+impl TaskFormat {
+    /// Picks the format from a file extension, `None` for anything else.
+    pub fn from_path(path: &Path) -> Option<TaskFormat> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "json" => Some(TaskFormat::Json),
+            "csv" => Some(TaskFormat::Csv),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            TaskFormat::Json => "application/json",
+            TaskFormat::Csv => "text/csv",
+        }
+    }
+}
+
+/// A problem with one task of an import. Rows count from 1, for CSV they are lines of
+/// the file with the header on line 1 so they match the spreadsheet.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct RowError {
+    pub row: usize,
+    pub error: String,
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    /// Nothing was imported because of these rows.
+    Invalid(Vec<RowError>),
+    Database {
+        error: String,
+    },
+}
+
+impl From<sqlx::Error> for ImportError {
+    fn from(error: sqlx::Error) -> Self {
+        ImportError::Database {
+            error: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Invalid(errors) => {
+                let errors: Vec<String> = errors
+                    .iter()
+                    .map(|error| format!("row {}: {}", error.row, error.error))
+                    .collect();
+                write!(f, "{}", errors.join("\n"))
+            }
+            ImportError::Database { error } => write!(f, "Database error: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// CSV has no nesting, so metadata travels as a JSON string.
+#[derive(Debug, Serialize, Deserialize)]
+struct TaskCsvRow {
+    description: String,
+    points: i32,
+    task_button_text: String,
+    link: Option<String>,
+    task_type: Option<TaskType>,
+    category: Option<String>,
+    icon_url: Option<String>,
+    display_order: Option<i32>,
+    metadata: Option<String>,
+    status: Option<TaskStatus>,
+    starts_at: Option<DateTime<Utc>>,
+    ends_at: Option<DateTime<Utc>>,
+    recurrence: Option<TaskRecurrence>,
+    recurrence_schedule: Option<String>,
+    max_completions: Option<i32>,
+    points_budget: Option<i32>,
+}
+
+impl TryFrom<TaskCsvRow> for CreateTaskDTO {
+    type Error = String;
+
+    fn try_from(row: TaskCsvRow) -> Result<Self, Self::Error> {
+        let metadata = match row.metadata.as_deref().map(str::trim) {
+            None | Some("") => None,
+            Some(metadata) => Some(
+                serde_json::from_str(metadata).map_err(|err| format!("Invalid metadata: {err}"))?,
+            ),
+        };
+        Ok(CreateTaskDTO {
+            description: row.description,
+            points: row.points,
+            task_button_text: row.task_button_text,
+            link: row.link,
+            task_type: row.task_type,
+            category: row.category,
+            icon_url: row.icon_url,
+            display_order: row.display_order,
+            metadata,
+            status: row.status,
+            starts_at: row.starts_at,
+            ends_at: row.ends_at,
+            recurrence: row.recurrence,
+            recurrence_schedule: row.recurrence_schedule,
+            max_completions: row.max_completions,
+            points_budget: row.points_budget,
+        })
+    }
+}
+
+impl From<CreateTaskDTO> for TaskCsvRow {
+    fn from(task: CreateTaskDTO) -> Self {
+        TaskCsvRow {
+            description: task.description,
+            points: task.points,
+            task_button_text: task.task_button_text,
+            link: task.link,
+            task_type: task.task_type,
+            category: task.category,
+            icon_url: task.icon_url,
+            display_order: task.display_order,
+            metadata: task.metadata.map(|metadata| metadata.to_string()),
+            status: task.status,
+            starts_at: task.starts_at,
+            ends_at: task.ends_at,
+            recurrence: task.recurrence,
+            recurrence_schedule: task.recurrence_schedule,
+            max_completions: task.max_completions,
+            points_budget: task.points_budget,
+        }
+    }
+}
+
+impl From<Task> for CreateTaskDTO {
+    fn from(task: Task) -> Self {
+        CreateTaskDTO {
+            description: task.description,
+            points: task.points,
+            task_button_text: task.task_button_text.unwrap_or_default(),
+            link: task.link,
+            task_type: Some(task.task_type),
+            category: task.category,
+            icon_url: task.icon_url,
+            display_order: Some(task.display_order),
+            metadata: match task.metadata {
+                Value::Object(metadata) if metadata.is_empty() => None,
+                metadata => Some(metadata),
+            },
+            status: Some(task.status),
+            starts_at: task.starts_at,
+            ends_at: task.ends_at,
+            recurrence: Some(task.recurrence),
+            recurrence_schedule: task.recurrence_schedule,
+            max_completions: task.max_completions,
+            points_budget: task.points_budget,
+        }
+    }
+}
+
+/// Parses and validates every task, reporting all bad rows at once instead of
+/// stopping at the first. Each task comes with its row, the file line for CSV, where
+/// the header is line 1, and the position in the array for JSON.
+pub fn parse_tasks(
+    format: TaskFormat,
+    input: &str,
+) -> Result<Vec<(usize, CreateTaskDTO)>, ImportError> {
+    let rows: Vec<(usize, Result<CreateTaskDTO, String>)> = match format {
+        TaskFormat::Json => {
+            let values: Vec<Value> = serde_json::from_str(input).map_err(|err| {
+                ImportError::Invalid(vec![RowError {
+                    row: 0,
+                    error: format!("Expected a JSON array of tasks: {err}"),
+                }])
+            })?;
+            values
+                .into_iter()
+                .enumerate()
+                .map(|(index, value)| {
+                    (
+                        index + 1,
+                        serde_json::from_value(value).map_err(|err| err.to_string()),
+                    )
+                })
+                .collect()
+        }
+        TaskFormat::Csv => {
+            let mut reader = csv::Reader::from_reader(input.as_bytes());
+            let headers = reader
+                .headers()
+                .map_err(|err| {
+                    ImportError::Invalid(vec![RowError {
+                        row: 1,
+                        error: err.to_string(),
+                    }])
+                })?
+                .clone();
+            reader
+                .records()
+                .enumerate()
+                .map(|(index, record)| match record {
+                    Ok(record) => (
+                        record
+                            .position()
+                            .map_or(index + 2, |position| position.line() as usize),
+                        record
+                            .deserialize::<TaskCsvRow>(Some(&headers))
+                            .map_err(|err| err.to_string())
+                            .and_then(CreateTaskDTO::try_from),
+                    ),
+                    Err(err) => (index + 2, Err(err.to_string())),
+                })
+                .collect()
+        }
+    };
+
+    let mut tasks = vec![];
+    let mut errors = vec![];
+    for (row, task) in rows {
+        let task = task.and_then(|task| match task.validate() {
+            Ok(()) => Ok(task),
+            Err(validation_errors) => {
+                let fields: Vec<String> = validation_errors
+                    .0
+                    .iter()
+                    .map(|error| format!("{}: {}", error.field, error.message))
+                    .collect();
+                Err(fields.join(" "))
+            }
+        });
+        match task {
+            Ok(task) => tasks.push((row, task)),
+            Err(error) => errors.push(RowError { row, error }),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ImportError::Invalid(errors));
+    }
+    Ok(tasks)
+}
+
+/// Writes tasks in the same shape `parse_tasks` reads, ids and counters left out.
+pub fn export_tasks(format: TaskFormat, tasks: Vec<Task>) -> Result<String, String> {
+    let tasks = tasks.into_iter().map(CreateTaskDTO::from);
+    match format {
+        TaskFormat::Json => {
+            serde_json::to_string_pretty(&tasks.collect::<Vec<_>>()).map_err(|err| err.to_string())
+        }
+        TaskFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(vec![]);
+            for task in tasks {
+                writer
+                    .serialize(TaskCsvRow::from(task))
+                    .map_err(|err| err.to_string())?;
+            }
+            let bytes = writer.into_inner().map_err(|err| err.to_string())?;
+            String::from_utf8(bytes).map_err(|err| err.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verification::tests::task;
+
+    const CSV: &str = "description,points,task_button_text,task_type,metadata,recurrence
+Follow us,100,Follow,follow,,
+\"Daily, check in\",5,Check in,check_in,\"{\"\"streak_bonus\"\": 2}\",daily
+";
+
+    #[test]
+    fn test_parse_csv() {
+        let (rows, tasks): (Vec<usize>, Vec<CreateTaskDTO>) = parse_tasks(TaskFormat::Csv, CSV)
+            .unwrap()
+            .into_iter()
+            .unzip();
+        assert_eq!(rows, vec![2, 3]);
+        assert_eq!(tasks[0].task_type, Some(TaskType::Follow));
+        assert_eq!(tasks[0].metadata, None);
+        assert_eq!(tasks[0].link, None);
+        assert_eq!(tasks[1].description, "Daily, check in");
+        assert_eq!(
+            tasks[1].metadata,
+            Some(serde_json::json!({ "streak_bonus": 2 }))
+        );
+        assert_eq!(tasks[1].recurrence, Some(TaskRecurrence::Daily));
+    }
+
+    #[test]
+    fn test_parse_reports_every_bad_row() {
+        let csv = "description,points,task_button_text,recurrence
+Follow us,100,Follow,
+,5,Go,
+Like,ten,Like,
+Cron,5,Go,cron
+Bonus step,0,Go,
+Refund,-5,Go,
+";
+        let Err(ImportError::Invalid(errors)) = parse_tasks(TaskFormat::Csv, csv) else {
+            panic!("bad rows were accepted");
+        };
+        let rows: Vec<usize> = errors.iter().map(|error| error.row).collect();
+        assert_eq!(rows, vec![3, 4, 5, 6, 7]);
+
+        let json =
+            r#"[{"description": "Follow", "points": 1, "task_button_text": "Go"}, {"points": 1}]"#;
+        let Err(ImportError::Invalid(errors)) = parse_tasks(TaskFormat::Json, json) else {
+            panic!("bad rows were accepted");
+        };
+        assert_eq!(errors[0].row, 2);
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let mut quiz = task(TaskType::Quiz, serde_json::json!({ "answer": "frog" }));
+        quiz.description = "Quiz, with a comma".to_string();
+        let tasks = vec![task(TaskType::Follow, serde_json::json!({})), quiz];
+
+        for format in [TaskFormat::Json, TaskFormat::Csv] {
+            let exported = export_tasks(format, tasks.clone()).unwrap();
+            let imported: Vec<CreateTaskDTO> = parse_tasks(format, &exported)
+                .unwrap()
+                .into_iter()
+                .map(|(_, task)| task)
+                .collect();
+            assert_eq!(imported.len(), 2);
+            assert_eq!(imported[0].metadata, None);
+            assert_eq!(imported[1].description, "Quiz, with a comma");
+            assert_eq!(
+                imported[1].metadata,
+                Some(serde_json::json!({ "answer": "frog" }))
+            );
+            assert_eq!(imported[1].task_type, Some(TaskType::Quiz));
+        }
+    }
+}
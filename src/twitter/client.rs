@@ -0,0 +1,475 @@
+use std::{
+    collections::HashMap,
+    env,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::{header::HeaderMap, StatusCode};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::constants::{TWITTER_CACHE_TTL, TWITTER_MAX_PAGES, TWITTER_RATE_LIMIT_BACKOFF};
+
+const DEFAULT_BASE_URL: &str = "https://api.twitter.com";
+
+#[derive(Debug)]
+pub enum TwitterError {
+    Request { error: String },
+    Status { status: u16 },
+    RateLimited { retry_after: Duration },
+    NotFound,
+}
+
+impl std::fmt::Display for TwitterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Request { error } => write!(f, "Twitter request failed: {error}"),
+            Self::Status { status } => write!(f, "Twitter responded with status {status}"),
+            Self::RateLimited { retry_after } => write!(
+                f,
+                "Twitter rate limit reached, retry in {} seconds",
+                retry_after.as_secs()
+            ),
+            Self::NotFound => write!(f, "Not found on Twitter."),
+        }
+    }
+}
+
+impl std::error::Error for TwitterError {}
+
+impl From<reqwest::Error> for TwitterError {
+    fn from(value: reqwest::Error) -> Self {
+        Self::Request {
+            error: value.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct ReferencedTweet {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct Tweet {
+    pub id: String,
+    pub author_id: Option<String>,
+    #[serde(default)]
+    pub referenced_tweets: Vec<ReferencedTweet>,
+}
+
+impl Tweet {
+    /// Whether this tweet replies to (`replied_to`) or quotes (`quoted`) `tweet_id`.
+    pub fn references(&self, kind: &str, tweet_id: &str) -> bool {
+        self.referenced_tweets
+            .iter()
+            .any(|referenced| referenced.kind == kind && referenced.id == tweet_id)
+    }
+}
+
+/// Read-only X API v2 client authenticated with an app bearer token.
+///
+/// Responses are cached for `TWITTER_CACHE_TTL`. User lists are only remembered for
+/// the users found in them, so someone who follows or likes right after a failed check
+/// isn't turned away until the cache expires. Once an endpoint is rate limited, further
+/// calls to it fail fast until the window reported by X resets.
+#[derive(Clone)]
+pub struct TwitterClient {
+    http: reqwest::Client,
+    base_url: String,
+    bearer_token: String,
+    cache: Arc<Mutex<HashMap<String, (Instant, Value)>>>,
+    blocked_until: Arc<Mutex<HashMap<&'static str, Instant>>>,
+}
+
+impl TwitterClient {
+    pub fn new(http: reqwest::Client, base_url: String, bearer_token: String) -> Self {
+        Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            bearer_token,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+            blocked_until: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Reads `TWITTER_BEARER_TOKEN` and `TWITTER_API_BASE_URL`. Without a token there
+    /// is no Twitter integration and `None` is returned.
+    pub fn from_env(http: reqwest::Client) -> Option<Self> {
+        let bearer_token = env::var("TWITTER_BEARER_TOKEN").ok()?;
+        let base_url =
+            env::var("TWITTER_API_BASE_URL").unwrap_or_else(|_| DEFAULT_BASE_URL.to_string());
+        Some(Self::new(http, base_url, bearer_token))
+    }
+
+    /// Turns a handle or numeric id into a numeric user id, anything that isn't numeric
+    /// is looked up as a username.
+    pub async fn resolve_user_id(&self, username: &str) -> Result<String, TwitterError> {
+        let username = username.trim().trim_start_matches('@');
+        if !username.is_empty() && username.chars().all(|c| c.is_ascii_digit()) {
+            return Ok(username.to_string());
+        }
+
+        let response = self
+            .get(
+                "users_by_username",
+                &format!("/2/users/by/username/{username}"),
+                &[],
+            )
+            .await?;
+        response["data"]["id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or(TwitterError::NotFound)
+    }
+
+    pub async fn is_following(
+        &self,
+        user_id: &str,
+        target_user_id: &str,
+    ) -> Result<bool, TwitterError> {
+        self.list_contains(
+            "following",
+            &format!("/2/users/{user_id}/following"),
+            1000,
+            target_user_id,
+        )
+        .await
+    }
+
+    pub async fn has_liked(&self, user_id: &str, tweet_id: &str) -> Result<bool, TwitterError> {
+        self.list_contains(
+            "liking_users",
+            &format!("/2/tweets/{tweet_id}/liking_users"),
+            100,
+            user_id,
+        )
+        .await
+    }
+
+    pub async fn has_retweeted(&self, user_id: &str, tweet_id: &str) -> Result<bool, TwitterError> {
+        self.list_contains(
+            "retweeted_by",
+            &format!("/2/tweets/{tweet_id}/retweeted_by"),
+            100,
+            user_id,
+        )
+        .await
+    }
+
+    pub async fn get_tweet(&self, tweet_id: &str) -> Result<Tweet, TwitterError> {
+        let response = self
+            .get(
+                "tweets",
+                &format!("/2/tweets/{tweet_id}"),
+                &[("tweet.fields", "author_id,referenced_tweets".to_string())],
+            )
+            .await?;
+        match response.get("data") {
+            Some(data) => {
+                serde_json::from_value(data.clone()).map_err(|err| TwitterError::Request {
+                    error: err.to_string(),
+                })
+            }
+            None => Err(TwitterError::NotFound),
+        }
+    }
+
+    /// Pages through a list of users looking for `id`, giving up after
+    /// `TWITTER_MAX_PAGES` pages.
+    async fn list_contains(
+        &self,
+        endpoint: &'static str,
+        path: &str,
+        max_results: u32,
+        id: &str,
+    ) -> Result<bool, TwitterError> {
+        let cache_key = format!("{}{path}#{id}", self.base_url);
+        if self.cached(&cache_key).is_some() {
+            return Ok(true);
+        }
+
+        let mut pagination_token: Option<String> = None;
+        for _ in 0..TWITTER_MAX_PAGES {
+            let mut query = vec![("max_results", max_results.to_string())];
+            if let Some(token) = pagination_token.take() {
+                query.push(("pagination_token", token));
+            }
+
+            let response = self.fetch(endpoint, path, &query).await?;
+            let found = response["data"]
+                .as_array()
+                .is_some_and(|users| users.iter().any(|user| user["id"].as_str() == Some(id)));
+            if found {
+                self.cache(cache_key, Value::Bool(true));
+                return Ok(true);
+            }
+
+            match response["meta"]["next_token"].as_str() {
+                Some(token) => pagination_token = Some(token.to_string()),
+                None => return Ok(false),
+            }
+        }
+        Ok(false)
+    }
+
+    fn cached(&self, cache_key: &str) -> Option<Value> {
+        match self.cache.lock().unwrap().get(cache_key) {
+            Some((expires_at, value)) if *expires_at > Instant::now() => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    /// Stores `value` and drops whatever has expired, so the cache doesn't keep growing
+    /// with lookups that are never repeated.
+    fn cache(&self, cache_key: String, value: Value) {
+        let now = Instant::now();
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (expires_at, _)| *expires_at > now);
+        cache.insert(cache_key, (now + TWITTER_CACHE_TTL, value));
+    }
+
+    async fn get(
+        &self,
+        endpoint: &'static str,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<Value, TwitterError> {
+        let cache_key = format!("{}{path}?{query:?}", self.base_url);
+        if let Some(value) = self.cached(&cache_key) {
+            return Ok(value);
+        }
+        let value = self.fetch(endpoint, path, query).await?;
+        self.cache(cache_key, value.clone());
+        Ok(value)
+    }
+
+    async fn fetch(
+        &self,
+        endpoint: &'static str,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<Value, TwitterError> {
+        let url = format!("{}{path}", self.base_url);
+        let now = Instant::now();
+        if let Some(blocked_until) = self.blocked_until.lock().unwrap().get(endpoint) {
+            if *blocked_until > now {
+                return Err(TwitterError::RateLimited {
+                    retry_after: *blocked_until - now,
+                });
+            }
+        }
+
+        let response = self
+            .http
+            .get(&url)
+            .bearer_auth(&self.bearer_token)
+            .query(query)
+            .send()
+            .await?;
+
+        let status = response.status();
+        let reset_in = rate_limit_reset_in(response.headers());
+        if status == StatusCode::TOO_MANY_REQUESTS || reset_in.is_some() {
+            let retry_after = reset_in.unwrap_or(TWITTER_RATE_LIMIT_BACKOFF);
+            self.blocked_until
+                .lock()
+                .unwrap()
+                .insert(endpoint, Instant::now() + retry_after);
+            if status == StatusCode::TOO_MANY_REQUESTS {
+                return Err(TwitterError::RateLimited { retry_after });
+            }
+        }
+        if status == StatusCode::NOT_FOUND {
+            return Err(TwitterError::NotFound);
+        }
+        if !status.is_success() {
+            return Err(TwitterError::Status {
+                status: status.as_u16(),
+            });
+        }
+
+        Ok(response.json().await?)
+    }
+}
+
+/// Time until the rate limit window resets, only set once the window is used up.
+fn rate_limit_reset_in(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+    };
+
+    if header("x-rate-limit-remaining")? > 0 {
+        return None;
+    }
+    let reset_at = header("x-rate-limit-reset")?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    Some(Duration::from_secs(reset_at.saturating_sub(now).max(1)))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use axum::{
+        extract::{Path, Query},
+        http::{HeaderMap as AxumHeaderMap, StatusCode as AxumStatusCode},
+        response::IntoResponse,
+        routing::get,
+        Json, Router,
+    };
+    use serde_json::json;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Local stand-in for the X API. User 1 (`frog`) follows user 2, likes and
+    /// retweets tweet 10 and wrote tweet 11, which replies to tweet 10. The liking
+    /// users endpoint is always rate limited.
+    pub async fn stub_server(calls: Arc<AtomicUsize>) -> String {
+        let count = move || {
+            calls.fetch_add(1, Ordering::SeqCst);
+        };
+
+        let app = Router::new()
+            .route(
+                "/2/users/by/username/:username",
+                get(|Path(username): Path<String>| async move {
+                    match username.as_str() {
+                        "frog" => Json(json!({ "data": { "id": "1", "username": "frog" } })),
+                        _ => Json(json!({ "errors": [{ "title": "Not Found Error" }] })),
+                    }
+                }),
+            )
+            .route(
+                "/2/users/:id/following",
+                get(
+                    |Path(id): Path<String>, Query(query): Query<HashMap<String, String>>| async move {
+                        match (id.as_str(), query.get("pagination_token").map(String::as_str)) {
+                            ("1", None) => Json(json!({
+                                "data": [{ "id": "3" }],
+                                "meta": { "next_token": "page2" }
+                            })),
+                            ("1", Some("page2")) => Json(json!({ "data": [{ "id": "2" }] })),
+                            _ => Json(json!({ "meta": { "result_count": 0 } })),
+                        }
+                    },
+                ),
+            )
+            .route(
+                "/2/tweets/:id/retweeted_by",
+                get(move |auth: AxumHeaderMap| async move {
+                    count();
+                    assert_eq!(auth["authorization"], "Bearer token");
+                    Json(json!({ "data": [{ "id": "1" }] }))
+                }),
+            )
+            .route(
+                "/2/tweets/:id/liking_users",
+                get(|| async {
+                    (
+                        AxumStatusCode::TOO_MANY_REQUESTS,
+                        [("x-rate-limit-remaining", "0"), ("x-rate-limit-reset", "9999999999")],
+                    )
+                        .into_response()
+                }),
+            )
+            .route(
+                "/2/tweets/:id",
+                get(|Path(id): Path<String>| async move {
+                    match id.as_str() {
+                        "11" => Json(json!({
+                            "data": {
+                                "id": "11",
+                                "author_id": "1",
+                                "referenced_tweets": [{ "type": "replied_to", "id": "10" }]
+                            }
+                        }))
+                        .into_response(),
+                        _ => AxumStatusCode::NOT_FOUND.into_response(),
+                    }
+                }),
+            );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{address}")
+    }
+
+    pub async fn stub_client() -> (TwitterClient, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let base_url = stub_server(calls.clone()).await;
+        let client = TwitterClient::new(reqwest::Client::new(), base_url, "token".to_string());
+        (client, calls)
+    }
+
+    #[tokio::test]
+    async fn test_resolve_user_id() {
+        let (client, _) = stub_client().await;
+        assert_eq!(client.resolve_user_id("@frog").await.unwrap(), "1");
+        assert_eq!(client.resolve_user_id("42").await.unwrap(), "42");
+        assert!(matches!(
+            client.resolve_user_id("toad").await,
+            Err(TwitterError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_is_following_pages_through_results() {
+        let (client, _) = stub_client().await;
+        assert!(client.is_following("1", "2").await.unwrap());
+        assert!(!client.is_following("1", "4").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_responses_are_cached() {
+        let (client, calls) = stub_client().await;
+        for _ in 0..3 {
+            assert!(client.has_retweeted("1", "10").await.unwrap());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        // Misses are asked again, the user may have retweeted since.
+        for _ in 0..2 {
+            assert!(!client.has_retweeted("5", "10").await.unwrap());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_endpoint_fails_fast() {
+        let (client, _) = stub_client().await;
+        for _ in 0..2 {
+            assert!(matches!(
+                client.has_liked("1", "10").await,
+                Err(TwitterError::RateLimited { .. })
+            ));
+        }
+        // Other endpoints have their own windows.
+        assert!(client.has_retweeted("1", "10").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_get_tweet() {
+        let (client, _) = stub_client().await;
+        let tweet = client.get_tweet("11").await.unwrap();
+        assert_eq!(tweet.author_id.as_deref(), Some("1"));
+        assert!(tweet.references("replied_to", "10"));
+        assert!(!tweet.references("quoted", "10"));
+        assert!(matches!(
+            client.get_tweet("12").await,
+            Err(TwitterError::NotFound)
+        ));
+    }
+}
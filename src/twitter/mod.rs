@@ -0,0 +1,5 @@
+mod client;
+mod oauth;
+
+pub use client::*;
+pub use oauth::*;
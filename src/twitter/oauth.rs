@@ -0,0 +1,288 @@
+use std::env;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::{distributions::Alphanumeric, Rng};
+use reqwest::Url;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use super::TwitterError;
+
+const DEFAULT_AUTHORIZE_URL: &str = "https://twitter.com/i/oauth2/authorize";
+const DEFAULT_TOKEN_URL: &str = "https://api.twitter.com/2/oauth2/token";
+const DEFAULT_USER_INFO_URL: &str = "https://api.twitter.com/2/users/me";
+const SCOPES: &str = "users.read tweet.read";
+
+/// The X account behind an access token.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct TwitterAccount {
+    /// Numeric id, stays the same when the handle changes.
+    pub id: String,
+    /// Handle at the time of linking, only for display.
+    pub username: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+struct UserInfoResponse {
+    data: TwitterAccount,
+}
+
+/// PKCE verifier and the S256 challenge sent along with the authorization request.
+pub struct Pkce {
+    pub code_verifier: String,
+    pub code_challenge: String,
+}
+
+impl Pkce {
+    pub fn generate() -> Self {
+        let code_verifier: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(64)
+            .map(char::from)
+            .collect();
+        let code_challenge = code_challenge(&code_verifier);
+        Self {
+            code_verifier,
+            code_challenge,
+        }
+    }
+}
+
+pub fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+/// OAuth 2.0 authorization code flow with PKCE, used to link an X account.
+#[derive(Clone)]
+pub struct TwitterOAuth {
+    http: reqwest::Client,
+    client_id: String,
+    client_secret: Option<String>,
+    redirect_uri: String,
+    authorize_url: String,
+    token_url: String,
+    user_info_url: String,
+}
+
+impl TwitterOAuth {
+    pub fn new(
+        http: reqwest::Client,
+        client_id: String,
+        client_secret: Option<String>,
+        redirect_uri: String,
+    ) -> Self {
+        Self {
+            http,
+            client_id,
+            client_secret,
+            redirect_uri,
+            authorize_url: DEFAULT_AUTHORIZE_URL.to_string(),
+            token_url: DEFAULT_TOKEN_URL.to_string(),
+            user_info_url: DEFAULT_USER_INFO_URL.to_string(),
+        }
+    }
+
+    /// Points the flow at another provider, e.g. a local fake one.
+    pub fn with_endpoints(
+        mut self,
+        authorize_url: String,
+        token_url: String,
+        user_info_url: String,
+    ) -> Self {
+        self.authorize_url = authorize_url;
+        self.token_url = token_url;
+        self.user_info_url = user_info_url;
+        self
+    }
+
+    /// Reads `TWITTER_OAUTH_CLIENT_ID`, `TWITTER_OAUTH_CLIENT_SECRET` and
+    /// `TWITTER_OAUTH_REDIRECT_URI`, and optionally `TWITTER_OAUTH_AUTHORIZE_URL`,
+    /// `TWITTER_OAUTH_TOKEN_URL` and `TWITTER_OAUTH_USER_INFO_URL`. Without a client
+    /// id and redirect uri account linking is disabled.
+    pub fn from_env(http: reqwest::Client) -> Option<Self> {
+        let client_id = env::var("TWITTER_OAUTH_CLIENT_ID").ok()?;
+        let redirect_uri = env::var("TWITTER_OAUTH_REDIRECT_URI").ok()?;
+        let client_secret = env::var("TWITTER_OAUTH_CLIENT_SECRET").ok();
+
+        let var_or = |name: &str, default: &str| env::var(name).unwrap_or(default.to_string());
+        Some(
+            Self::new(http, client_id, client_secret, redirect_uri).with_endpoints(
+                var_or("TWITTER_OAUTH_AUTHORIZE_URL", DEFAULT_AUTHORIZE_URL),
+                var_or("TWITTER_OAUTH_TOKEN_URL", DEFAULT_TOKEN_URL),
+                var_or("TWITTER_OAUTH_USER_INFO_URL", DEFAULT_USER_INFO_URL),
+            ),
+        )
+    }
+
+    /// URL to send the user to, X redirects back with `code` and `state`.
+    pub fn authorize_url(&self, state: &str, code_challenge: &str) -> Result<Url, TwitterError> {
+        Url::parse_with_params(
+            &self.authorize_url,
+            [
+                ("response_type", "code"),
+                ("client_id", self.client_id.as_str()),
+                ("redirect_uri", self.redirect_uri.as_str()),
+                ("scope", SCOPES),
+                ("state", state),
+                ("code_challenge", code_challenge),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|err| TwitterError::Request {
+            error: err.to_string(),
+        })
+    }
+
+    /// Trades the authorization code for an access token and looks up whose it is.
+    pub async fn fetch_account(
+        &self,
+        code: &str,
+        code_verifier: &str,
+    ) -> Result<TwitterAccount, TwitterError> {
+        let mut request = self.http.post(&self.token_url).form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.redirect_uri.as_str()),
+            ("code_verifier", code_verifier),
+            ("client_id", self.client_id.as_str()),
+        ]);
+        // Confidential clients authenticate, public ones only send their id.
+        if let Some(client_secret) = &self.client_secret {
+            request = request.basic_auth(&self.client_id, Some(client_secret));
+        }
+
+        let token: TokenResponse = request.send().await?.error_for_status()?.json().await?;
+
+        let user_info: UserInfoResponse = self
+            .http
+            .get(&self.user_info_url)
+            .bearer_auth(token.access_token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(user_info.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        http::{HeaderMap, StatusCode},
+        response::IntoResponse,
+        routing::{get, post},
+        Form, Json, Router,
+    };
+    use serde_json::json;
+    use std::collections::HashMap;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Fake provider issuing a token for code `good` if the verifier matches the
+    /// challenge the test used.
+    async fn fake_provider(expected_challenge: String) -> String {
+        let app = Router::new()
+            .route(
+                "/oauth2/token",
+                post(|Form(form): Form<HashMap<String, String>>| async move {
+                    let verifier = form.get("code_verifier").cloned().unwrap_or_default();
+                    if form.get("code").map(String::as_str) == Some("good")
+                        && code_challenge(&verifier) == expected_challenge
+                    {
+                        Json(json!({ "access_token": "access", "token_type": "bearer" }))
+                            .into_response()
+                    } else {
+                        StatusCode::BAD_REQUEST.into_response()
+                    }
+                }),
+            )
+            .route(
+                "/2/users/me",
+                get(|headers: HeaderMap| async move {
+                    match headers["authorization"].to_str() {
+                        Ok("Bearer access") => Json(json!({
+                            "data": { "id": "1234", "username": "frog", "name": "Frog" }
+                        }))
+                        .into_response(),
+                        _ => StatusCode::UNAUTHORIZED.into_response(),
+                    }
+                }),
+            );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{address}")
+    }
+
+    fn oauth(base_url: &str) -> TwitterOAuth {
+        TwitterOAuth::new(
+            reqwest::Client::new(),
+            "client".to_string(),
+            None,
+            "https://farm.example/twitter/callback".to_string(),
+        )
+        .with_endpoints(
+            format!("{base_url}/authorize"),
+            format!("{base_url}/oauth2/token"),
+            format!("{base_url}/2/users/me"),
+        )
+    }
+
+    #[test]
+    fn test_code_challenge_matches_rfc_example() {
+        assert_eq!(
+            code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn test_authorize_url() {
+        let url = oauth("http://localhost")
+            .authorize_url("state", "challenge")
+            .unwrap();
+        let params: HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(params["client_id"], "client");
+        assert_eq!(params["state"], "state");
+        assert_eq!(params["code_challenge"], "challenge");
+        assert_eq!(params["code_challenge_method"], "S256");
+        assert_eq!(
+            params["redirect_uri"],
+            "https://farm.example/twitter/callback"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_account() {
+        let pkce = Pkce::generate();
+        let oauth = oauth(&fake_provider(pkce.code_challenge.clone()).await);
+
+        let account = oauth
+            .fetch_account("good", &pkce.code_verifier)
+            .await
+            .unwrap();
+        assert_eq!(
+            account,
+            TwitterAccount {
+                id: "1234".to_string(),
+                username: "frog".to_string()
+            }
+        );
+
+        assert!(oauth.fetch_account("good", "wrong").await.is_err());
+        assert!(oauth
+            .fetch_account("bad", &pkce.code_verifier)
+            .await
+            .is_err());
+    }
+}
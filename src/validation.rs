@@ -0,0 +1,165 @@
+use axum::{
+    async_trait,
+    extract::{FromRequest, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use reqwest::Url;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::json;
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Every problem found in a request body, answered with 422.
+#[derive(Debug, Default, Serialize, Clone, PartialEq, Eq)]
+pub struct ValidationErrors(pub Vec<FieldError>);
+
+impl ValidationErrors {
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    pub fn check(&mut self, field: &str, valid: bool, message: impl Into<String>) {
+        if !valid {
+            self.add(field, message);
+        }
+    }
+
+    pub fn max_length(&mut self, field: &str, value: &str, max: usize) {
+        self.check(
+            field,
+            value.chars().count() <= max,
+            format!("Must be at most {max} characters."),
+        );
+    }
+
+    /// Absolute http(s) URL that fits the column.
+    pub fn url(&mut self, field: &str, value: &str, max: usize) {
+        let valid = Url::parse(value).is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+        self.check(field, valid, "Must be an http or https URL.");
+        self.max_length(field, value, max);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn into_result(self) -> Result<(), ValidationErrors> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        Err(self)
+    }
+}
+
+impl IntoResponse for ValidationErrors {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({
+                "error": "Validation failed.",
+                "fields": self.0
+            })),
+        )
+            .into_response()
+    }
+}
+
+/// Rules a request body has to follow before a handler sees it.
+pub trait Validate {
+    fn validate(&self) -> Result<(), ValidationErrors>;
+}
+
+/// `Json` that also runs the body's `Validate` rules, answering 422 with every
+/// failed field.
+pub struct ValidatedJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        value.validate().map_err(IntoResponse::into_response)?;
+        Ok(ValidatedJson(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{routing::post, Router};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::models::SetMultiplierDTO;
+
+    #[test]
+    fn test_length_counts_characters() {
+        let mut errors = ValidationErrors::default();
+        errors.max_length("description", &"🐸".repeat(255), 255);
+        assert!(errors.is_empty());
+        errors.max_length("description", &"🐸".repeat(256), 255);
+        assert_eq!(errors.0.len(), 1);
+    }
+
+    #[test]
+    fn test_url() {
+        let mut errors = ValidationErrors::default();
+        errors.url("link", "https://x.com/frog", 120);
+        assert!(errors.is_empty());
+        errors.url("link", "javascript:alert(1)", 120);
+        errors.url("link", "x.com/frog", 120);
+        assert_eq!(errors.0.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_validated_json_rejects_with_fields() {
+        let app = Router::new().route(
+            "/",
+            post(
+                |ValidatedJson(dto): ValidatedJson<SetMultiplierDTO>| async move {
+                    dto.multiplier.to_string()
+                },
+            ),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let client = reqwest::Client::new();
+        let response = client
+            .post(&url)
+            .json(&json!({ "twitter_id": "", "multiplier": 0 }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 422);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["fields"][0]["field"], "twitter_id");
+        assert_eq!(body["fields"][1]["field"], "multiplier");
+
+        let response = client
+            .post(&url)
+            .json(&json!({ "twitter_id": "frog", "multiplier": 3 }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.text().await.unwrap(), "3");
+    }
+}
@@ -0,0 +1,73 @@
+use axum::async_trait;
+use serde_json::Value;
+
+use crate::models::{Task, TaskProof, User};
+
+use super::{normalize, TaskVerifier, Verdict, VerificationError};
+
+/// Accepts the code word from the task metadata, e.g. one announced in a Discord
+/// channel or a livestream.
+pub struct CodeWordVerifier;
+
+#[async_trait]
+impl TaskVerifier for CodeWordVerifier {
+    fn name(&self) -> &'static str {
+        "code_word"
+    }
+
+    async fn verify(
+        &self,
+        task: &Task,
+        _user: &User,
+        proof: &TaskProof,
+    ) -> Result<Verdict, VerificationError> {
+        let code_word = task
+            .metadata
+            .get("code_word")
+            .and_then(Value::as_str)
+            .ok_or_else(|| VerificationError::Misconfigured {
+                error: "missing code_word".to_string(),
+            })?;
+
+        match &proof.code {
+            Some(code) if normalize(code) == normalize(code_word) => {
+                Ok(Verdict::verified(Value::Null))
+            }
+            Some(_) => Ok(Verdict::rejected("Wrong code word.")),
+            None => Ok(Verdict::rejected("A code word is required.")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        models::TaskType,
+        verification::tests::{task, user},
+    };
+
+    #[tokio::test]
+    async fn test_code_word() {
+        let task = task(TaskType::Custom, json!({ "code_word": "Ribbit" }));
+        let verify = |code: Option<&str>| {
+            let proof = TaskProof {
+                code: code.map(str::to_string),
+                ..Default::default()
+            };
+            let task = task.clone();
+            async move {
+                CodeWordVerifier
+                    .verify(&task, &user(), &proof)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        assert!(verify(Some(" ribbit ")).await.verified);
+        assert!(!verify(Some("croak")).await.verified);
+        assert!(!verify(None).await.verified);
+    }
+}
@@ -0,0 +1,136 @@
+use axum::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::models::{Task, TaskProof, User};
+
+use super::{TaskVerifier, Verdict, VerificationError};
+
+/// Asks an external service whether the proof holds. The service gets the task, the
+/// user and the proof as JSON and answers with `{"verified": bool, "reason": ...}`.
+pub struct HttpVerifier {
+    client: reqwest::Client,
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct HttpVerdict {
+    verified: bool,
+    reason: Option<String>,
+    #[serde(default)]
+    details: Value,
+}
+
+impl HttpVerifier {
+    pub fn new(client: reqwest::Client, url: String) -> Self {
+        Self { client, url }
+    }
+}
+
+#[async_trait]
+impl TaskVerifier for HttpVerifier {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    async fn verify(
+        &self,
+        task: &Task,
+        user: &User,
+        proof: &TaskProof,
+    ) -> Result<Verdict, VerificationError> {
+        let unavailable = |err: reqwest::Error| VerificationError::Unavailable {
+            error: err.to_string(),
+        };
+
+        let response = self
+            .client
+            .post(&self.url)
+            .json(&json!({
+                "task_id": task.id,
+                "task_type": task.task_type,
+                "user_id": user.id,
+                "twitter_id": user.twitter_id,
+                "wallet_address": user.wallet_address,
+                "proof": proof
+            }))
+            .send()
+            .await
+            .map_err(unavailable)?
+            .error_for_status()
+            .map_err(unavailable)?;
+
+        let verdict: HttpVerdict = response.json().await.map_err(unavailable)?;
+        Ok(Verdict {
+            verified: verdict.verified,
+            reason: verdict.reason,
+            details: verdict.details,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{routing::post, Json, Router};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::{
+        models::TaskType,
+        verification::tests::{task, user},
+    };
+
+    /// Mock verifier accepting the code "ok" and failing on "boom".
+    async fn mock_verifier() -> String {
+        async fn verify(Json(body): Json<Value>) -> axum::response::Response {
+            use axum::{http::StatusCode, response::IntoResponse};
+
+            match body["proof"]["code"].as_str() {
+                Some("ok") => Json(json!({
+                    "verified": true,
+                    "details": { "user_id": body["user_id"] }
+                }))
+                .into_response(),
+                Some("boom") => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+                _ => Json(json!({ "verified": false, "reason": "Nope." })).into_response(),
+            }
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, Router::new().route("/verify", post(verify)))
+                .await
+                .unwrap();
+        });
+        format!("http://{address}/verify")
+    }
+
+    fn proof(code: &str) -> TaskProof {
+        TaskProof {
+            code: Some(code.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_verifier() {
+        let verifier = HttpVerifier::new(reqwest::Client::new(), mock_verifier().await);
+        let task = task(TaskType::Follow, json!({}));
+        let user = user();
+
+        let verdict = verifier.verify(&task, &user, &proof("ok")).await.unwrap();
+        assert!(verdict.verified);
+        assert_eq!(verdict.details, json!({ "user_id": 7 }));
+
+        let verdict = verifier.verify(&task, &user, &proof("nope")).await.unwrap();
+        assert!(!verdict.verified);
+        assert_eq!(verdict.reason.as_deref(), Some("Nope."));
+
+        let verdict = verifier.verify(&task, &user, &proof("boom")).await;
+        assert!(matches!(
+            verdict,
+            Err(VerificationError::Unavailable { .. })
+        ));
+    }
+}
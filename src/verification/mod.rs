@@ -0,0 +1,247 @@
+mod code_word;
+mod http;
+mod quiz;
+mod trust;
+mod tweet;
+mod twitter;
+
+pub use code_word::*;
+pub use http::*;
+pub use quiz::*;
+pub use trust::*;
+pub use tweet::*;
+pub use twitter::*;
+
+use axum::async_trait;
+use serde_json::Value;
+
+use crate::{
+    models::{Task, TaskProof, TaskType, User},
+    twitter::TwitterClient,
+};
+
+/// Outcome of checking a user's proof, stored whether it passed or not.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verdict {
+    pub verified: bool,
+    pub reason: Option<String>,
+    /// Whatever the verifier learned along the way, e.g. the parsed tweet id.
+    pub details: Value,
+}
+
+impl Verdict {
+    pub fn verified(details: Value) -> Self {
+        Self {
+            verified: true,
+            reason: None,
+            details,
+        }
+    }
+
+    pub fn rejected(reason: impl Into<String>) -> Self {
+        Self {
+            verified: false,
+            reason: Some(reason.into()),
+            details: Value::Null,
+        }
+    }
+}
+
+/// The verifier couldn't reach a verdict, as opposed to rejecting the proof.
+#[derive(Debug)]
+pub enum VerificationError {
+    Misconfigured { error: String },
+    Unavailable { error: String },
+}
+
+impl std::fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Misconfigured { error } => write!(f, "Task verifier is misconfigured: {error}"),
+            Self::Unavailable { error } => write!(f, "Task verifier is unavailable: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for VerificationError {}
+
+#[async_trait]
+pub trait TaskVerifier: Send + Sync {
+    /// Name stored along with the verification result.
+    fn name(&self) -> &'static str;
+
+    async fn verify(
+        &self,
+        task: &Task,
+        user: &User,
+        proof: &TaskProof,
+    ) -> Result<Verdict, VerificationError>;
+}
+
+/// Everything verifiers need from the outside, kept in the app state.
+#[derive(Clone)]
+pub struct Verifiers {
+    http_client: reqwest::Client,
+    /// Without it Twitter tasks fall back to checking the submitted tweet URL, or
+    /// to trusting the user.
+    twitter: Option<TwitterClient>,
+}
+
+impl Verifiers {
+    pub fn new(http_client: reqwest::Client, twitter: Option<TwitterClient>) -> Self {
+        Self {
+            http_client,
+            twitter,
+        }
+    }
+
+    /// Picks the verifier for a task. A `verifier_url` in the task metadata hands the
+    /// decision to an external service, otherwise the task type decides.
+    pub fn for_task(&self, task: &Task) -> Box<dyn TaskVerifier> {
+        if let Some(url) = task.metadata.get("verifier_url").and_then(Value::as_str) {
+            return Box::new(HttpVerifier::new(self.http_client.clone(), url.to_string()));
+        }
+
+        match (task.task_type, &self.twitter) {
+            (
+                TaskType::Follow
+                | TaskType::Like
+                | TaskType::Retweet
+                | TaskType::Reply
+                | TaskType::QuoteTweet,
+                Some(twitter),
+            ) => Box::new(TwitterVerifier::new(twitter.clone())),
+            (TaskType::Retweet | TaskType::Reply | TaskType::QuoteTweet, None) => {
+                Box::new(TweetUrlVerifier)
+            }
+            (TaskType::Quiz, _) => Box::new(QuizVerifier),
+            (TaskType::Custom, _) if task.metadata.get("code_word").is_some() => {
+                Box::new(CodeWordVerifier)
+            }
+            (
+                TaskType::Follow
+                | TaskType::Like
+                | TaskType::JoinDiscord
+                | TaskType::VisitLink
+                | TaskType::Custom
+                | TaskType::CheckIn,
+                _,
+            ) => Box::new(TrustVerifier),
+        }
+    }
+}
+
+/// Case and whitespace insensitive comparison used for answers and code words.
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::{TaskRecurrence, TaskStatus};
+
+    pub fn task(task_type: TaskType, metadata: Value) -> Task {
+        Task {
+            id: 1,
+            description: "task".to_string(),
+            points: 10,
+            link: None,
+            task_button_text: None,
+            task_type,
+            category: None,
+            icon_url: None,
+            display_order: 0,
+            metadata,
+            status: TaskStatus::Active,
+            starts_at: None,
+            ends_at: None,
+            recurrence: TaskRecurrence::None,
+            recurrence_schedule: None,
+            max_completions: None,
+            points_budget: None,
+            completions_count: 0,
+            points_spent: 0,
+            archived_at: None,
+            version: 1,
+        }
+    }
+
+    pub fn user() -> User {
+        User {
+            id: 7,
+            wallet_address: "wallet".to_string(),
+            wallet_address_invalid: true,
+            twitter_id: "frog".to_string(),
+            referral_code: "code".to_string(),
+            total_points: 0,
+            finished_tasks: vec![],
+            referral_points: 0,
+            referred_by: vec![],
+            referrer_id: None,
+            multiplier: 1,
+            current_streak: 0,
+            longest_streak: 0,
+            last_check_in: None,
+            streak_freezes: 0,
+            twitter_user_id: Some("1".to_string()),
+            twitter_handle: Some("frog".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_verifier_is_selected_by_task_type() {
+        let verifiers = Verifiers::new(reqwest::Client::new(), None);
+        let name = |task_type, metadata| verifiers.for_task(&task(task_type, metadata)).name();
+
+        assert_eq!(name(TaskType::Retweet, json!({})), "tweet_url");
+        assert_eq!(name(TaskType::Like, json!({})), "trust");
+        assert_eq!(name(TaskType::Quiz, json!({})), "quiz");
+        assert_eq!(
+            name(TaskType::Custom, json!({ "code_word": "ribbit" })),
+            "code_word"
+        );
+        assert_eq!(name(TaskType::Custom, json!({})), "trust");
+        assert_eq!(
+            name(
+                TaskType::Follow,
+                json!({ "verifier_url": "http://localhost/verify" })
+            ),
+            "http"
+        );
+    }
+
+    #[test]
+    fn test_twitter_tasks_use_twitter_when_configured() {
+        let http_client = reqwest::Client::new();
+        let twitter = TwitterClient::new(
+            http_client.clone(),
+            "http://localhost".to_string(),
+            "token".to_string(),
+        );
+        let verifiers = Verifiers::new(http_client, Some(twitter));
+
+        for task_type in [TaskType::Follow, TaskType::Like, TaskType::Reply] {
+            assert_eq!(
+                verifiers.for_task(&task(task_type, json!({}))).name(),
+                "twitter"
+            );
+        }
+        assert_eq!(
+            verifiers
+                .for_task(&task(TaskType::VisitLink, json!({})))
+                .name(),
+            "trust"
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("  Frog   Of\tRoggins "), "frog of roggins");
+    }
+}
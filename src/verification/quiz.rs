@@ -0,0 +1,94 @@
+use axum::async_trait;
+use serde_json::{json, Value};
+
+use crate::models::{Task, TaskProof, User};
+
+use super::{normalize, TaskVerifier, Verdict, VerificationError};
+
+/// Matches the submitted answer against `answer` or any of `answers` in the task
+/// metadata.
+pub struct QuizVerifier;
+
+#[async_trait]
+impl TaskVerifier for QuizVerifier {
+    fn name(&self) -> &'static str {
+        "quiz"
+    }
+
+    async fn verify(
+        &self,
+        task: &Task,
+        _user: &User,
+        proof: &TaskProof,
+    ) -> Result<Verdict, VerificationError> {
+        let mut accepted: Vec<&str> = task
+            .metadata
+            .get("answers")
+            .and_then(Value::as_array)
+            .map(|answers| answers.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        if let Some(answer) = task.metadata.get("answer").and_then(Value::as_str) {
+            accepted.push(answer);
+        }
+        if accepted.is_empty() {
+            return Err(VerificationError::Misconfigured {
+                error: "missing answer".to_string(),
+            });
+        }
+
+        let answer = match &proof.answer {
+            Some(answer) => answer,
+            None => return Ok(Verdict::rejected("An answer is required.")),
+        };
+        if accepted
+            .iter()
+            .any(|accepted| normalize(accepted) == normalize(answer))
+        {
+            Ok(Verdict::verified(json!({ "answer": answer })))
+        } else {
+            Ok(Verdict::rejected("Wrong answer."))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::TaskType,
+        verification::tests::{task, user},
+    };
+
+    fn proof(answer: &str) -> TaskProof {
+        TaskProof {
+            answer: Some(answer.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quiz_accepts_any_listed_answer() {
+        let task = task(
+            TaskType::Quiz,
+            json!({ "answer": "Solana", "answers": ["SOL"] }),
+        );
+        let user = user();
+
+        for answer in ["solana", "sol"] {
+            let verdict = QuizVerifier.verify(&task, &user, &proof(answer)).await;
+            assert!(verdict.unwrap().verified);
+        }
+        let verdict = QuizVerifier.verify(&task, &user, &proof("eth")).await;
+        assert!(!verdict.unwrap().verified);
+    }
+
+    #[tokio::test]
+    async fn test_quiz_without_answer_is_misconfigured() {
+        let task = task(TaskType::Quiz, json!({}));
+        let verdict = QuizVerifier.verify(&task, &user(), &proof("sol")).await;
+        assert!(matches!(
+            verdict,
+            Err(VerificationError::Misconfigured { .. })
+        ));
+    }
+}
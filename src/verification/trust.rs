@@ -0,0 +1,25 @@
+use axum::async_trait;
+use serde_json::Value;
+
+use crate::models::{Task, TaskProof, User};
+
+use super::{TaskVerifier, Verdict, VerificationError};
+
+/// Accepts every completion, for tasks nothing can be checked for yet.
+pub struct TrustVerifier;
+
+#[async_trait]
+impl TaskVerifier for TrustVerifier {
+    fn name(&self) -> &'static str {
+        "trust"
+    }
+
+    async fn verify(
+        &self,
+        _task: &Task,
+        _user: &User,
+        _proof: &TaskProof,
+    ) -> Result<Verdict, VerificationError> {
+        Ok(Verdict::verified(Value::Null))
+    }
+}
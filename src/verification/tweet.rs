@@ -0,0 +1,125 @@
+use axum::async_trait;
+use serde_json::json;
+
+use crate::models::{Task, TaskProof, User};
+
+use super::{TaskVerifier, Verdict, VerificationError};
+
+const TWEET_HOSTS: [&str; 5] = [
+    "twitter.com",
+    "www.twitter.com",
+    "mobile.twitter.com",
+    "x.com",
+    "www.x.com",
+];
+
+/// Checks that the submitted URL points to a tweet by the user, for retweets, replies
+/// and quote tweets. The user is their linked handle, or the `twitter_id` they
+/// registered with until they link one.
+pub struct TweetUrlVerifier;
+
+/// Handle and tweet id of a `https://x.com/<handle>/status/<id>` URL.
+pub fn parse_tweet_url(url: &str) -> Option<(String, String)> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let rest = rest.split(['?', '#']).next()?;
+    let mut segments = rest.trim_end_matches('/').split('/');
+
+    let host = segments.next()?;
+    if !TWEET_HOSTS.contains(&host) {
+        return None;
+    }
+
+    let handle = segments.next()?;
+    let status = segments.next()?;
+    let tweet_id = segments.next()?;
+    let valid = !handle.is_empty()
+        && status == "status"
+        && !tweet_id.is_empty()
+        && tweet_id.chars().all(|c| c.is_ascii_digit())
+        && segments.next().is_none();
+
+    valid.then(|| (handle.to_string(), tweet_id.to_string()))
+}
+
+#[async_trait]
+impl TaskVerifier for TweetUrlVerifier {
+    fn name(&self) -> &'static str {
+        "tweet_url"
+    }
+
+    async fn verify(
+        &self,
+        _task: &Task,
+        user: &User,
+        proof: &TaskProof,
+    ) -> Result<Verdict, VerificationError> {
+        let url = match &proof.url {
+            Some(url) => url,
+            None => return Ok(Verdict::rejected("A tweet URL is required.")),
+        };
+
+        let expected = user.twitter_handle.as_deref().unwrap_or(&user.twitter_id);
+        match parse_tweet_url(url) {
+            Some((handle, _)) if !handle.eq_ignore_ascii_case(expected) => {
+                Ok(Verdict::rejected("The tweet isn't yours."))
+            }
+            Some((handle, tweet_id)) => Ok(Verdict::verified(json!({
+                "handle": handle,
+                "tweet_id": tweet_id
+            }))),
+            None => Ok(Verdict::rejected("Not a tweet URL.")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::TaskType,
+        verification::tests::{task, user},
+    };
+
+    #[test]
+    fn test_parse_tweet_url() {
+        assert_eq!(
+            parse_tweet_url("https://x.com/frog/status/123?s=20"),
+            Some(("frog".to_string(), "123".to_string()))
+        );
+        assert_eq!(
+            parse_tweet_url("https://twitter.com/frog/status/456/"),
+            Some(("frog".to_string(), "456".to_string()))
+        );
+        assert_eq!(parse_tweet_url("https://x.com/frog"), None);
+        assert_eq!(parse_tweet_url("https://x.com/frog/status/abc"), None);
+        assert_eq!(parse_tweet_url("https://evil.com/frog/status/123"), None);
+        assert_eq!(parse_tweet_url("x.com/frog/status/123"), None);
+    }
+
+    async fn verify(user: &User, url: &str) -> bool {
+        let proof = TaskProof {
+            url: Some(url.to_string()),
+            ..Default::default()
+        };
+        TweetUrlVerifier
+            .verify(&task(TaskType::Retweet, json!({})), user, &proof)
+            .await
+            .unwrap()
+            .verified
+    }
+
+    #[tokio::test]
+    async fn test_tweet_must_be_by_the_user() {
+        assert!(verify(&user(), "https://x.com/Frog/status/1").await);
+        assert!(!verify(&user(), "https://x.com/toad/status/1").await);
+
+        let mut unlinked = user();
+        unlinked.twitter_user_id = None;
+        unlinked.twitter_handle = None;
+        unlinked.twitter_id = "toad".to_string();
+        assert!(verify(&unlinked, "https://x.com/toad/status/1").await);
+        assert!(!verify(&unlinked, "https://x.com/frog/status/1").await);
+    }
+}
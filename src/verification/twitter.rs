@@ -0,0 +1,212 @@
+use axum::async_trait;
+use serde_json::{json, Value};
+
+use crate::{
+    models::{Task, TaskProof, TaskType, User},
+    twitter::{TwitterClient, TwitterError},
+};
+
+use super::{parse_tweet_url, TaskVerifier, Verdict, VerificationError};
+
+/// Checks follows, likes, retweets, replies and quote tweets of the user's linked
+/// account against the X API.
+///
+/// Follow tasks need `target_user_id` or `target_username` in their metadata, the
+/// others a `tweet_id` or a tweet `link`. Replies and quote tweets are submitted as
+/// a tweet URL in the proof.
+pub struct TwitterVerifier {
+    client: TwitterClient,
+}
+
+impl TwitterVerifier {
+    pub fn new(client: TwitterClient) -> Self {
+        Self { client }
+    }
+
+    async fn target_user_id(&self, task: &Task) -> Result<String, VerificationError> {
+        if let Some(user_id) = task.metadata.get("target_user_id").and_then(Value::as_str) {
+            return Ok(user_id.to_string());
+        }
+        let username = task
+            .metadata
+            .get("target_username")
+            .and_then(Value::as_str)
+            .ok_or_else(|| misconfigured("missing target_user_id or target_username"))?;
+        self.client
+            .resolve_user_id(username)
+            .await
+            .map_err(|err| misconfigured(&err.to_string()))
+    }
+}
+
+/// Tweet the task is about, from the metadata or else the task link.
+fn target_tweet_id(task: &Task) -> Result<String, VerificationError> {
+    if let Some(tweet_id) = task.metadata.get("tweet_id").and_then(Value::as_str) {
+        return Ok(tweet_id.to_string());
+    }
+    task.link
+        .as_deref()
+        .and_then(parse_tweet_url)
+        .map(|(_, tweet_id)| tweet_id)
+        .ok_or_else(|| misconfigured("missing tweet_id"))
+}
+
+fn misconfigured(error: &str) -> VerificationError {
+    VerificationError::Misconfigured {
+        error: error.to_string(),
+    }
+}
+
+fn unavailable(err: TwitterError) -> VerificationError {
+    VerificationError::Unavailable {
+        error: err.to_string(),
+    }
+}
+
+#[async_trait]
+impl TaskVerifier for TwitterVerifier {
+    fn name(&self) -> &'static str {
+        "twitter"
+    }
+
+    async fn verify(
+        &self,
+        task: &Task,
+        user: &User,
+        proof: &TaskProof,
+    ) -> Result<Verdict, VerificationError> {
+        // The registered twitter_id is free-form, only a linked account proves who
+        // the user is on X.
+        let user_id = match &user.twitter_user_id {
+            Some(user_id) => user_id.clone(),
+            None => return Ok(Verdict::rejected("Link your Twitter account first.")),
+        };
+
+        match task.task_type {
+            TaskType::Follow => {
+                let target_user_id = self.target_user_id(task).await?;
+                let following = self
+                    .client
+                    .is_following(&user_id, &target_user_id)
+                    .await
+                    .map_err(unavailable)?;
+                Ok(match following {
+                    true => Verdict::verified(json!({ "target_user_id": target_user_id })),
+                    false => Verdict::rejected("Account is not followed yet."),
+                })
+            }
+            TaskType::Like | TaskType::Retweet => {
+                let tweet_id = target_tweet_id(task)?;
+                let done = if task.task_type == TaskType::Like {
+                    self.client.has_liked(&user_id, &tweet_id).await
+                } else {
+                    self.client.has_retweeted(&user_id, &tweet_id).await
+                }
+                .map_err(unavailable)?;
+                Ok(match done {
+                    true => Verdict::verified(json!({ "tweet_id": tweet_id })),
+                    false => Verdict::rejected("Tweet not liked or retweeted yet."),
+                })
+            }
+            TaskType::Reply | TaskType::QuoteTweet => {
+                let target_tweet_id = target_tweet_id(task)?;
+                let tweet_id = match proof.url.as_deref().and_then(parse_tweet_url) {
+                    Some((_, tweet_id)) => tweet_id,
+                    None => return Ok(Verdict::rejected("A tweet URL is required.")),
+                };
+                let tweet = match self.client.get_tweet(&tweet_id).await {
+                    Ok(tweet) => tweet,
+                    Err(TwitterError::NotFound) => {
+                        return Ok(Verdict::rejected("Tweet not found."))
+                    }
+                    Err(err) => return Err(unavailable(err)),
+                };
+
+                let kind = if task.task_type == TaskType::Reply {
+                    "replied_to"
+                } else {
+                    "quoted"
+                };
+                if tweet.author_id.as_deref() != Some(user_id.as_str()) {
+                    return Ok(Verdict::rejected("Tweet was posted by someone else."));
+                }
+                if !tweet.references(kind, &target_tweet_id) {
+                    return Ok(Verdict::rejected("Tweet doesn't reference the task tweet."));
+                }
+                Ok(Verdict::verified(json!({ "tweet_id": tweet_id })))
+            }
+            _ => Err(misconfigured("task type can't be checked on Twitter")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        twitter::tests::stub_client,
+        verification::tests::{task, user},
+    };
+
+    async fn verify(task: Task, proof_url: Option<&str>) -> Result<Verdict, VerificationError> {
+        let (client, _) = stub_client().await;
+        let proof = TaskProof {
+            url: proof_url.map(str::to_string),
+            ..Default::default()
+        };
+        TwitterVerifier::new(client)
+            .verify(&task, &user(), &proof)
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_unlinked_account_is_rejected() {
+        let (client, _) = stub_client().await;
+        let mut user = user();
+        user.twitter_user_id = None;
+        let follow = task(TaskType::Follow, json!({ "target_user_id": "2" }));
+        let verdict = TwitterVerifier::new(client)
+            .verify(&follow, &user, &TaskProof::default())
+            .await;
+        assert!(!verdict.unwrap().verified);
+    }
+
+    #[tokio::test]
+    async fn test_follow() {
+        let follow = task(TaskType::Follow, json!({ "target_user_id": "2" }));
+        assert!(verify(follow, None).await.unwrap().verified);
+
+        let follow = task(TaskType::Follow, json!({ "target_user_id": "4" }));
+        assert!(!verify(follow, None).await.unwrap().verified);
+    }
+
+    #[tokio::test]
+    async fn test_retweet_uses_task_link() {
+        let mut retweet = task(TaskType::Retweet, json!({}));
+        retweet.link = Some("https://x.com/frogofroggins/status/10".to_string());
+        assert!(verify(retweet, None).await.unwrap().verified);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_like_is_unavailable() {
+        let like = task(TaskType::Like, json!({ "tweet_id": "10" }));
+        assert!(matches!(
+            verify(like, None).await,
+            Err(VerificationError::Unavailable { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_reply_must_reference_task_tweet() {
+        let reply = task(TaskType::Reply, json!({ "tweet_id": "10" }));
+        let url = Some("https://x.com/frog/status/11");
+        assert!(verify(reply, url).await.unwrap().verified);
+
+        let quote = task(TaskType::QuoteTweet, json!({ "tweet_id": "10" }));
+        assert!(!verify(quote, url).await.unwrap().verified);
+
+        let reply = task(TaskType::Reply, json!({ "tweet_id": "10" }));
+        let verdict = verify(reply, Some("https://x.com/frog/status/12")).await;
+        assert!(!verdict.unwrap().verified);
+    }
+}
@@ -0,0 +1,245 @@
+use std::fmt;
+
+use ed25519_dalek::{Signature as Ed25519Signature, VerifyingKey as Ed25519Key};
+use k256::ecdsa::{RecoveryId, Signature as EcdsaSignature, VerifyingKey as EcdsaKey};
+use sha3::{Digest, Keccak256};
+
+use crate::models::{EvmAddress, SolanaAddress, WalletChain};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalletError {
+    InvalidAddress(String),
+    /// The signature isn't in the chain's encoding.
+    MalformedSignature,
+    /// A well formed signature that wasn't made by the address.
+    InvalidSignature,
+}
+
+impl fmt::Display for WalletError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalletError::InvalidAddress(error) => write!(f, "{error}"),
+            WalletError::MalformedSignature => write!(f, "Malformed signature"),
+            WalletError::InvalidSignature => write!(f, "Signature doesn't match the address"),
+        }
+    }
+}
+
+impl std::error::Error for WalletError {}
+
+/// Checks an address for the chain and returns its canonical form, which is what
+/// gets stored and compared.
+pub fn normalize_address(chain: WalletChain, address: &str) -> Result<String, WalletError> {
+    let address = if chain.is_evm() {
+        EvmAddress::parse(address)
+            .map(String::from)
+            .map_err(|err| err.to_string())
+    } else {
+        SolanaAddress::parse(address)
+            .map(String::from)
+            .map_err(|err| err.to_string())
+    };
+    address.map_err(WalletError::InvalidAddress)
+}
+
+/// What the wallet signs to prove it belongs to the account. The nonce comes from a
+/// challenge so a signature can't be replayed.
+pub fn link_message(user_id: i32, chain: WalletChain, address: &str, nonce: &str) -> String {
+    format!(
+        "Link this {chain} wallet to points farmer account {user_id}.\n\nAddress: {address}\nNonce: {nonce}"
+    )
+}
+
+/// Solana signatures are the 64 ed25519 bytes in base58, EVM ones the 65 byte
+/// `personal_sign` (EIP-191) result in hex.
+pub fn verify_signature(
+    chain: WalletChain,
+    address: &str,
+    message: &str,
+    signature: &str,
+) -> Result<(), WalletError> {
+    if chain.is_evm() {
+        verify_evm_signature(address, message, signature)
+    } else {
+        verify_solana_signature(address, message, signature)
+    }
+}
+
+fn verify_solana_signature(
+    address: &str,
+    message: &str,
+    signature: &str,
+) -> Result<(), WalletError> {
+    let address = SolanaAddress::parse(address)
+        .map_err(|err| WalletError::InvalidAddress(err.to_string()))?;
+    let signature: [u8; 64] = bs58::decode(signature.trim())
+        .into_vec()
+        .map_err(|_| WalletError::MalformedSignature)?
+        .try_into()
+        .map_err(|_| WalletError::MalformedSignature)?;
+
+    // Off curve addresses have no key and can't sign anything.
+    let key =
+        Ed25519Key::from_bytes(&address.to_bytes()).map_err(|_| WalletError::InvalidSignature)?;
+    key.verify_strict(
+        message.as_bytes(),
+        &Ed25519Signature::from_bytes(&signature),
+    )
+    .map_err(|_| WalletError::InvalidSignature)
+}
+
+fn verify_evm_signature(address: &str, message: &str, signature: &str) -> Result<(), WalletError> {
+    let address =
+        EvmAddress::parse(address).map_err(|err| WalletError::InvalidAddress(err.to_string()))?;
+    let signature = signature.trim();
+    let bytes: [u8; 65] = hex::decode(signature.strip_prefix("0x").unwrap_or(signature))
+        .map_err(|_| WalletError::MalformedSignature)?
+        .try_into()
+        .map_err(|_| WalletError::MalformedSignature)?;
+
+    // Wallets send v as 27/28, some hardware wallets as 0/1.
+    let v = match bytes[64] {
+        27 | 28 => bytes[64] - 27,
+        0 | 1 => bytes[64],
+        _ => return Err(WalletError::MalformedSignature),
+    };
+    let mut signature =
+        EcdsaSignature::from_slice(&bytes[..64]).map_err(|_| WalletError::MalformedSignature)?;
+    let mut recovery_id = RecoveryId::new(v == 1, false);
+    if let Some(normalized) = signature.normalize_s() {
+        signature = normalized;
+        recovery_id = RecoveryId::new(!recovery_id.is_y_odd(), false);
+    }
+
+    let key = EcdsaKey::recover_from_prehash(&eip191_hash(message), &signature, recovery_id)
+        .map_err(|_| WalletError::InvalidSignature)?;
+    if evm_address_of(&key) != address {
+        return Err(WalletError::InvalidSignature);
+    }
+    Ok(())
+}
+
+fn eip191_hash(message: &str) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(format!("\x19Ethereum Signed Message:\n{}", message.len()));
+    hasher.update(message);
+    hasher.finalize().into()
+}
+
+/// The last 20 bytes of the Keccak hash of the uncompressed public key.
+fn evm_address_of(key: &EcdsaKey) -> EvmAddress {
+    let point = key.to_encoded_point(false);
+    let hash = Keccak256::digest(&point.as_bytes()[1..]);
+    let mut address = [0; 20];
+    address.copy_from_slice(&hash[12..]);
+    EvmAddress::from_bytes(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Signer, SigningKey as Ed25519SigningKey};
+    use k256::ecdsa::SigningKey as EcdsaSigningKey;
+
+    use super::*;
+
+    fn evm_sign(key: &EcdsaSigningKey, message: &str) -> String {
+        let (signature, recovery_id) = key.sign_prehash_recoverable(&eip191_hash(message)).unwrap();
+        let mut bytes = signature.to_bytes().to_vec();
+        bytes.push(27 + recovery_id.to_byte());
+        format!("0x{}", hex::encode(bytes))
+    }
+
+    #[test]
+    fn test_normalize_address() {
+        assert_eq!(
+            normalize_address(
+                WalletChain::Base,
+                "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"
+            ),
+            Ok("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string())
+        );
+        assert!(normalize_address(
+            WalletChain::Solana,
+            "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"
+        )
+        .is_err());
+        assert!(normalize_address(
+            WalletChain::Ethereum,
+            "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_evm_address_of_key() {
+        let mut secret = [0; 32];
+        secret[31] = 1;
+        let key = EcdsaSigningKey::from_slice(&secret).unwrap();
+        assert_eq!(
+            evm_address_of(key.verifying_key()).as_str(),
+            "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf"
+        );
+    }
+
+    #[test]
+    fn test_verify_evm_signature() {
+        let key = EcdsaSigningKey::from_slice(&[7; 32]).unwrap();
+        let address = evm_address_of(key.verifying_key()).to_string();
+        let message = link_message(1, WalletChain::Ethereum, &address, "nonce");
+        let signature = evm_sign(&key, &message);
+
+        assert_eq!(
+            verify_signature(WalletChain::Ethereum, &address, &message, &signature),
+            Ok(())
+        );
+        assert_eq!(
+            verify_signature(
+                WalletChain::Ethereum,
+                &address,
+                &message.replace("nonce", "other"),
+                &signature
+            ),
+            Err(WalletError::InvalidSignature)
+        );
+        let other = EcdsaSigningKey::from_slice(&[8; 32]).unwrap();
+        assert_eq!(
+            verify_signature(
+                WalletChain::Ethereum,
+                &address,
+                &message,
+                &evm_sign(&other, &message)
+            ),
+            Err(WalletError::InvalidSignature)
+        );
+        assert_eq!(
+            verify_signature(WalletChain::Ethereum, &address, &message, "0x1234"),
+            Err(WalletError::MalformedSignature)
+        );
+    }
+
+    #[test]
+    fn test_verify_solana_signature() {
+        let key = Ed25519SigningKey::from_bytes(&[7; 32]);
+        let address = SolanaAddress::from_bytes(key.verifying_key().to_bytes()).to_string();
+        let message = link_message(1, WalletChain::Solana, &address, "nonce");
+        let signature = bs58::encode(key.sign(message.as_bytes()).to_bytes()).into_string();
+
+        assert_eq!(
+            verify_signature(WalletChain::Solana, &address, &message, &signature),
+            Ok(())
+        );
+        assert_eq!(
+            verify_signature(
+                WalletChain::Solana,
+                &address,
+                &message.replace("nonce", "other"),
+                &signature
+            ),
+            Err(WalletError::InvalidSignature)
+        );
+        assert_eq!(
+            verify_signature(WalletChain::Solana, &address, &message, "frog"),
+            Err(WalletError::MalformedSignature)
+        );
+    }
+}